@@ -0,0 +1,227 @@
+use raster::Bitmap;
+
+/// Empty pixels left between bitmaps, so that filtering does not bleed
+/// one glyph into another.
+const GUTTER: usize = 1;
+
+/// A rectangle of texture coordinates, from 0 to 1 across the page.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UvRect {
+    pub u_min: f32,
+    pub v_min: f32,
+    pub u_max: f32,
+    pub v_max: f32,
+}
+
+/// Where a bitmap was placed in the atlas.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AtlasRect {
+    pub page: usize,
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub uv: UvRect,
+}
+
+/// A span of the skyline, the lowest free row above `width` columns.
+#[derive(Debug, Copy, Clone)]
+struct Span {
+    x: usize,
+    y: usize,
+    width: usize,
+}
+
+/// A texture filled from the top, keeping the outline of the used area.
+#[derive(Debug, Clone)]
+pub struct Page {
+    skyline: Vec<Span>,
+    /// Coverage values, in rows from the top.
+    pub data: Vec<u8>,
+}
+
+impl Page {
+    fn new(width: usize, height: usize) -> Page {
+        Page { skyline: vec![Span { x: 0, y: 0, width }], data: vec![0; width * height] }
+    }
+
+    /// Finds the lowest position the rectangle fits at, preferring the
+    /// left, and raises the skyline over it.
+    fn allocate(&mut self, width: usize, height: usize, page_width: usize, page_height: usize)
+        -> Option<(usize, usize)>
+    {
+        let mut best: Option<(usize, usize, usize)> = None;
+        for (i, span) in self.skyline.iter().enumerate() {
+            if span.x + width > page_width {
+                break
+            }
+
+            let mut y = 0;
+            let mut covered = 0;
+            for next in &self.skyline[i..] {
+                y = y.max(next.y);
+                covered += next.width;
+                if covered >= width {
+                    break
+                }
+            }
+
+            if y + height <= page_height && best.is_none_or(|(_, best_y, _)| y < best_y) {
+                best = Some((i, y, span.x));
+            }
+        }
+
+        let (index, y, x) = try_opt!(best);
+        self.skyline.insert(index, Span { x, y: y + height, width });
+
+        // Shrink or remove the spans now under the new one.
+        let right = x + width;
+        let i = index + 1;
+        while i < self.skyline.len() && self.skyline[i].x < right {
+            let span = self.skyline[i];
+            if span.x + span.width <= right {
+                self.skyline.remove(i);
+            } else {
+                self.skyline[i] = Span { x: right, y: span.y, width: span.x + span.width - right };
+                break
+            }
+        }
+
+        // Merge neighbours at the same height.
+        let mut i = 0;
+        while i + 1 < self.skyline.len() {
+            if self.skyline[i].y == self.skyline[i + 1].y {
+                self.skyline[i].width += self.skyline[i + 1].width;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+
+        Some((x, y))
+    }
+}
+
+/// Packs bitmaps into fixed size pages with a skyline packer.  Space is
+/// only reclaimed by clearing the atlas, after which the bitmaps still
+/// in use are added again.
+#[derive(Debug, Clone)]
+pub struct Atlas {
+    width: usize,
+    height: usize,
+    max_pages: usize,
+    pages: Vec<Page>,
+}
+
+impl Atlas {
+    pub fn new(width: usize, height: usize, max_pages: usize) -> Atlas {
+        Atlas { width, height, max_pages, pages: Vec::new() }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+
+    /// Whether a bitmap of the given size fits on an empty page.
+    pub fn can_hold(&self, width: usize, height: usize) -> bool {
+        width + GUTTER <= self.width && height + GUTTER <= self.height
+    }
+
+    /// Copies a bitmap into the first page with room for it, adding a
+    /// page if needed.  Returns None once every page is full.
+    pub fn add(&mut self, bitmap: &Bitmap) -> Option<AtlasRect> {
+        if !self.can_hold(bitmap.width, bitmap.height) {
+            return None
+        }
+
+        let (width, height) = (bitmap.width + GUTTER, bitmap.height + GUTTER);
+        let (atlas_width, atlas_height) = (self.width, self.height);
+        let mut found = None;
+        for (page, data) in self.pages.iter_mut().enumerate() {
+            if let Some((x, y)) = data.allocate(width, height, atlas_width, atlas_height) {
+                found = Some((page, x, y));
+                break
+            }
+        }
+
+        if found.is_none() && self.pages.len() < self.max_pages {
+            let mut page = Page::new(atlas_width, atlas_height);
+            let (x, y) = try_opt!(page.allocate(width, height, atlas_width, atlas_height));
+            self.pages.push(page);
+            found = Some((self.pages.len() - 1, x, y));
+        }
+
+        let (page, x, y) = try_opt!(found);
+        let data = &mut self.pages[page].data;
+        for row in 0..bitmap.height {
+            let start = (y + row) * atlas_width + x;
+            data[start..start + bitmap.width]
+                .copy_from_slice(&bitmap.coverage[row * bitmap.width..(row + 1) * bitmap.width]);
+        }
+
+        Some(AtlasRect {
+            page,
+            x,
+            y,
+            width: bitmap.width,
+            height: bitmap.height,
+            uv: UvRect {
+                u_min: x as f32 / atlas_width as f32,
+                v_min: y as f32 / atlas_height as f32,
+                u_max: (x + bitmap.width) as f32 / atlas_width as f32,
+                v_max: (y + bitmap.height) as f32 / atlas_height as f32,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use atlas::Atlas;
+    use raster::Bitmap;
+
+    fn bitmap(width: usize, height: usize, value: u8) -> Bitmap {
+        Bitmap { width, height, left: 0, top: 0, coverage: vec![value; width * height] }
+    }
+
+    #[test]
+    fn packing() {
+        let mut atlas = Atlas::new(16, 16, 2);
+
+        // Bitmaps fill rows from the top left, with a gutter between them.
+        let a = atlas.add(&bitmap(7, 4, 1)).expect("unable to add bitmap");
+        let b = atlas.add(&bitmap(7, 6, 2)).expect("unable to add bitmap");
+        let c = atlas.add(&bitmap(4, 4, 3)).expect("unable to add bitmap");
+        assert_eq!((a.page, a.x, a.y), (0, 0, 0));
+        assert_eq!((b.page, b.x, b.y), (0, 8, 0));
+        assert_eq!((c.page, c.x, c.y), (0, 0, 5));
+        assert_eq!((b.uv.u_min, b.uv.v_min, b.uv.u_max, b.uv.v_max), (0.5, 0.0, 0.9375, 0.375));
+
+        let page = &atlas.pages()[0].data;
+        assert_eq!(&page[..16], &[1, 1, 1, 1, 1, 1, 1, 0, 2, 2, 2, 2, 2, 2, 2, 0]);
+        assert_eq!(page[5 * 16 + 3], 3);
+        assert_eq!(page[5 * 16 + 4], 0);
+
+        // A bitmap too tall for the first page starts a second one.
+        let d = atlas.add(&bitmap(15, 12, 4)).expect("unable to add bitmap");
+        assert_eq!((d.page, d.x, d.y), (1, 0, 0));
+        assert!(atlas.add(&bitmap(15, 12, 5)).is_none());
+        assert!(atlas.add(&bitmap(16, 1, 5)).is_none());
+
+        atlas.clear();
+        assert!(atlas.pages().is_empty());
+        assert_eq!(atlas.add(&bitmap(15, 12, 5)).map(|r| (r.page, r.x, r.y)), Some((0, 0, 0)));
+    }
+}
@@ -0,0 +1,108 @@
+/// A glyph and the index of the character cluster it originated from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GlyphInfo {
+    pub glyph_id: u16,
+    pub cluster: u32,
+    /// Identifies the ligature a glyph forms or was skipped over by, or 0
+    /// when it is not part of a ligature.
+    pub lig_id: u8,
+    /// For a mark within a ligature, the component it belongs to,
+    /// counting from 1.  This is 0 for the ligature itself.
+    pub lig_component: u8,
+    /// The number of components of a ligature, or 1 for other glyphs.
+    pub lig_components: u8,
+}
+
+/// The placement of a glyph, in font units.  Offsets move the glyph
+/// without affecting the pen position, while advances move the pen.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct GlyphPosition {
+    pub x_advance: i32,
+    pub y_advance: i32,
+    pub x_offset: i32,
+    pub y_offset: i32,
+    /// The glyph a mark is attached to, whose offset the mark's offset is
+    /// relative to until attachments are resolved.
+    pub attached_to: Option<usize>,
+}
+
+/// A run of glyphs along with their positions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GlyphBuffer {
+    pub glyphs: Vec<GlyphInfo>,
+    pub positions: Vec<GlyphPosition>,
+    last_lig_id: u8,
+}
+
+impl GlyphBuffer {
+    pub fn new() -> GlyphBuffer {
+        GlyphBuffer::default()
+    }
+
+    pub fn push(&mut self, glyph_id: u16, cluster: u32) {
+        self.glyphs.push(GlyphInfo {
+            glyph_id,
+            cluster,
+            lig_id: 0,
+            lig_component: 0,
+            lig_components: 1,
+        });
+        self.positions.push(GlyphPosition::default());
+    }
+
+    /// Inserts a glyph before `index`, with an empty position.
+    pub fn insert(&mut self, index: usize, glyph: GlyphInfo) {
+        self.glyphs.insert(index, glyph);
+        self.positions.insert(index, GlyphPosition::default());
+    }
+
+    pub fn remove(&mut self, index: usize) -> GlyphInfo {
+        self.positions.remove(index);
+        self.glyphs.remove(index)
+    }
+
+    /// Gives the glyphs in `start..end` the lowest of their clusters.
+    pub fn merge_clusters(&mut self, start: usize, end: usize) {
+        let glyphs = &mut self.glyphs[start..end];
+        if let Some(cluster) = glyphs.iter().map(|g| g.cluster).min() {
+            for glyph in glyphs {
+                glyph.cluster = cluster;
+            }
+        }
+    }
+
+    /// Makes the offsets of attached marks relative to their own origin,
+    /// so that marks follow the glyphs they are attached to.
+    pub fn resolve_attachments(&mut self) {
+        for i in 0..self.positions.len() {
+            let base = match self.positions[i].attached_to.take() {
+                Some(base) if base < i => base,
+                _ => continue,
+            };
+
+            // Bases come first, so their offsets are already resolved.
+            let (x_offset, y_offset) = (self.positions[base].x_offset, self.positions[base].y_offset);
+            let (x_advance, y_advance) = self.positions[base..i].iter()
+                .fold((0, 0), |(x, y), p| (x + p.x_advance, y + p.y_advance));
+
+            let position = &mut self.positions[i];
+            position.x_offset += x_offset - x_advance;
+            position.y_offset += y_offset - y_advance;
+        }
+    }
+
+    /// A new ligature id, which is never 0.  Ids wrap around, so only
+    /// nearby glyphs should be compared.
+    pub fn next_lig_id(&mut self) -> u8 {
+        self.last_lig_id = self.last_lig_id.checked_add(1).unwrap_or(1);
+        self.last_lig_id
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+}
@@ -0,0 +1,323 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use font::Font;
+use metrics::Size;
+use raster::Bitmap;
+use atlas::{Atlas, AtlasRect};
+
+/// Number of subpixel positions a pixel is divided into on each axis.
+pub const SUBPIXEL_STEPS: u8 = 4;
+
+/// Identifies a rasterized glyph.  Sizes, offsets and variation
+/// coordinates are quantized, so that nearby requests share a bitmap.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GlyphKey {
+    /// Chosen by the caller to tell fonts apart.
+    pub font_id: u32,
+    pub glyph_id: u16,
+    /// Pixels per em, in 1/64 pixels.
+    pub size: u32,
+    /// The offset from the pixel grid, in steps of `1 / SUBPIXEL_STEPS`.
+    pub subpixel: (u8, u8),
+    /// Normalized variation coordinates in 2.14.
+    pub variation: Vec<i16>,
+}
+
+impl GlyphKey {
+    /// Only the fractional part of `offset` is kept, as whole pixels
+    /// move the bitmap without changing it.  These are returned with the
+    /// key, and include a pixel carried when the fraction rounds up.
+    pub fn new(font_id: u32, glyph_id: u16, size: Size, offset: (f32, f32),
+               coords: &[f32]) -> (GlyphKey, (i32, i32)) {
+        let split = |offset: f32| {
+            let steps = (offset * SUBPIXEL_STEPS as f32).round() as i32;
+            let pixels = steps.div_euclid(SUBPIXEL_STEPS as i32);
+            (pixels, (steps - pixels * SUBPIXEL_STEPS as i32) as u8)
+        };
+        let (x, y) = (split(offset.0), split(offset.1));
+
+        let key = GlyphKey {
+            font_id,
+            glyph_id,
+            size: (size.ppem() * 64.0).round() as u32,
+            subpixel: (x.1, y.1),
+            variation: coords.iter().map(|c| (c.clamp(-1.0, 1.0) * 16384.0).round() as i16).collect(),
+        };
+        (key, (x.0, y.0))
+    }
+
+    pub fn ppem(&self) -> f32 {
+        self.size as f32 / 64.0
+    }
+
+    pub fn offset(&self) -> (f32, f32) {
+        (self.subpixel.0 as f32 / SUBPIXEL_STEPS as f32,
+         self.subpixel.1 as f32 / SUBPIXEL_STEPS as f32)
+    }
+
+    pub fn coords(&self) -> Vec<f32> {
+        self.variation.iter().map(|&c| c as f32 / 16384.0).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedGlyph {
+    /// Kept so that the atlas can be repacked without rasterizing again.
+    pub bitmap: Bitmap,
+    /// None for glyphs without any pixels.
+    pub location: Option<AtlasRect>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    glyph: CachedGlyph,
+    last_used: u64,
+}
+
+/// Rasterized glyphs packed into an atlas, dropping the least recently
+/// used glyphs once the budget or the atlas is full.
+///
+/// Repacking the atlas moves glyphs, so renderers should compare
+/// `generation` with the value seen when they last uploaded the pages,
+/// and look glyphs up again after a change.
+#[derive(Debug, Clone)]
+pub struct GlyphCache {
+    atlas: Atlas,
+    entries: HashMap<GlyphKey, Entry>,
+    /// Keys by the tick they were last used at, oldest first.
+    lru: BTreeMap<u64, GlyphKey>,
+    tick: u64,
+    budget: usize,
+    generation: u64,
+}
+
+impl GlyphCache {
+    /// Keeps at most `budget` glyphs, in up to `max_pages` pages.
+    pub fn new(page_width: usize, page_height: usize, max_pages: usize, budget: usize) -> GlyphCache {
+        GlyphCache {
+            atlas: Atlas::new(page_width, page_height, max_pages),
+            entries: HashMap::new(),
+            lru: BTreeMap::new(),
+            tick: 0,
+            budget,
+            generation: 0,
+        }
+    }
+
+    pub fn atlas(&self) -> &Atlas {
+        &self.atlas
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Incremented each time the atlas is repacked.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn contains(&self, key: &GlyphKey) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Looks up a glyph, marking it as recently used.
+    pub fn get(&mut self, key: &GlyphKey) -> Option<&CachedGlyph> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = try_opt!(self.entries.get_mut(key));
+        self.lru.remove(&entry.last_used);
+        self.lru.insert(tick, key.clone());
+        entry.last_used = tick;
+        Some(&entry.glyph)
+    }
+
+    /// Looks up a glyph, rasterizing it on a miss.
+    pub fn get_or_rasterize(&mut self, font: &Font, key: &GlyphKey) -> Option<&CachedGlyph> {
+        if !self.contains(key) {
+            let bitmap = try_opt!(font.rasterize_glyph_instance(key.glyph_id, &key.coords(),
+                                                                Size::Pixels(key.ppem()), key.offset()));
+            return self.insert(key.clone(), bitmap)
+        }
+
+        self.get(key)
+    }
+
+    /// Adds a glyph, evicting old glyphs to make room for it.  Returns
+    /// None if the bitmap is larger than a page.
+    pub fn insert(&mut self, key: GlyphKey, bitmap: Bitmap) -> Option<&CachedGlyph> {
+        if !self.atlas.can_hold(bitmap.width, bitmap.height) {
+            return None
+        }
+
+        if let Some(entry) = self.entries.remove(&key) {
+            self.lru.remove(&entry.last_used);
+        }
+
+        while self.entries.len() >= self.budget.max(1) {
+            self.evict_oldest();
+        }
+
+        let location = if bitmap.width == 0 || bitmap.height == 0 {
+            None
+        } else {
+            Some(try_opt!(self.allocate(&bitmap)))
+        };
+
+        self.tick += 1;
+        self.lru.insert(self.tick, key.clone());
+        let entry = Entry { glyph: CachedGlyph { bitmap, location }, last_used: self.tick };
+        Some(&self.entries.entry(key).or_insert(entry).glyph)
+    }
+
+    fn evict_oldest(&mut self) {
+        let tick = match self.lru.keys().next() {
+            Some(&tick) => tick,
+            None => return,
+        };
+
+        if let Some(key) = self.lru.remove(&tick) {
+            self.entries.remove(&key);
+        }
+    }
+
+    /// Places a bitmap in the atlas.  When it is full, the oldest quarter
+    /// of the glyphs is evicted and the rest are packed again, until the
+    /// bitmap fits or nothing is left to evict.
+    fn allocate(&mut self, bitmap: &Bitmap) -> Option<AtlasRect> {
+        loop {
+            if let Some(rect) = self.atlas.add(bitmap) {
+                return Some(rect)
+            }
+
+            if self.entries.is_empty() {
+                return None
+            }
+
+            let count = self.entries.len().div_ceil(4);
+            for _ in 0..count {
+                self.evict_oldest();
+            }
+            self.repack();
+        }
+    }
+
+    /// Clears the atlas and adds back every cached glyph, tallest first
+    /// for a tighter packing.
+    fn repack(&mut self) {
+        self.atlas.clear();
+        self.generation += 1;
+
+        let mut keys: Vec<GlyphKey> = self.entries.iter()
+            .filter(|&(_, entry)| entry.glyph.location.is_some())
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort_by_key(|key| Reverse(self.entries[key].glyph.bitmap.height));
+
+        for key in keys {
+            let location = self.atlas.add(&self.entries[&key].glyph.bitmap);
+            match location {
+                Some(location) => self.entries.get_mut(&key).unwrap().glyph.location = Some(location),
+                None => {
+                    let tick = self.entries[&key].last_used;
+                    self.lru.remove(&tick);
+                    self.entries.remove(&key);
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use metrics::Size;
+    use raster::Bitmap;
+    use cache::{GlyphCache, GlyphKey};
+
+    fn bitmap(width: usize, height: usize) -> Bitmap {
+        Bitmap { width, height, left: 0, top: 0, coverage: vec![255; width * height] }
+    }
+
+    fn key(glyph_id: u16) -> GlyphKey {
+        GlyphKey::new(0, glyph_id, Size::Pixels(16.0), (0.0, 0.0), &[]).0
+    }
+
+    #[test]
+    fn keys() {
+        let (key, pixels) = GlyphKey::new(1, 36, Size::Points { size: 12.0, dpi: 96.0 }, (10.3, -0.9), &[0.5, -2.0]);
+        assert_eq!(pixels, (10, -1));
+        assert_eq!(key.size, 16 * 64);
+        assert_eq!(key.subpixel, (1, 0));
+        assert_eq!(key.variation, vec![8192, -16384]);
+        assert_eq!(key.offset(), (0.25, 0.0));
+        assert_eq!(key.coords(), vec![0.5, -1.0]);
+
+        // Offsets rounding up to the next pixel share the aligned bitmap,
+        // drawn one pixel further on.
+        let (key, pixels) = GlyphKey::new(0, 36, Size::Pixels(12.0), (0.9, -2.1), &[]);
+        assert_eq!(key.subpixel, (0, 0));
+        assert_eq!(pixels, (1, -2));
+        assert_eq!(GlyphKey::new(0, 36, Size::Pixels(12.0), (0.6, 0.0), &[]), (GlyphKey { subpixel: (2, 0), ..key }, (0, 0)));
+    }
+
+    #[test]
+    fn eviction() {
+        // Budget of three glyphs.
+        let mut cache = GlyphCache::new(16, 16, 1, 3);
+        for glyph_id in 0..3 {
+            assert!(cache.insert(key(glyph_id), bitmap(4, 4)).is_some());
+        }
+
+        assert!(cache.get(&key(0)).is_some());
+        cache.insert(key(3), bitmap(4, 4));
+        assert_eq!(cache.len(), 3);
+        assert!(!cache.contains(&key(1)));
+        assert!(cache.contains(&key(0)));
+
+        // The page fits three rows of three glyphs.  Filling it evicts
+        // the oldest glyphs and packs the rest from the top left again.
+        let mut cache = GlyphCache::new(16, 16, 1, 100);
+        for glyph_id in 0..9 {
+            cache.insert(key(glyph_id), bitmap(4, 4)).expect("unable to cache glyph");
+        }
+        assert_eq!(cache.generation(), 0);
+        cache.get(&key(0));
+
+        let location = cache.insert(key(9), bitmap(4, 4)).and_then(|g| g.location).unwrap();
+        assert_eq!(cache.generation(), 1);
+        assert_eq!(cache.len(), 7);
+        assert!(cache.contains(&key(0)));
+        assert!(!cache.contains(&key(1)) && !cache.contains(&key(2)) && !cache.contains(&key(3)));
+        assert_eq!((location.x, location.y), (0, 10));
+
+        // Bitmaps larger than a page are never cached.
+        assert!(cache.insert(key(10), bitmap(20, 4)).is_none());
+        assert_eq!(cache.len(), 7);
+    }
+
+    #[test]
+    fn rasterize() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let mut cache = GlyphCache::new(64, 64, 1, 16);
+
+        // 'A' and ' '
+        let a = cache.get_or_rasterize(&font, &key(36)).cloned().expect("unable to cache glyph");
+        let expected = font.rasterize_glyph(36, Size::Pixels(16.0), (0.0, 0.0)).unwrap();
+        assert_eq!(a.bitmap, expected);
+
+        let location = a.location.unwrap();
+        let page = &cache.atlas().pages()[location.page].data;
+        assert_eq!(&page[location.y * 64 + location.x..][..location.width], &expected.coverage[..expected.width]);
+
+        let space = cache.get_or_rasterize(&font, &key(3)).cloned().expect("unable to cache glyph");
+        assert!(space.location.is_none());
+        assert_eq!(cache.get_or_rasterize(&font, &key(36)), Some(&a));
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -6,6 +6,37 @@ use table::name::Name;
 use table::hmtx::Hmtx;
 use table::maxp::Maxp;
 use table::hhea::Hhea;
+use table::vhea::Vhea;
+use table::vmtx::{Vmtx, SyntheticVmtx};
+use table::vorg::Vorg;
+use table::os2::Os2;
+use table::head::{self, Head};
+use table::loca::Loca;
+use table::glyf::{self, GlyphHeader, Glyph, Outline};
+use table::eblc::BitmapLocation;
+use table::os2::{self, USE_TYPO_METRICS};
+use table::kern::Kern;
+use table::hdmx::Hdmx;
+use table::ltsh::Ltsh;
+use table::gasp::{self, Gasp};
+use table::vdmx::Vdmx;
+use table::cmap::CmapHeader;
+use table::gdef;
+use table::gpos;
+use table::gsub;
+use table::layout::{FeatureInfo, FeatureParams, LanguageSystem, LayoutTable};
+use table::cff::Cff;
+use table::cff::cff2::Cff2;
+use buffer::{GlyphBuffer, GlyphPosition};
+use outline::{BoundsBuilder, OutlineBuilder, PathRecorder, Transform, TransformBuilder};
+use raster::{Bitmap, Rasterizer};
+use geometry::{Contour, FlattenBuilder, Mesh};
+use pathops;
+use sdf::{DistanceField, DistanceFieldBuilder, FieldOptions};
+use hinting::{Hinter, HintingMode, RenderingFlags};
+use svg::{SvgDocument, SvgOptions, SvgPathBuilder, ViewBox};
+use metrics::{self, Size, BoundingBox, GlyphMetrics, FontMetrics, Style};
+use synthesis::{self, Synthesis, DEFAULT_SKEW};
 
 #[derive(Debug)]
 pub enum Version {
@@ -101,6 +132,16 @@ impl<'f> Font<'f> {
         }
     }
 
+    /// Returns the raw bytes of a table, bounded by the length
+    /// given in its table record.
+    pub fn get_table_data(&self, tag: Tag) -> Option<&'f [u8]> {
+        let record = try_opt!(self.get_table_record(tag));
+        let start = record.offset as usize;
+        let end = start + record.length as usize;
+
+        self.buf.get(start..end)
+    }
+
     pub fn get_table<'tbl, T: TaggedTable<'tbl>>(&'tbl self) -> Option<T> {
         let offset = match self.get_table_offset(T::tag()) {
             Some(offset) => offset,
@@ -140,6 +181,855 @@ impl<'f> Font<'f> {
             Err(_) => None,
         }
     }
+
+    pub fn get_table_vmtx(&self) -> Option<Vmtx<'f>> {
+        let offset = try_opt!(self.get_table_offset(Tag(*b"vmtx")));
+        let vhea = try_opt!(self.get_table::<Vhea>());
+        let maxp = try_opt!(self.get_table::<Maxp>());
+
+        let (_, buffer) = self.buf.split_at(offset);
+        Vmtx::parse(buffer, maxp.get_num_glyphs(), vhea.number_of_v_metrics).ok()
+    }
+
+    /// Vertical metrics synthesized from the `OS/2` typographic ascender and
+    /// descender, or from the `head` bounding box if `OS/2` is unavailable.
+    pub fn get_synthetic_vmtx(&self) -> Option<SyntheticVmtx> {
+        if let Some(os2) = self.get_table::<Os2>() {
+            return Some(SyntheticVmtx {
+                ascender: os2.s_typo_ascender(),
+                descender: os2.s_typo_descender(),
+            })
+        }
+
+        self.get_table::<Head>().map(|head| SyntheticVmtx {
+            ascender: head.y_max(),
+            descender: head.y_min(),
+        })
+    }
+
+    /// The vertical advance of a glyph, from `vmtx` when present.
+    pub fn get_vert_advance(&self, glyph_id: u16) -> Option<u16> {
+        match self.get_table_vmtx() {
+            Some(vmtx) => vmtx.get_advance(glyph_id),
+            None => self.get_synthetic_vmtx().map(|v| v.get_advance()),
+        }
+    }
+
+    /// The y coordinate of a glyph's vertical origin.  This is taken from
+    /// `VORG` when present, otherwise from the top side bearing in `vmtx`
+    /// added to the top of the glyph's bounding box.
+    pub fn get_vert_origin_y(&self, glyph_id: u16) -> Option<i16> {
+        if let Some(vorg) = self.get_table::<Vorg>() {
+            return Some(vorg.get_vert_origin_y(glyph_id))
+        }
+
+        let tsb = self.get_table_vmtx().and_then(|vmtx| vmtx.get_tsb(glyph_id));
+        let header = self.get_glyph_header(glyph_id);
+
+        match (tsb, header) {
+            (Some(tsb), Some(header)) => Some(tsb.saturating_add(header.y_max)),
+            _ => self.get_synthetic_vmtx().map(|v| v.get_vert_origin_y()),
+        }
+    }
+
+    pub fn get_units_per_em(&self) -> Option<u16> {
+        self.get_table::<Head>().map(|head| head.units_per_em())
+    }
+
+    pub fn get_table_loca(&self) -> Option<Loca<'f>> {
+        let buffer = try_opt!(self.get_table_data(Tag(*b"loca")));
+        let head = try_opt!(self.get_table::<Head>());
+        let maxp = try_opt!(self.get_table::<Maxp>());
+
+        Loca::parse(buffer, maxp.get_num_glyphs(), head.index_to_loc_format()).ok()
+    }
+
+    /// Returns the description of a glyph in the `glyf` table.  Glyphs
+    /// without an outline return an empty slice.
+    pub fn get_glyph_data(&self, glyph_id: u16) -> Option<&'f [u8]> {
+        let glyf = try_opt!(self.get_table_data(Tag(*b"glyf")));
+        let loca = try_opt!(self.get_table_loca());
+        let range = try_opt!(loca.get_glyph_range(glyph_id));
+
+        glyf.get(range)
+    }
+
+    pub fn get_glyph(&self, glyph_id: u16) -> Option<Glyph<'f>> {
+        Glyph::parse(try_opt!(self.get_glyph_data(glyph_id))).ok()
+    }
+
+    fn get_max_component_depth(&self) -> u16 {
+        match self.get_table::<Maxp>() {
+            Some(Maxp::Version1(ref maxp)) if maxp.max_component_depth > 0 => maxp.max_component_depth,
+            _ => glyf::MAX_COMPONENT_DEPTH,
+        }
+    }
+
+    /// The outline of a glyph, with composite glyphs resolved.  Nesting
+    /// is bounded by `max_component_depth` from the `maxp` table.
+    pub fn get_glyph_outline(&self, glyph_id: u16) -> Option<Outline> {
+        let max_depth = self.get_max_component_depth();
+        glyf::resolve_outline(glyph_id, max_depth, &|id| self.get_glyph(id)).ok()
+    }
+
+    /// Streams the outline of a glyph to the builder in font units.
+    /// Returns false if the glyph could not be read.
+    pub fn outline_glyph<B: OutlineBuilder>(&self, glyph_id: u16, builder: &mut B) -> bool {
+        self.outline_glyph_transformed(glyph_id, &Transform::identity(), builder)
+    }
+
+    /// Streams the outline of a glyph to the builder, applying the
+    /// transform to every point.
+    pub fn outline_glyph_transformed<B: OutlineBuilder>(&self, glyph_id: u16,
+                                                        transform: &Transform,
+                                                        builder: &mut B) -> bool {
+        self.outline_glyph_instance(glyph_id, &[], transform, builder)
+    }
+
+    /// Streams the outline of a glyph at a location in the font's
+    /// variation space, given as normalized coordinates for each axis.
+    /// Only `CFF2` outlines are varied.
+    pub fn outline_glyph_instance<B: OutlineBuilder>(&self, glyph_id: u16,
+                                                     coords: &[f32],
+                                                     transform: &Transform,
+                                                     builder: &mut B) -> bool {
+        if self.get_table_record(Tag(*b"glyf")).is_some() {
+            let max_depth = self.get_max_component_depth();
+            let load = |id| self.get_glyph(id);
+            return glyf::draw_glyph(glyph_id, max_depth, &load, transform, builder).is_ok()
+        }
+
+        if let Some(cff) = self.get_table::<Cff>() {
+            let mut builder = TransformBuilder::new(builder, *transform);
+            return cff.outline(glyph_id, &mut builder).is_ok()
+        }
+
+        if let Some(cff2) = self.get_table::<Cff2>() {
+            let mut builder = TransformBuilder::new(builder, *transform);
+            return cff2.outline(glyph_id, coords, &mut builder).is_ok()
+        }
+
+        false
+    }
+
+    pub fn get_glyph_header(&self, glyph_id: u16) -> Option<GlyphHeader> {
+        let data = try_opt!(self.get_glyph_data(glyph_id));
+        if data.is_empty() {
+            return None
+        }
+
+        GlyphHeader::parse(data).ok()
+    }
+
+    /// The ink bounding box of a glyph in font units.  This is read from the
+    /// glyph's outline header or found from its charstring, falling back
+    /// to the embedded bitmap tables.
+    pub fn get_glyph_bounds(&self, glyph_id: u16) -> Option<BoundingBox<i32>> {
+        if self.get_table_record(Tag(*b"glyf")).is_some() {
+            return self.get_glyph_header(glyph_id).map(|h| BoundingBox {
+                x_min: h.x_min as i32,
+                y_min: h.y_min as i32,
+                x_max: h.x_max as i32,
+                y_max: h.y_max as i32,
+            })
+        }
+
+        // Charstrings have no stored bounds, so the outline's control box
+        // is used instead.
+        if self.get_table_record(Tag(*b"CFF ")).is_some() || self.get_table_record(Tag(*b"CFF2")).is_some() {
+            let mut builder = BoundsBuilder::new();
+            if !self.outline_glyph(glyph_id, &mut builder) || builder.is_empty() {
+                return None
+            }
+
+            return Some(BoundingBox {
+                x_min: builder.x_min.floor() as i32,
+                y_min: builder.y_min.floor() as i32,
+                x_max: builder.x_max.ceil() as i32,
+                y_max: builder.y_max.ceil() as i32,
+            })
+        }
+
+        self.get_bitmap_bounds(glyph_id)
+    }
+
+    fn get_bitmap_bounds(&self, glyph_id: u16) -> Option<BoundingBox<i32>> {
+        let (loc, data) = match (self.get_table_data(Tag(*b"CBLC")), self.get_table_data(Tag(*b"CBDT"))) {
+            (Some(loc), Some(data)) => (loc, data),
+            _ => (try_opt!(self.get_table_data(Tag(*b"EBLC"))),
+                  try_opt!(self.get_table_data(Tag(*b"EBDT")))),
+        };
+
+        let loc = try_opt!(BitmapLocation::parse(loc).ok());
+        let (size, m) = try_opt!(loc.get_glyph_metrics(glyph_id, data));
+        let upem = try_opt!(self.get_units_per_em()) as i32;
+
+        if size.ppem_x == 0 || size.ppem_y == 0 {
+            return None
+        }
+
+        let x = |v: i32| v * upem / size.ppem_x as i32;
+        let y = |v: i32| v * upem / size.ppem_y as i32;
+        let bearing_x = m.hori_bearing_x as i32;
+        let bearing_y = m.hori_bearing_y as i32;
+
+        Some(BoundingBox {
+            x_min: x(bearing_x),
+            y_min: y(bearing_y - m.height as i32),
+            x_max: x(bearing_x + m.width as i32),
+            y_max: y(bearing_y),
+        })
+    }
+
+    /// The advance, side bearings and bounding box of a glyph in font units.
+    pub fn get_glyph_metrics(&self, glyph_id: u16) -> Option<GlyphMetrics<i32>> {
+        let hmtx = try_opt!(self.get_table_hmtx());
+        let bounds = self.get_glyph_bounds(glyph_id);
+
+        // Composite glyphs may take their metrics from a component.
+        let metrics_glyph = match self.get_glyph(glyph_id) {
+            Some(Glyph::Composite(_)) => self.get_glyph_outline(glyph_id)
+                .and_then(|outline| outline.metrics_glyph)
+                .unwrap_or(glyph_id),
+            _ => glyph_id,
+        };
+        let record = try_opt!(hmtx.get_record(metrics_glyph));
+
+        let advance_width = record.advance_width as i32;
+        let left_side_bearing = record.lsb as i32;
+        let width = bounds.map(|b| b.width()).unwrap_or(0);
+
+        Some(GlyphMetrics {
+            advance_width,
+            left_side_bearing,
+            right_side_bearing: advance_width - left_side_bearing - width,
+            bounds,
+        })
+    }
+
+    pub fn get_glyph_metrics_scaled(&self, glyph_id: u16, size: Size) -> Option<GlyphMetrics<f32>> {
+        let upem = try_opt!(self.get_units_per_em());
+        self.get_glyph_metrics(glyph_id)
+            .map(|m| m.scale(size.scale_factor(upem)))
+    }
+
+    /// Renders a glyph into an anti-aliased coverage bitmap.  The offset
+    /// moves the pen by a fraction of a pixel, with y pointing up.
+    pub fn rasterize_glyph(&self, glyph_id: u16, size: Size, offset: (f32, f32)) -> Option<Bitmap> {
+        self.rasterize_glyph_instance(glyph_id, &[], size, offset)
+    }
+
+    /// Rasterizes a glyph at a location in the font's variation space,
+    /// given as normalized coordinates for each axis.
+    pub fn rasterize_glyph_instance(&self, glyph_id: u16, coords: &[f32], size: Size,
+                                    offset: (f32, f32)) -> Option<Bitmap> {
+        let scale = size.scale_factor(try_opt!(self.get_units_per_em()));
+
+        // Bitmaps are stored from the top row down.
+        let transform = Transform {
+            yy: -scale,
+            dx: offset.0,
+            dy: -offset.1,
+            ..Transform::scale(scale, scale)
+        };
+
+        let mut bounds = BoundsBuilder::new();
+        if !self.outline_glyph_instance(glyph_id, coords, &transform, &mut bounds) {
+            return None
+        }
+
+        if bounds.is_empty() {
+            return Some(Bitmap { width: 0, height: 0, left: 0, top: 0, coverage: Vec::new() })
+        }
+
+        let left = bounds.x_min.floor();
+        let top = bounds.y_min.floor();
+        let width = (bounds.x_max.ceil() - left) as usize;
+        let height = (bounds.y_max.ceil() - top) as usize;
+
+        let mut rasterizer = Rasterizer::new(width, height);
+        let transform = transform.then(&Transform::translate(-left, -top));
+        self.outline_glyph_instance(glyph_id, coords, &transform, &mut rasterizer);
+
+        Some(Bitmap {
+            width,
+            height,
+            left: left as i32,
+            top: -top as i32,
+            coverage: rasterizer.accumulate(),
+        })
+    }
+
+    /// Generates a signed distance field of a glyph, with the outline
+    /// scaled to `size` and surrounded by the padding.
+    pub fn get_glyph_distance_field(&self, glyph_id: u16, size: Size,
+                                    options: &FieldOptions) -> Option<DistanceField> {
+        let scale = size.scale_factor(try_opt!(self.get_units_per_em()));
+        let transform = Transform { yy: -scale, ..Transform::scale(scale, scale) };
+
+        let mut bounds = BoundsBuilder::new();
+        if !self.outline_glyph_transformed(glyph_id, &transform, &mut bounds) {
+            return None
+        }
+
+        let channels = options.kind.channels();
+        let range = options.range / scale;
+        if bounds.is_empty() {
+            let bounds = BoundingBox { x_min: 0.0, y_min: 0.0, x_max: 0.0, y_max: 0.0 };
+            return Some(DistanceField { width: 0, height: 0, channels, data: Vec::new(), bounds, range })
+        }
+
+        let padding = options.padding as f32;
+        let left = bounds.x_min.floor() - padding;
+        let top = bounds.y_min.floor() - padding;
+        let width = (bounds.x_max.ceil() + padding - left) as usize;
+        let height = (bounds.y_max.ceil() + padding - top) as usize;
+
+        let mut builder = DistanceFieldBuilder::new();
+        let transform = transform.then(&Transform::translate(-left, -top));
+        self.outline_glyph_transformed(glyph_id, &transform, &mut builder);
+
+        Some(DistanceField {
+            width,
+            height,
+            channels,
+            data: builder.render(width, height, options),
+            bounds: BoundingBox {
+                x_min: left / scale,
+                y_min: -(top + height as f32) / scale,
+                x_max: (left + width as f32) / scale,
+                y_max: -top / scale,
+            },
+            range,
+        })
+    }
+
+    /// The outline of a glyph as polygons in font units, with curves
+    /// replaced by lines at most `tolerance` units away.  The tolerance
+    /// must be positive.
+    pub fn flatten_glyph(&self, glyph_id: u16, tolerance: f32) -> Option<Vec<Contour>> {
+        if !(tolerance > 0.0 && tolerance.is_finite()) {
+            return None
+        }
+
+        let mut builder = FlattenBuilder::new(tolerance);
+        if !self.outline_glyph(glyph_id, &mut builder) {
+            return None
+        }
+
+        Some(builder.into_contours())
+    }
+
+    /// Streams the outline of a glyph with overlapping contours merged,
+    /// as needed when instancing variable fonts or stroking.
+    pub fn outline_glyph_simplified<B: OutlineBuilder>(&self, glyph_id: u16, transform: &Transform,
+                                                       builder: &mut B) -> bool {
+        let mut path = PathRecorder::new();
+        if !self.outline_glyph(glyph_id, &mut path) {
+            return false
+        }
+
+        pathops::simplify(&path, &mut TransformBuilder::new(builder, *transform));
+        true
+    }
+
+    /// Triangulates a glyph in font units, optionally extruded by `depth`.
+    pub fn get_glyph_mesh(&self, glyph_id: u16, tolerance: f32, depth: Option<f32>) -> Option<Mesh> {
+        let contours = try_opt!(self.flatten_glyph(glyph_id, tolerance));
+        Some(match depth {
+            Some(depth) => Mesh::extrude(&contours, depth),
+            None => Mesh::fill(&contours),
+        })
+    }
+
+    /// The outline of a glyph as the `d` attribute of an SVG path, in
+    /// font units flipped so that y points down.
+    pub fn get_glyph_svg_path(&self, glyph_id: u16) -> Option<String> {
+        let mut path = SvgPathBuilder::new();
+        if !self.outline_glyph_transformed(glyph_id, &Transform::scale(1.0, -1.0), &mut path) {
+            return None
+        }
+
+        Some(path.into_string())
+    }
+
+    /// Draws a run of positioned glyphs as an SVG document in font units,
+    /// with the pen starting at the origin.
+    pub fn get_glyph_run_svg(&self, buffer: &GlyphBuffer, options: &SvgOptions) -> Option<String> {
+        if buffer.is_empty() {
+            return None
+        }
+
+        let mut document = SvgDocument::new(options.symbols);
+        let mut extents = BoundsBuilder::new();
+        let mut origins = Vec::with_capacity(buffer.len());
+        let (mut pen_x, mut pen_y) = (0, 0);
+
+        for (glyph, position) in buffer.glyphs.iter().zip(&buffer.positions) {
+            let x = (pen_x + position.x_offset) as f32;
+            let y = (pen_y + position.y_offset) as f32;
+            origins.push((x, y));
+            pen_x += position.x_advance;
+            pen_y += position.y_advance;
+
+            let placed = Transform::translate(x, y);
+            if options.view_box == ViewBox::Extents {
+                self.outline_glyph_transformed(glyph.glyph_id, &placed, &mut extents);
+            }
+
+            if !document.needs_path(glyph.glyph_id) {
+                document.add_glyph(glyph.glyph_id, "", x, -y);
+                continue
+            }
+
+            // Symbols are drawn at the origin and placed by `<use>`.
+            let transform = if options.symbols { Transform::identity() } else { placed };
+            let mut path = SvgPathBuilder::new();
+            if !self.outline_glyph_transformed(glyph.glyph_id,
+                                               &transform.then(&Transform::scale(1.0, -1.0)),
+                                               &mut path) {
+                return None
+            }
+
+            document.add_glyph(glyph.glyph_id, path.as_str(), x, -y);
+        }
+
+        let bounds = if !extents.is_empty() {
+            BoundingBox {
+                x_min: extents.x_min,
+                y_min: extents.y_min,
+                x_max: extents.x_max,
+                y_max: extents.y_max,
+            }
+        } else {
+            let head = try_opt!(self.get_table::<Head>());
+            let mut bounds = BoundingBox {
+                x_min: f32::INFINITY,
+                y_min: f32::INFINITY,
+                x_max: f32::NEG_INFINITY,
+                y_max: f32::NEG_INFINITY,
+            };
+
+            for &(x, y) in &origins {
+                bounds.x_min = bounds.x_min.min(x + head.x_min() as f32);
+                bounds.y_min = bounds.y_min.min(y + head.y_min() as f32);
+                bounds.x_max = bounds.x_max.max(x + head.x_max() as f32);
+                bounds.y_max = bounds.y_max.max(y + head.y_max() as f32);
+            }
+            bounds
+        };
+
+        Some(document.finish(&bounds))
+    }
+
+    /// Runs the font's TrueType instructions at the given ppem, returning
+    /// a hinter for its glyphs.  Fonts without `glyf` outlines, or whose
+    /// programs fail, cannot be hinted.
+    pub fn get_hinter(&'f self, ppem: u16, mode: HintingMode) -> Option<Hinter<'f>> {
+        self.get_table_record(Tag(*b"glyf"))?;
+        Hinter::new(self, ppem, mode).ok()
+    }
+
+    /// The rendering the font asks for at the given ppem.  Sizes not
+    /// covered by `gasp` are hinted and anti-aliased, though only `glyf`
+    /// outlines can be hinted.
+    pub fn rendering_flags(&self, ppem: u16) -> RenderingFlags {
+        let behavior = self.get_table::<Gasp>()
+            .and_then(|gasp| gasp.get_behavior(ppem))
+            .unwrap_or(gasp::GRIDFIT | gasp::DOGRAY);
+
+        let (flags, lowest_rec_ppem) = match self.get_table::<Head>() {
+            Some(head) => (head.get_flags(), head.lowest_rec_ppem()),
+            None => (head::HeadFlags::empty(), 0),
+        };
+
+        let has_glyf = self.get_table_record(Tag(*b"glyf")).is_some();
+
+        RenderingFlags {
+            gridfit: has_glyf && behavior.contains(gasp::GRIDFIT),
+            grayscale: behavior.contains(gasp::DOGRAY),
+            symmetric_gridfit: behavior.contains(gasp::SYMMETRIC_GRIDFIT),
+            symmetric_smoothing: behavior.contains(gasp::SYMMETRIC_SMOOTHING),
+            integer_ppem: flags.contains(head::FORCE_INTEGER_PPEM),
+            nonlinear_advances: flags.contains(head::INSTRUCTIONS_ALTER_ADVANCE),
+            below_lowest_rec_ppem: ppem < lowest_rec_ppem,
+        }
+    }
+
+    /// The ascender, descender and line gap in font units.  The `OS/2`
+    /// typographic metrics are used when `USE_TYPO_METRICS` is set,
+    /// otherwise `hhea` is preferred, followed by the `OS/2` typographic
+    /// and then the Windows metrics.
+    pub fn get_font_metrics(&self) -> Option<FontMetrics<i32>> {
+        let os2 = self.get_table::<Os2>();
+        let style = self.get_style();
+
+        let typo = os2.as_ref().map(|os2| FontMetrics {
+            ascender: os2.s_typo_ascender() as i32,
+            descender: os2.s_typo_descender() as i32,
+            line_gap: os2.s_typo_line_gap() as i32,
+            style,
+        });
+
+        if let Some(ref os2) = os2 {
+            if os2.get_fs_selection().contains(USE_TYPO_METRICS) {
+                return typo
+            }
+        }
+
+        if let Some(hhea) = self.get_table::<Hhea>() {
+            if hhea.ascent.0 != 0 || hhea.descent.0 != 0 {
+                return Some(FontMetrics {
+                    ascender: hhea.ascent.0 as i32,
+                    descender: hhea.descent.0 as i32,
+                    line_gap: hhea.line_gap.0 as i32,
+                    style,
+                })
+            }
+        }
+
+        match os2 {
+            Some(ref os2) if os2.s_typo_ascender() != 0 || os2.s_typo_descender() != 0 => typo,
+            Some(ref os2) => Some(FontMetrics {
+                ascender: os2.us_win_ascent() as i32,
+                descender: -(os2.us_win_descent() as i32),
+                line_gap: 0,
+                style,
+            }),
+            None => None,
+        }
+    }
+
+    /// The style of the face, from `OS/2` and `head`.
+    pub fn get_style(&self) -> Style {
+        let mut style = Style::empty();
+        if let Some(head) = self.get_table::<Head>() {
+            let mac_style = head.get_mac_style();
+            style.set(metrics::BOLD, mac_style.contains(head::BOLD));
+            style.set(metrics::ITALIC, mac_style.contains(head::ITALIC));
+        }
+
+        if let Some(os2) = self.get_table::<Os2>() {
+            let fs_selection = os2.get_fs_selection();
+            if fs_selection.contains(os2::BOLD) {
+                style |= metrics::BOLD;
+            }
+            if fs_selection.contains(os2::ITALIC) {
+                style |= metrics::ITALIC;
+            }
+            if fs_selection.contains(os2::OBLIQUE) {
+                style |= metrics::ITALIC | metrics::OBLIQUE;
+            }
+        }
+
+        style
+    }
+
+    /// The synthesis imitating a bold or oblique face.  Styles the face
+    /// already has are not synthesized again.  Obliques follow the caret
+    /// slope from `hhea` when it is slanted.
+    pub fn get_synthesis(&self, bold: bool, oblique: bool) -> Synthesis {
+        let style = self.get_style();
+        let mut synthesis = Synthesis::default();
+
+        if bold && !style.contains(metrics::BOLD) {
+            if let Some(upem) = self.get_units_per_em() {
+                synthesis.embolden = Synthesis::bold_strength(upem);
+            }
+        }
+
+        if oblique && !style.contains(metrics::ITALIC) {
+            synthesis.skew = match self.get_table::<Hhea>() {
+                Some(ref hhea) if hhea.caret_slope_run != 0 && hhea.caret_slope_rise != 0 =>
+                    hhea.caret_slope_run as f32 / hhea.caret_slope_rise as f32,
+                _ => DEFAULT_SKEW,
+            };
+        }
+
+        synthesis
+    }
+
+    /// The font metrics, with the styles added by the synthesis.
+    pub fn get_font_metrics_synthesized(&self, synthesis: &Synthesis) -> Option<FontMetrics<i32>> {
+        self.get_font_metrics().map(|metrics| FontMetrics {
+            style: metrics.style | synthesis.style(),
+            ..metrics
+        })
+    }
+
+    /// Streams the outline of a glyph with the synthesis applied, before
+    /// the transform.
+    pub fn outline_glyph_synthesized<B: OutlineBuilder>(&self, glyph_id: u16, synthesis: &Synthesis,
+                                                        transform: &Transform, builder: &mut B) -> bool {
+        let transform = synthesis.transform().then(transform);
+        if synthesis.embolden == 0.0 {
+            return self.outline_glyph_transformed(glyph_id, &transform, builder)
+        }
+
+        let mut path = PathRecorder::new();
+        if !self.outline_glyph(glyph_id, &mut path) {
+            return false
+        }
+
+        synthesis::embolden(&mut path, synthesis.embolden);
+        path.replay(&mut TransformBuilder::new(builder, transform));
+        true
+    }
+
+    /// The metrics of a glyph with the synthesis applied.  Bounds are
+    /// those of the changed outline, rounded out to whole units.
+    pub fn get_glyph_metrics_synthesized(&self, glyph_id: u16,
+                                         synthesis: &Synthesis) -> Option<GlyphMetrics<i32>> {
+        let metrics = try_opt!(self.get_glyph_metrics(glyph_id));
+        let advance_width = metrics.advance_width + synthesis.advance_delta();
+        if synthesis.is_empty() || metrics.bounds.is_none() {
+            return Some(GlyphMetrics {
+                advance_width,
+                right_side_bearing: metrics.right_side_bearing + synthesis.advance_delta(),
+                ..metrics
+            })
+        }
+
+        let mut builder = BoundsBuilder::new();
+        if !self.outline_glyph_synthesized(glyph_id, synthesis, &Transform::identity(), &mut builder) {
+            return None
+        }
+
+        let bounds = BoundingBox {
+            x_min: builder.x_min.floor() as i32,
+            y_min: builder.y_min.floor() as i32,
+            x_max: builder.x_max.ceil() as i32,
+            y_max: builder.y_max.ceil() as i32,
+        };
+
+        // The side bearings keep the origin of the original outline.
+        let left_side_bearing = metrics.left_side_bearing
+            + bounds.x_min - metrics.bounds.map(|b| b.x_min).unwrap_or(0);
+
+        Some(GlyphMetrics {
+            advance_width,
+            left_side_bearing,
+            right_side_bearing: advance_width - left_side_bearing - bounds.width(),
+            bounds: Some(bounds),
+        })
+    }
+
+    pub fn get_table_hdmx(&self) -> Option<Hdmx<'f>> {
+        let buffer = try_opt!(self.get_table_data(Tag(*b"hdmx")));
+        let maxp = try_opt!(self.get_table::<Maxp>());
+
+        Hdmx::parse(buffer, maxp.get_num_glyphs()).ok()
+    }
+
+    /// The advance of a glyph in whole pixels at the given ppem.  Hinted
+    /// widths from `hdmx` are used unless `LTSH` reports the glyph as linear
+    /// at this size, otherwise the `hmtx` advance is scaled and rounded.
+    pub fn get_pixel_advance(&self, glyph_id: u16, ppem: u16) -> Option<i32> {
+        let linear = self.get_table::<Ltsh>()
+            .map(|ltsh| ltsh.is_linear(glyph_id, ppem))
+            .unwrap_or(false);
+
+        if !linear && ppem <= 255 {
+            let hinted = self.get_table_hdmx()
+                .and_then(|hdmx| hdmx.get_advance(glyph_id, ppem as u8));
+
+            if let Some(advance) = hinted {
+                return Some(advance as i32)
+            }
+        }
+
+        let upem = try_opt!(self.get_units_per_em());
+        let advance = try_opt!(self.get_table_hmtx()
+            .and_then(|hmtx| hmtx.get_advance(glyph_id)));
+
+        Some((advance as f32 * Size::Pixels(ppem as f32).scale_factor(upem)).round() as i32)
+    }
+
+    /// The line metrics in whole pixels at the given ppem.  The ascender and
+    /// descender come from `VDMX` when available, otherwise the font metrics
+    /// are scaled with the ascender rounded up and the descender rounded down.
+    pub fn get_pixel_font_metrics(&self, ppem: u16) -> Option<FontMetrics<i32>> {
+        let scaled = try_opt!(self.get_font_metrics_scaled(Size::Pixels(ppem as f32)));
+        let line_gap = scaled.line_gap.round() as i32;
+
+        if let Some(rec) = self.get_table::<Vdmx>().and_then(|vdmx| vdmx.get_extents(ppem)) {
+            return Some(FontMetrics {
+                ascender: rec.y_max as i32,
+                descender: rec.y_min as i32,
+                line_gap,
+                style: scaled.style,
+            })
+        }
+
+        Some(FontMetrics {
+            ascender: scaled.ascender.ceil() as i32,
+            descender: scaled.descender.floor() as i32,
+            line_gap,
+            style: scaled.style,
+        })
+    }
+
+    /// Maps text to glyphs using the default `cmap`, with advances from
+    /// `hmtx`.  Characters missing from the font map to glyph 0.
+    pub fn get_glyph_buffer(&self, text: &str) -> Option<GlyphBuffer> {
+        let cmap = try_opt!(self.get_table::<CmapHeader>()
+            .and_then(|cmap| cmap.get_default_cmap()));
+        let hmtx = try_opt!(self.get_table_hmtx());
+
+        let mut buffer = GlyphBuffer::new();
+        for (cluster, ch) in text.char_indices() {
+            let glyph_id = cmap.get_glyph_id(ch as u32).unwrap_or(0);
+            buffer.push(glyph_id, cluster as u32);
+
+            let advance = hmtx.get_advance(glyph_id).unwrap_or(0);
+            buffer.positions.last_mut().unwrap().x_advance = advance as i32;
+        }
+
+        Some(buffer)
+    }
+
+    /// Applies the `GSUB` lookups of some features for a script and
+    /// language to a glyph run, then resets the positions to the advances
+    /// of the new glyphs.  Returns whether any glyph was substituted.
+    pub fn apply_substitutions(&self, buffer: &mut GlyphBuffer, script: Tag, language: Option<Tag>,
+                               features: &[Tag]) -> bool {
+        let gsub = match self.get_table::<gsub::Header>() {
+            Some(gsub) => gsub,
+            None => return false,
+        };
+        let gdef = self.get_table::<gdef::Header>();
+
+        if !gsub.apply(buffer, gdef.as_ref(), script, language, features) {
+            return false
+        }
+
+        let hmtx = self.get_table_hmtx();
+        for (glyph, position) in buffer.glyphs.iter().zip(buffer.positions.iter_mut()) {
+            let advance = hmtx.as_ref().and_then(|hmtx| hmtx.get_advance(glyph.glyph_id)).unwrap_or(0);
+            *position = GlyphPosition { x_advance: advance as i32, ..GlyphPosition::default() };
+        }
+        true
+    }
+
+    /// The scripts and languages of `GSUB` and `GPOS`, with the features
+    /// of both tables for each.
+    pub fn get_language_systems(&self) -> Vec<LanguageSystem> {
+        let mut systems: Vec<LanguageSystem> = Vec::new();
+        let tables = self.get_table::<gsub::Header>().map(|gsub| gsub.language_systems()).into_iter()
+            .chain(self.get_table::<gpos::Header>().map(|gpos| gpos.language_systems()));
+
+        for system in tables.flatten() {
+            match systems.iter_mut().find(|s| s.script == system.script && s.language == system.language) {
+                Some(existing) => {
+                    existing.features.extend(system.features);
+                    existing.features.sort();
+                    existing.features.dedup();
+                },
+                None => systems.push(system),
+            }
+        }
+        systems
+    }
+
+    /// Every feature of `GSUB` and `GPOS`, sorted by tag, with the names
+    /// given by its parameters.
+    pub fn get_features(&self) -> Vec<FeatureInfo> {
+        fn collect(table: &LayoutTable, features: &mut Vec<(Tag, Option<FeatureParams>)>) {
+            for index in 0..table.features.len() as u16 {
+                let tag = match table.features.get(index) {
+                    Some((tag, _)) => tag,
+                    None => continue,
+                };
+                let params = table.features.params(index);
+
+                match features.iter_mut().find(|&&mut (t, _)| t == tag) {
+                    Some(&mut (_, ref mut existing)) => {
+                        if existing.is_none() {
+                            *existing = params;
+                        }
+                    },
+                    None => features.push((tag, params)),
+                }
+            }
+        }
+
+        let mut features = Vec::new();
+        if let Some(gsub) = self.get_table::<gsub::Header>() {
+            collect(&gsub, &mut features);
+        }
+        if let Some(gpos) = self.get_table::<gpos::Header>() {
+            collect(&gpos, &mut features);
+        }
+        features.sort_by_key(|&(tag, _)| tag);
+
+        let names = self.get_table::<Name>();
+        let name = |id: u16| if id == 0 { None } else { names.as_ref().and_then(|names| names.get_name(id)) };
+
+        features.into_iter().map(|(tag, params)| {
+            let mut info = FeatureInfo {
+                tag,
+                params: params.clone(),
+                name: None,
+                tooltip: None,
+                sample_text: None,
+                param_labels: Vec::new(),
+            };
+
+            match params {
+                Some(FeatureParams::Size(size)) => info.name = name(size.subfamily_name_id),
+                Some(FeatureParams::StylisticSet { ui_name_id }) => info.name = name(ui_name_id),
+                Some(FeatureParams::CharacterVariant(cv)) => {
+                    info.name = name(cv.label_name_id);
+                    info.tooltip = name(cv.tooltip_name_id);
+                    info.sample_text = name(cv.sample_text_name_id);
+                    info.param_labels = (0..cv.num_named_parameters)
+                        .filter_map(|i| cv.first_param_label_name_id.checked_add(i))
+                        .filter_map(&name)
+                        .collect();
+                },
+                None => {},
+            }
+            info
+        }).collect()
+    }
+
+    /// Applies the `GPOS` lookups of some features for a script and
+    /// language to the positions of a glyph run.  Returns whether any glyph
+    /// was positioned.
+    pub fn apply_positioning(&self, buffer: &mut GlyphBuffer, script: Tag, language: Option<Tag>,
+                             features: &[Tag]) -> bool {
+        let gpos = match self.get_table::<gpos::Header>() {
+            Some(gpos) => gpos,
+            None => return false,
+        };
+        let gdef = self.get_table::<gdef::Header>();
+
+        gpos.apply(buffer, gdef.as_ref(), script, language, features)
+    }
+
+    /// Kerns a glyph run with the `kern` feature of `GPOS` for a script.
+    /// Like other shaping engines, the legacy `kern` table is only used
+    /// when `GPOS` has no `kern` feature for the script.  Returns whether
+    /// kerning was applied.
+    pub fn apply_kerning(&self, buffer: &mut GlyphBuffer, script: Tag) -> bool {
+        if let Some(gpos) = self.get_table::<gpos::Header>() {
+            if gpos.has_lang_sys_feature(script, None, Tag(*b"kern")) {
+                let gdef = self.get_table::<gdef::Header>();
+                return gpos.apply(buffer, gdef.as_ref(), script, None, &[Tag(*b"kern")])
+            }
+        }
+
+        match self.get_table::<Kern>() {
+            Some(kern) => kern.apply(buffer),
+            None => false,
+        }
+    }
+
+    pub fn get_font_metrics_scaled(&self, size: Size) -> Option<FontMetrics<f32>> {
+        let upem = try_opt!(self.get_units_per_em());
+        self.get_font_metrics()
+            .map(|m| m.scale(size.scale_factor(upem)))
+    }
 }
 
 pub struct TableIter<'a> {
@@ -201,4 +1091,210 @@ mod test {
             *b"prep", check_sum: 1136105124, offset: 7300, length: 265,
         );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn glyph_metrics() {
+        use metrics::{Size, BoundingBox, FontMetrics, Style};
+
+        let buf = open_font!(r"data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // 'g'
+        let g = font.get_glyph_metrics(74).expect("Unable to read metrics for 'g'");
+        assert_eq!(g.advance_width, 1122);
+        assert_eq!(g.left_side_bearing, 39);
+        assert_eq!(g.right_side_bearing, 1122 - 1073);
+        assert_eq!(g.bounds, Some(BoundingBox { x_min: 39, y_min: -492, x_max: 1073, y_max: 1116 }));
+
+        // ' '
+        let space = font.get_glyph_metrics(3).expect("Unable to read metrics for ' '");
+        assert_eq!(space.advance_width, 532);
+        assert_eq!(space.right_side_bearing, 532);
+        assert_eq!(space.bounds, None);
+
+        let scaled = font.get_glyph_metrics_scaled(74, Size::Pixels(2048.0 / 4.0))
+            .expect("Unable to read scaled metrics for 'g'");
+        assert_eq!(scaled.advance_width, 280.5);
+
+        let points = font.get_glyph_metrics_scaled(74, Size::Points { size: 384.0, dpi: 96.0 })
+            .expect("Unable to read scaled metrics for 'g'");
+        assert_eq!(points, scaled);
+
+        let metrics = font.get_font_metrics().expect("Unable to read font metrics");
+        assert_eq!(metrics, FontMetrics { ascender: 2189, descender: -600, line_gap: 0, style: Style::empty() });
+        assert_eq!(metrics.line_height(), 2789);
+    }
+
+    #[test]
+    fn cff_glyph_bounds() {
+        use metrics::BoundingBox;
+        use table::cff::test::{build, index, ops};
+
+        let charstrings = index(&[
+            &ops(false, &[&[14]]),
+            &ops(false, &[&[500, 10, -20, 21], &[100, 0, 5], &[0, 50, -50, 100, -50, 0, 8], &[14]]),
+        ]);
+        let charset = vec![0, 0, 34];
+        let private = ops(true, &[&[0, 20]]);
+        let size = private.len() as i32;
+        let cff = build(&[charset, charstrings, private], |o| ops(true, &[
+            &[o[0] as i32, 15],
+            &[o[1] as i32, 17],
+            &[size, o[2] as i32, 18],
+        ]));
+
+        // An OpenType font holding only the CFF table.
+        let mut buf = b"OTTO\x00\x01\x00\x10\x00\x00\x00\x00CFF \x00\x00\x00\x00\x00\x00\x00\x1C".to_vec();
+        buf.extend(&[0, 0, (cff.len() >> 8) as u8, cff.len() as u8]);
+        buf.extend(cff);
+
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        assert_eq!(font.get_glyph_bounds(1), Some(BoundingBox { x_min: 10, y_min: -20, x_max: 110, y_max: 130 }));
+        assert_eq!(font.get_glyph_bounds(0), None);
+        assert_eq!(font.get_glyph_bounds(2), None);
+    }
+
+    #[test]
+    fn kerning_fallback() {
+        use table::kern::Kern;
+
+        let buf = open_font!(r"data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        let mut run = font.get_glyph_buffer("AVA").expect("Unable to map text");
+        let glyphs = run.glyphs.iter().map(|g| g.glyph_id).collect::<Vec<_>>();
+        assert_eq!(glyphs, [36, 57, 36]);
+
+        assert!(font.apply_kerning(&mut run, Tag(*b"latn")));
+        let advances = run.positions.iter().map(|p| p.x_advance).collect::<Vec<_>>();
+        assert_eq!(advances, [1296 - 82, font.get_glyph_metrics(57).unwrap().advance_width - 82, 1296]);
+
+        // DroidSerif kerns through GPOS, and has no kern table.
+        let buf = open_font!(r"data/DroidSerif.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let mut run = font.get_glyph_buffer("AV").expect("Unable to map text");
+        let advance = run.positions[0].x_advance;
+        assert!(font.apply_kerning(&mut run, Tag(*b"latn")));
+        assert_eq!(run.positions[0].x_advance, advance - 164);
+
+        // A script the font has no entry for uses the default one.
+        let mut run = font.get_glyph_buffer("AV").expect("Unable to map text");
+        assert!(font.apply_kerning(&mut run, Tag(*b"cyrl")));
+        assert_eq!(run.positions[0].x_advance, advance - 164);
+
+        // With OpenSans' kern table in place of FFTM, the kern table
+        // kerns "TT" by 41, but GPOS has a kern feature so it is not used.
+        let kern_font = open_font!(r"data/OpenSans-Regular.ttf");
+        let kern = Font::from_buffer(&kern_font).ok()
+            .and_then(|f| f.get_table_data(Tag(*b"kern")))
+            .expect("Unable to read kern table")
+            .to_vec();
+        let mut buf = buf.clone();
+        assert_eq!(&buf[12..16], b"FFTM");
+        let (offset, length) = (buf.len() as u32, kern.len() as u32);
+        buf[12..16].copy_from_slice(b"kern");
+        buf[20..24].copy_from_slice(&[(offset >> 24) as u8, (offset >> 16) as u8, (offset >> 8) as u8, offset as u8]);
+        buf[24..28].copy_from_slice(&[(length >> 24) as u8, (length >> 16) as u8, (length >> 8) as u8, length as u8]);
+        buf.extend(kern);
+
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let mut run = font.get_glyph_buffer("TT").expect("Unable to map text");
+        let (left, right) = (run.glyphs[0].glyph_id, run.glyphs[1].glyph_id);
+        assert_eq!(font.get_table::<Kern>().map(|k| k.kerning(left, right)), Some(41));
+        let advances = run.positions.iter().map(|p| p.x_advance).collect::<Vec<_>>();
+        assert!(!font.apply_kerning(&mut run, Tag(*b"latn")));
+        assert_eq!(run.positions.iter().map(|p| p.x_advance).collect::<Vec<_>>(), advances);
+    }
+
+    #[test]
+    fn pixel_metrics() {
+        use metrics::{FontMetrics, Style};
+
+        let buf = open_font!(r"data/Roboto-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // 'A' at 9 ppem is hinted in hdmx, but not at 10 ppem.
+        assert_eq!(font.get_pixel_advance(37, 9), Some(6));
+        assert_eq!(font.get_pixel_advance(37, 10), Some(7));
+
+        // No VDMX, so the hhea metrics are scaled.
+        let metrics = font.get_pixel_font_metrics(16).expect("Unable to read pixel metrics");
+        assert_eq!(metrics, FontMetrics { ascender: 15, descender: -4, line_gap: 0, style: Style::empty() });
+    }
+
+    #[test]
+    fn synthetic_vertical_metrics() {
+        let buf = open_font!(r"data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        assert!(font.get_table_vmtx().is_none());
+        assert_eq!(font.get_vert_advance(36), Some(1567 + 492));
+        assert_eq!(font.get_vert_origin_y(36), Some(1567));
+    }
+
+    #[test]
+    fn rendering_flags() {
+        use hinting::{HintingMode, RenderingFlags};
+
+        let buf = open_font!(r"data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // Unhinted but smoothed up to 8 ppem, below the smallest readable size.
+        let small = font.rendering_flags(8);
+        assert_eq!(small, RenderingFlags {
+            gridfit: false,
+            grayscale: true,
+            symmetric_gridfit: false,
+            symmetric_smoothing: true,
+            integer_ppem: true,
+            nonlinear_advances: false,
+            below_lowest_rec_ppem: true,
+        });
+        assert_eq!(small.hinting_mode(), None);
+
+        assert_eq!(font.rendering_flags(12).hinting_mode(), Some(HintingMode::Legacy));
+        assert_eq!(font.rendering_flags(20).hinting_mode(), Some(HintingMode::Subpixel));
+
+        // Version 0 tables only ask for hinting and smoothing.
+        let buf = open_font!(r"data/DroidSerif.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let flags = font.rendering_flags(20);
+        assert!(flags.gridfit && flags.grayscale && !flags.symmetric_smoothing);
+        assert!(flags.nonlinear_advances);
+        assert_eq!(flags.hinting_mode(), Some(HintingMode::Legacy));
+    }
+
+    #[test]
+    fn rasterize_glyph() {
+        use metrics::Size;
+
+        let buf = open_font!(r"data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let o = font.get_glyph_buffer("o").expect("Unable to map text").glyphs[0].glyph_id;
+        let bounds = font.get_glyph_metrics_scaled(o, Size::Pixels(20.0))
+            .and_then(|m| m.bounds)
+            .expect("Unable to read bounds");
+
+        let bitmap = font.rasterize_glyph(o, Size::Pixels(20.0), (0.0, 0.0))
+            .expect("Unable to rasterize glyph");
+        assert_eq!(bitmap.left, bounds.x_min.floor() as i32);
+        assert_eq!(bitmap.top, bounds.y_max.ceil() as i32);
+        assert_eq!(bitmap.width as i32, bounds.x_max.ceil() as i32 - bitmap.left);
+        assert_eq!(bitmap.height as i32, bitmap.top - bounds.y_min.floor() as i32);
+        assert_eq!(bitmap.coverage.len(), bitmap.width * bitmap.height);
+
+        // The counter of the 'o' is empty, and its stems are over a pixel wide.
+        let row = &bitmap.coverage[bitmap.height / 2 * bitmap.width..][..bitmap.width];
+        assert_eq!(row[bitmap.width / 2], 0);
+        assert!(row[..bitmap.width / 2].iter().map(|&c| c as u32).sum::<u32>() > 255);
+
+        let shifted = font.rasterize_glyph(o, Size::Pixels(20.0), (0.5, 0.0))
+            .expect("Unable to rasterize glyph");
+        assert_ne!(shifted.coverage, bitmap.coverage);
+
+        // The space has no outline.
+        let space = font.get_glyph_buffer(" ").expect("Unable to map text").glyphs[0].glyph_id;
+        let empty = font.rasterize_glyph(space, Size::Pixels(20.0), (0.0, 0.0));
+        assert_eq!(empty.map(|b| b.coverage.len()), Some(0));
+    }
+}
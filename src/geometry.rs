@@ -0,0 +1,664 @@
+use std::cmp::Ordering;
+
+use outline::OutlineBuilder;
+
+/// The most lines a single curve is replaced by, however small the
+/// tolerance.
+const MAX_SEGMENTS: usize = 1024;
+
+/// Splits a quadratic curve into lines deviating at most `tolerance`
+/// from it, calling `line_to` with the end of each line.
+pub fn flatten_quad<F>(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), tolerance: f32, mut line_to: F)
+    where F: FnMut((f32, f32))
+{
+    // The deviation from the chord is a quarter of the second difference.
+    let dd = hypot(p0.0 - 2.0 * p1.0 + p2.0, p0.1 - 2.0 * p1.1 + p2.1);
+    let n = segments(0.25 * dd, tolerance);
+
+    for i in 1..n + 1 {
+        let t = i as f32 / n as f32;
+        let mt = 1.0 - t;
+        line_to((mt * mt * p0.0 + 2.0 * mt * t * p1.0 + t * t * p2.0,
+                 mt * mt * p0.1 + 2.0 * mt * t * p1.1 + t * t * p2.1));
+    }
+}
+
+/// Splits a cubic curve into lines deviating at most `tolerance` from
+/// it, calling `line_to` with the end of each line.
+pub fn flatten_cubic<F>(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32),
+                        tolerance: f32, mut line_to: F)
+    where F: FnMut((f32, f32))
+{
+    let dd0 = hypot(p0.0 - 2.0 * p1.0 + p2.0, p0.1 - 2.0 * p1.1 + p2.1);
+    let dd1 = hypot(p1.0 - 2.0 * p2.0 + p3.0, p1.1 - 2.0 * p2.1 + p3.1);
+    let n = segments(0.75 * dd0.max(dd1), tolerance);
+
+    for i in 1..n + 1 {
+        let t = i as f32 / n as f32;
+        let mt = 1.0 - t;
+        let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+        line_to((a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                 a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1));
+    }
+}
+
+fn hypot(x: f32, y: f32) -> f32 {
+    (x * x + y * y).sqrt()
+}
+
+/// Number of lines needed to keep a curve within tolerance, given the
+/// curve's maximum deviation from its chord.
+fn segments(deviation: f32, tolerance: f32) -> usize {
+    // A NaN ratio, from a negative or NaN tolerance, gives a single line.
+    let n = (deviation / tolerance).sqrt().ceil();
+    if n > 1.0 {
+        n.min(MAX_SEGMENTS as f32) as usize
+    } else {
+        1
+    }
+}
+
+/// Twice the signed area of a triangle, positive when counter-clockwise.
+fn cross(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Orientation {
+    Clockwise,
+    CounterClockwise,
+}
+
+/// A closed polygon.  The last point connects back to the first.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Contour {
+    pub points: Vec<(f32, f32)>,
+}
+
+impl Contour {
+    /// The area enclosed, positive when counter-clockwise with y up.
+    pub fn signed_area(&self) -> f32 {
+        let n = self.points.len();
+        let sum: f32 = (0..n).map(|i| {
+            let (p, q) = (self.points[i], self.points[(i + 1) % n]);
+            p.0 * q.1 - q.0 * p.1
+        }).sum();
+        sum / 2.0
+    }
+
+    /// The direction of the contour with y pointing up.  TrueType outer
+    /// contours are clockwise, while CFF outer contours are
+    /// counter-clockwise.
+    pub fn orientation(&self) -> Orientation {
+        if self.signed_area() < 0.0 {
+            Orientation::Clockwise
+        } else {
+            Orientation::CounterClockwise
+        }
+    }
+
+    pub fn reverse(&mut self) {
+        self.points.reverse();
+    }
+
+    /// Whether a point lies inside the contour, by the even-odd rule.
+    pub fn contains(&self, p: (f32, f32)) -> bool {
+        let n = self.points.len();
+        let mut inside = false;
+        for i in 0..n {
+            let (a, b) = (self.points[i], self.points[(i + n - 1) % n]);
+            if (a.1 > p.1) != (b.1 > p.1) && p.0 < a.0 + (p.1 - a.1) * (b.0 - a.0) / (b.1 - a.1) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+}
+
+/// Where a contour sits among the other contours of an outline.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Nesting {
+    /// Number of contours enclosing this one.
+    pub depth: usize,
+    /// The innermost contour enclosing this one.
+    pub parent: Option<usize>,
+}
+
+impl Nesting {
+    /// Whether the contour cuts a hole into its parent, rather than
+    /// filling an area.  This follows the nesting of the contours, so
+    /// does not depend on how the font orders its points.
+    pub fn is_hole(&self) -> bool {
+        self.depth % 2 == 1
+    }
+
+    /// The orientation the contour should have for non-zero filling.
+    pub fn orientation(&self) -> Orientation {
+        if self.is_hole() {
+            Orientation::Clockwise
+        } else {
+            Orientation::CounterClockwise
+        }
+    }
+}
+
+/// Finds which contours enclose each other.  A contour counts as
+/// enclosed when most of its points are inside the other, so that
+/// overlapping components of composite glyphs stay separate shapes.
+pub fn nesting(contours: &[Contour]) -> Vec<Nesting> {
+    let areas: Vec<f32> = contours.iter().map(|c| c.signed_area().abs()).collect();
+
+    contours.iter().enumerate().map(|(i, contour)| {
+        let mut nesting = Nesting { depth: 0, parent: None };
+        for (j, other) in contours.iter().enumerate() {
+            if j == i || areas[j] <= areas[i] {
+                continue
+            }
+
+            let inside = contour.points.iter().filter(|&&p| other.contains(p)).count();
+            if 2 * inside <= contour.points.len() {
+                continue
+            }
+
+            nesting.depth += 1;
+            if nesting.parent.is_none_or(|parent| areas[j] < areas[parent]) {
+                nesting.parent = Some(j);
+            }
+        }
+        nesting
+    }).collect()
+}
+
+/// Collects an outline as polygons, replacing curves by lines.
+#[derive(Debug, Clone)]
+pub struct FlattenBuilder {
+    tolerance: f32,
+    contours: Vec<Contour>,
+    current: Contour,
+}
+
+impl FlattenBuilder {
+    /// `tolerance` is the maximum distance between a curve and its
+    /// lines, in the units of the outline.  Curves are split into at
+    /// most `MAX_SEGMENTS` lines, so a tolerance of zero is not met.
+    pub fn new(tolerance: f32) -> FlattenBuilder {
+        FlattenBuilder { tolerance, contours: Vec::new(), current: Contour::default() }
+    }
+
+    pub fn into_contours(mut self) -> Vec<Contour> {
+        self.close();
+        self.contours
+    }
+
+    fn last(&self) -> (f32, f32) {
+        self.current.points.last().cloned().unwrap_or((0.0, 0.0))
+    }
+
+    fn push(&mut self, p: (f32, f32)) {
+        if self.current.points.last() != Some(&p) {
+            self.current.points.push(p);
+        }
+    }
+}
+
+impl OutlineBuilder for FlattenBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.push((x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.push((x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let current = self.last();
+        let tolerance = self.tolerance;
+        flatten_quad(current, (x1, y1), (x, y), tolerance, |p| self.push(p));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let current = self.last();
+        let tolerance = self.tolerance;
+        flatten_cubic(current, (x1, y1), (x2, y2), (x, y), tolerance, |p| self.push(p));
+    }
+
+    fn close(&mut self) {
+        let mut contour = ::std::mem::take(&mut self.current);
+        if contour.points.len() > 1 && contour.points.first() == contour.points.last() {
+            contour.points.pop();
+        }
+
+        if contour.points.len() >= 3 {
+            self.contours.push(contour);
+        }
+    }
+}
+
+/// Triangles sharing a list of vertices.  Triangles are
+/// counter-clockwise when seen from the outside.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mesh {
+    pub vertices: Vec<[f32; 3]>,
+    /// Three vertex indices per triangle.
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Triangulates the area filled by the contours in the plane `z = 0`,
+    /// facing up the z axis.  Holes are found by nesting, so contours may
+    /// have either orientation, but should not intersect.
+    pub fn fill(contours: &[Contour]) -> Mesh {
+        let mut mesh = Mesh::default();
+        let contours = oriented(contours);
+        let nesting = nesting(&contours);
+
+        let mut first_vertex = Vec::with_capacity(contours.len());
+        for contour in &contours {
+            first_vertex.push(mesh.vertices.len() as u32);
+            mesh.vertices.extend(contour.points.iter().map(|p| [p.0, p.1, 0.0]));
+        }
+
+        for (i, contour) in contours.iter().enumerate() {
+            if nesting[i].is_hole() {
+                continue
+            }
+
+            let mut polygon = Polygon::default();
+            let start = polygon.add_ring(contour, first_vertex[i]);
+
+            // Holes are bridged from right to left, so that each bridge
+            // only crosses area already joined to the outer ring.
+            let mut holes: Vec<usize> = (0..contours.len())
+                .filter(|&j| nesting[j].is_hole() && nesting[j].parent == Some(i))
+                .collect();
+            holes.sort_by(|&a, &b| max_x(&contours[b]).partial_cmp(&max_x(&contours[a])).unwrap_or(Ordering::Equal));
+
+            for j in holes {
+                let hole = polygon.add_ring(&contours[j], first_vertex[j]);
+                polygon.bridge(start, hole);
+            }
+
+            polygon.clip_ears(start, &mut mesh.indices);
+        }
+
+        mesh
+    }
+
+    /// Builds a solid from the contours, with the front face at `z = 0`,
+    /// the back face at `z = -depth`, and walls joining their edges.
+    pub fn extrude(contours: &[Contour], depth: f32) -> Mesh {
+        let mut mesh = Mesh::fill(contours);
+        let front_len = mesh.vertices.len() as u32;
+        let front_indices = mesh.indices.len();
+
+        // The back face faces down the z axis, so its triangles are reversed.
+        for i in 0..front_len as usize {
+            let [x, y, _] = mesh.vertices[i];
+            mesh.vertices.push([x, y, -depth]);
+        }
+        for i in 0..front_indices / 3 {
+            let (a, b, c) = (mesh.indices[3 * i], mesh.indices[3 * i + 1], mesh.indices[3 * i + 2]);
+            mesh.indices.extend_from_slice(&[a + front_len, c + front_len, b + front_len]);
+        }
+
+        // The vertices of `fill` follow the oriented contours, where the
+        // filled area is always to the left of each edge.
+        let mut first = 0;
+        for contour in oriented(contours) {
+            let n = contour.points.len() as u32;
+            for i in 0..n {
+                let (a, b) = (first + i, first + (i + 1) % n);
+                let (a_back, b_back) = (a + front_len, b + front_len);
+                mesh.indices.extend_from_slice(&[a, a_back, b_back, a, b_back, b]);
+            }
+            first += n;
+        }
+
+        mesh
+    }
+
+    pub fn num_triangles(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    pub fn triangles<'a>(&'a self) -> impl Iterator<Item = [[f32; 3]; 3]> + 'a {
+        self.indices.chunks(3).map(move |t| {
+            [self.vertices[t[0] as usize], self.vertices[t[1] as usize], self.vertices[t[2] as usize]]
+        })
+    }
+}
+
+/// Orients contours for non-zero filling: counter-clockwise around
+/// filled areas, and clockwise around holes.
+fn oriented(contours: &[Contour]) -> Vec<Contour> {
+    let nesting = nesting(contours);
+    contours.iter().zip(&nesting).map(|(contour, nesting)| {
+        let mut contour = contour.clone();
+        if contour.orientation() != nesting.orientation() {
+            contour.reverse();
+        }
+        contour
+    }).collect()
+}
+
+fn max_x(contour: &Contour) -> f32 {
+    contour.points.iter().fold(f32::NEG_INFINITY, |x, p| x.max(p.0))
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Node {
+    vertex: u32,
+    p: (f32, f32),
+    prev: usize,
+    next: usize,
+}
+
+/// A polygon as a linked list of vertices, which ear clipping removes
+/// one at a time.  Bridges to holes duplicate their end vertices.
+#[derive(Debug, Default)]
+struct Polygon {
+    nodes: Vec<Node>,
+}
+
+impl Polygon {
+    fn add_ring(&mut self, contour: &Contour, first_vertex: u32) -> usize {
+        let start = self.nodes.len();
+        let n = contour.points.len();
+        for (i, &p) in contour.points.iter().enumerate() {
+            self.nodes.push(Node {
+                vertex: first_vertex + i as u32,
+                p,
+                prev: start + (i + n - 1) % n,
+                next: start + (i + 1) % n,
+            });
+        }
+        start
+    }
+
+    fn prev(&self, node: usize) -> usize {
+        self.nodes[node].prev
+    }
+
+    fn next(&self, node: usize) -> usize {
+        self.nodes[node].next
+    }
+
+    fn point(&self, node: usize) -> (f32, f32) {
+        self.nodes[node].p
+    }
+
+    fn ring_len(&self, start: usize) -> usize {
+        let (mut len, mut node) = (1, self.next(start));
+        while node != start {
+            len += 1;
+            node = self.next(node);
+        }
+        len
+    }
+
+    fn remove(&mut self, node: usize) {
+        let (prev, next) = (self.prev(node), self.next(node));
+        self.nodes[prev].next = next;
+        self.nodes[next].prev = prev;
+    }
+
+    /// Joins a hole to the ring containing `start`, by a pair of edges
+    /// from the hole's rightmost point to a vertex visible from it.
+    fn bridge(&mut self, start: usize, hole: usize) {
+        let mut m = hole;
+        let mut node = self.next(hole);
+        while node != hole {
+            if self.point(node).0 > self.point(m).0 {
+                m = node;
+            }
+            node = self.next(node);
+        }
+
+        if let Some(target) = self.find_bridge(start, m) {
+            self.split(target, m);
+        }
+    }
+
+    /// Casts a ray to the right of `m`, then picks the vertex of the
+    /// first edge it hits, or a reflex vertex blocking the way to it.
+    fn find_bridge(&self, start: usize, m: usize) -> Option<usize> {
+        let h = self.point(m);
+        let mut nearest_x = f32::INFINITY;
+        let mut candidate = None;
+
+        let mut p = start;
+        loop {
+            let q = self.next(p);
+            let (a, b) = (self.point(p), self.point(q));
+            // Edges with the filled area to the left go up on the right side.
+            if a.1 <= h.1 && h.1 <= b.1 && a.1 != b.1 {
+                let x = a.0 + (h.1 - a.1) * (b.0 - a.0) / (b.1 - a.1);
+                if x >= h.0 && x < nearest_x {
+                    nearest_x = x;
+                    if x == h.0 {
+                        return Some(if h.1 == a.1 { p } else if h.1 == b.1 { q } else { p });
+                    }
+                    candidate = Some(if a.0 > b.0 { p } else { q });
+                }
+            }
+            p = q;
+            if p == start {
+                break
+            }
+        }
+
+        let mut best = try_opt!(candidate);
+        let c = self.point(best);
+        let hit = (nearest_x, h.1);
+        let mut best_tan = f32::INFINITY;
+
+        let mut p = start;
+        loop {
+            let v = self.point(p);
+            if p != best && v.0 >= h.0 && v.0 <= c.0 && v != c && in_triangle(h, hit, c, v)
+                && self.locally_inside(p, h) {
+                let tan = (h.1 - v.1).abs() / (v.0 - h.0);
+                if tan < best_tan || (tan == best_tan && v.0 < self.point(best).0) {
+                    best = p;
+                    best_tan = tan;
+                }
+            }
+            p = self.next(p);
+            if p == start {
+                break
+            }
+        }
+
+        Some(best)
+    }
+
+    /// Whether the direction from a node towards `p` starts inside the polygon.
+    fn locally_inside(&self, node: usize, p: (f32, f32)) -> bool {
+        let (prev, a, next) = (self.point(self.prev(node)), self.point(node), self.point(self.next(node)));
+        if cross(prev, a, next) >= 0.0 {
+            cross(a, next, p) >= 0.0 && cross(prev, a, p) >= 0.0
+        } else {
+            cross(a, next, p) >= 0.0 || cross(prev, a, p) >= 0.0
+        }
+    }
+
+    /// Connects `a` to `b` and back, duplicating both.
+    fn split(&mut self, a: usize, b: usize) {
+        let (a_next, b_prev) = (self.next(a), self.prev(b));
+        let a2 = self.nodes.len();
+        let b2 = a2 + 1;
+        let (node_a, node_b) = (self.nodes[a], self.nodes[b]);
+        self.nodes.push(Node { prev: b2, next: a_next, ..node_a });
+        self.nodes.push(Node { prev: b_prev, next: a2, ..node_b });
+
+        self.nodes[a].next = b;
+        self.nodes[b].prev = a;
+        self.nodes[a_next].prev = a2;
+        self.nodes[b_prev].next = b2;
+    }
+
+    fn is_ear(&self, ear: usize) -> bool {
+        let (prev, next) = (self.prev(ear), self.next(ear));
+        let (a, b, c) = (self.point(prev), self.point(ear), self.point(next));
+        if cross(a, b, c) <= 0.0 {
+            return false
+        }
+
+        // Only reflex vertices can reach into a convex corner.
+        let mut node = self.next(next);
+        while node != prev {
+            let p = self.point(node);
+            if p != a && p != b && p != c && in_triangle(a, b, c, p)
+                && cross(self.point(self.prev(node)), p, self.point(self.next(node))) <= 0.0 {
+                return false
+            }
+            node = self.next(node);
+        }
+        true
+    }
+
+    /// Cuts off convex corners with no vertices inside until only a
+    /// triangle is left.
+    fn clip_ears(&mut self, start: usize, indices: &mut Vec<u32>) {
+        let mut remaining = self.ring_len(start);
+        let mut ear = start;
+        let mut stop = ear;
+
+        while remaining > 2 {
+            let (prev, next) = (self.prev(ear), self.next(ear));
+            if self.is_ear(ear) {
+                indices.extend_from_slice(&[self.nodes[prev].vertex, self.nodes[ear].vertex,
+                                            self.nodes[next].vertex]);
+                self.remove(ear);
+                remaining -= 1;
+                ear = next;
+                stop = ear;
+                continue
+            }
+
+            ear = next;
+            if ear != stop {
+                continue
+            }
+
+            // No ear was found.  Drop a vertex adding no area, or cut off
+            // a corner regardless, which keeps broken outlines finite.
+            let mut node = ear;
+            loop {
+                let (a, b, c) = (self.point(self.prev(node)), self.point(node), self.point(self.next(node)));
+                if cross(a, b, c) == 0.0 {
+                    break
+                }
+                node = self.next(node);
+                if node == ear {
+                    indices.extend_from_slice(&[self.nodes[self.prev(node)].vertex, self.nodes[node].vertex,
+                                                self.nodes[self.next(node)].vertex]);
+                    break
+                }
+            }
+
+            ear = self.next(node);
+            stop = ear;
+            self.remove(node);
+            remaining -= 1;
+        }
+    }
+}
+
+/// Whether `p` lies inside or on the edge of a triangle of either orientation.
+fn in_triangle(a: (f32, f32), b: (f32, f32), c: (f32, f32), p: (f32, f32)) -> bool {
+    let (d0, d1, d2) = (cross(a, b, p), cross(b, c, p), cross(c, a, p));
+    (d0 >= 0.0 && d1 >= 0.0 && d2 >= 0.0) || (d0 <= 0.0 && d1 <= 0.0 && d2 <= 0.0)
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use geometry::{self, Contour, Mesh, Orientation};
+
+    fn rect(x0: f32, y0: f32, x1: f32, y1: f32) -> Contour {
+        Contour { points: vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)] }
+    }
+
+    fn area(mesh: &Mesh) -> f32 {
+        mesh.triangles().map(|[a, b, c]| {
+            ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0
+        }).sum()
+    }
+
+    #[test]
+    fn flatten() {
+        let mut points = Vec::new();
+        geometry::flatten_quad((0.0, 0.0), (50.0, 100.0), (100.0, 0.0), 1.0, |p| points.push(p));
+
+        // The peak of the curve is 50 units from its chord.
+        assert_eq!(points.len(), 8);
+        assert_eq!(points.last(), Some(&(100.0, 0.0)));
+
+        let mut points = Vec::new();
+        geometry::flatten_cubic((0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0), 2.0, |p| points.push(p));
+        assert_eq!(points, vec![(1.0, 0.0)]);
+
+        // Curves are never split into more than the maximum number of
+        // lines, nor by an invalid tolerance.
+        for &tolerance in &[0.0, -1.0, f32::NAN] {
+            let mut count = 0;
+            geometry::flatten_quad((0.0, 0.0), (50.0, 100.0), (100.0, 0.0), tolerance, |_| count += 1);
+            assert!((1..=geometry::MAX_SEGMENTS).contains(&count));
+        }
+    }
+
+    #[test]
+    fn fill() {
+        // A square with a square hole, both clockwise.
+        let mut outer = rect(0.0, 0.0, 10.0, 10.0);
+        outer.reverse();
+        let mut hole = rect(2.0, 3.0, 6.0, 7.0);
+        hole.reverse();
+        assert_eq!(outer.orientation(), Orientation::Clockwise);
+        assert_eq!(outer.signed_area(), -100.0);
+
+        let contours = vec![outer, hole];
+        let nesting = geometry::nesting(&contours);
+        assert!(!nesting[0].is_hole());
+        assert!(nesting[1].is_hole());
+        assert_eq!(nesting[1].parent, Some(0));
+
+        let mesh = Mesh::fill(&contours);
+        assert_eq!(mesh.vertices.len(), 8);
+        assert_eq!(mesh.num_triangles(), 8);
+        assert_eq!(area(&mesh), 84.0);
+
+        // Two walls per edge, and both faces.
+        let solid = Mesh::extrude(&contours, 5.0);
+        assert_eq!(solid.vertices.len(), 16);
+        assert_eq!(solid.num_triangles(), 8 + 8 + 16);
+    }
+
+    #[test]
+    fn glyph_mesh() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // 'A', 'o', 'B' and '%', some with holes or several islands.
+        for &glyph_id in &[36, 82, 37, 8] {
+            let contours = font.flatten_glyph(glyph_id, 0.5).expect("unable to flatten glyph");
+            let nesting = geometry::nesting(&contours);
+            let holes = nesting.iter().filter(|n| n.is_hole()).count();
+            let expected: f32 = nesting.iter().zip(&contours).map(|(nesting, c)| {
+                if nesting.is_hole() { -c.signed_area().abs() } else { c.signed_area().abs() }
+            }).sum();
+
+            // Every bridge to a hole adds two triangles.
+            let mesh = Mesh::fill(&contours);
+            let points: usize = contours.iter().map(|c| c.points.len()).sum();
+            assert_eq!(mesh.num_triangles(), points + 4 * holes - 2 * contours.len());
+            assert!((area(&mesh) - expected).abs() < 1.0, "glyph {}", glyph_id);
+        }
+
+        assert_eq!(font.flatten_glyph(3, 0.5).map(|c| c.len()), Some(0));
+        assert!(font.flatten_glyph(36, 0.0).is_none());
+        assert!(font.flatten_glyph(36, f32::NAN).is_none());
+    }
+}
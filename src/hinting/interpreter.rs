@@ -0,0 +1,1424 @@
+use decode::{Error, Result};
+use hinting::math::{self, mul_div, mul14};
+use hinting::state::{GraphicsState, RoundState, Vector, Zone, TOUCHED_X, TOUCHED_Y};
+
+/// Bound on nested function calls.
+const MAX_CALL_DEPTH: u32 = 64;
+/// Bound on the instructions executed by a single program, guarding
+/// against loops that never terminate.
+const MAX_INSTRUCTIONS: usize = 1_000_000;
+
+/// The program being run, as some instructions behave differently
+/// depending on where they appear.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ProgramKind {
+    /// The font program, `fpgm`.
+    Font,
+    /// The control value program, `prep`.
+    ControlValue,
+    Glyph,
+}
+
+/// State of a font at a particular size, shared by every program run
+/// at that size.
+#[derive(Debug, Clone)]
+pub struct Instance<'a> {
+    pub functions: Vec<Option<&'a [u8]>>,
+    /// Definitions of otherwise unused opcodes.
+    pub instructions: Vec<Option<&'a [u8]>>,
+    /// The control value table, scaled to 26.6 pixels.
+    pub cvt: Vec<i32>,
+    pub storage: Vec<i32>,
+    pub twilight: Zone,
+    pub ppem: i32,
+    /// Scale from font units to 26.6 pixels, as 16.16.
+    pub scale: i32,
+    pub max_stack: usize,
+    /// Run in the subpixel (v40) mode, where the backwards compatibility
+    /// mode ignores horizontal movement unless the font opts out.
+    pub subpixel: bool,
+}
+
+/// Executes TrueType instructions against an instance and a glyph zone.
+pub struct Machine<'a: 'h, 'h> {
+    pub gs: GraphicsState,
+    pub stack: Vec<i32>,
+    pub instance: &'h mut Instance<'a>,
+    pub glyph: &'h mut Zone,
+    pub kind: ProgramKind,
+    pub is_composite: bool,
+    backward_compatibility: bool,
+    iup_x: bool,
+    iup_y: bool,
+    instruction_count: usize,
+}
+
+/// The number of bytes taken by the instruction at the start of `code`.
+fn instruction_len(code: &[u8]) -> Result<usize> {
+    let len = match code[0] {
+        // NPUSHB, NPUSHW
+        0x40 => 2 + *try_opt_code(code.get(1))? as usize,
+        0x41 => 2 + 2 * *try_opt_code(code.get(1))? as usize,
+        // PUSHB, PUSHW
+        op @ 0xB0..=0xB7 => 2 + (op - 0xB0) as usize,
+        op @ 0xB8..=0xBF => 1 + 2 * (op - 0xB7) as usize,
+        _ => 1,
+    };
+
+    verify!(len <= code.len());
+    Ok(len)
+}
+
+fn try_opt_code<T>(value: Option<T>) -> Result<T> {
+    value.ok_or(Error::UnexpectedEof)
+}
+
+/// Finds the end of a function definition, returning the offsets of
+/// the `ENDF` instruction and of the instruction following it.
+fn find_endf(code: &[u8], mut ip: usize) -> Result<(usize, usize)> {
+    while ip < code.len() {
+        match code[ip] {
+            0x2D => return Ok((ip, ip + 1)),
+            // Definitions may not nest.
+            0x2C | 0x89 => return Err(Error::InvalidData),
+            _ => ip += instruction_len(&code[ip..])?,
+        }
+    }
+
+    Err(Error::UnexpectedEof)
+}
+
+impl<'a: 'h, 'h> Machine<'a, 'h> {
+    pub fn new(gs: GraphicsState, instance: &'h mut Instance<'a>, glyph: &'h mut Zone,
+               kind: ProgramKind) -> Machine<'a, 'h> {
+        Machine {
+            gs,
+            stack: Vec::new(),
+            instance,
+            glyph,
+            kind,
+            is_composite: false,
+            backward_compatibility: false,
+            iup_x: false,
+            iup_y: false,
+            instruction_count: 0,
+        }
+    }
+
+    /// Runs a program from the start.
+    pub fn execute(&mut self, code: &'a [u8]) -> Result<()> {
+        // Fonts opt into native subpixel hinting from the control value program.
+        self.backward_compatibility = self.instance.subpixel && self.gs.instruct_control & 4 == 0;
+        self.iup_x = false;
+        self.iup_y = false;
+        self.instruction_count = 0;
+        self.stack.clear();
+
+        self.run(code, 0)
+    }
+
+    fn push(&mut self, value: i32) -> Result<()> {
+        verify!(self.stack.len() < self.instance.max_stack);
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<i32> {
+        self.stack.pop().ok_or(Error::InvalidData)
+    }
+
+    fn zone(&self, zone: u8) -> &Zone {
+        if zone == 0 { &self.instance.twilight } else { &*self.glyph }
+    }
+
+    fn zone_mut(&mut self, zone: u8) -> &mut Zone {
+        if zone == 0 { &mut self.instance.twilight } else { &mut *self.glyph }
+    }
+
+    fn point_index(&self, zone: u8, index: i32) -> Result<usize> {
+        verify!(index >= 0 && (index as usize) < self.zone(zone).len());
+        Ok(index as usize)
+    }
+
+    fn pop_point(&mut self, zone: u8) -> Result<usize> {
+        let index = self.pop()?;
+        self.point_index(zone, index)
+    }
+
+    fn cur(&self, zone: u8, index: usize) -> Result<Vector> {
+        self.zone(zone).cur.get(index).cloned().ok_or(Error::InvalidData)
+    }
+
+    fn org(&self, zone: u8, index: usize) -> Result<Vector> {
+        self.zone(zone).org.get(index).cloned().ok_or(Error::InvalidData)
+    }
+
+    fn orus(&self, zone: u8, index: usize) -> Result<Vector> {
+        self.zone(zone).orus.get(index).cloned().ok_or(Error::InvalidData)
+    }
+
+    fn in_twilight(&self) -> bool {
+        self.gs.zp0 == 0 && self.gs.zp1 == 0 && self.gs.zp2 == 0
+    }
+
+    fn cvt_index(&self, index: i32) -> Result<usize> {
+        verify!(index >= 0 && (index as usize) < self.instance.cvt.len());
+        Ok(index as usize)
+    }
+
+    fn round(&self, distance: i32) -> i32 {
+        self.gs.round_state.round(distance)
+    }
+
+    /// Moves a point so that its projection changes by `distance`, along
+    /// the freedom vector.  The backwards compatibility mode ignores
+    /// horizontal moves, and all moves once both axes are interpolated.
+    fn move_point(&mut self, zone: u8, index: usize, distance: i32, touch: bool) {
+        let movement = self.gs.movement(distance);
+        let fv = self.gs.freedom_vector;
+        self.shift_point(zone, index, movement, fv, touch);
+    }
+
+    fn shift_point(&mut self, zone: u8, index: usize, by: Vector, fv: Vector, touch: bool) {
+        let block_x = self.backward_compatibility;
+        let block_y = self.backward_compatibility && self.iup_x && self.iup_y;
+        let zone = self.zone_mut(zone);
+
+        if fv.x != 0 {
+            if !block_x {
+                zone.cur[index].x = zone.cur[index].x.wrapping_add(by.x);
+            }
+            if touch {
+                zone.flags[index] |= TOUCHED_X;
+            }
+        }
+
+        if fv.y != 0 {
+            if !block_y {
+                zone.cur[index].y = zone.cur[index].y.wrapping_add(by.y);
+            }
+            if touch {
+                zone.flags[index] |= TOUCHED_Y;
+            }
+        }
+    }
+
+    /// Moves the original position of a point, as done for twilight points.
+    fn move_original(&mut self, zone: u8, index: usize, distance: i32) {
+        let movement = self.gs.movement(distance);
+        let zone = self.zone_mut(zone);
+        zone.org[index].x = zone.org[index].x.wrapping_add(movement.x);
+        zone.org[index].y = zone.org[index].y.wrapping_add(movement.y);
+    }
+
+    fn set_vector_to_line(&mut self, opcode: u8, p2: i32, p1: i32, original: bool) -> Result<Vector> {
+        let p1 = self.point_index(self.gs.zp1, p1)?;
+        let p2 = self.point_index(self.gs.zp2, p2)?;
+        let (a, b) = if original {
+            (self.org(self.gs.zp1, p1)?, self.org(self.gs.zp2, p2)?)
+        } else {
+            (self.cur(self.gs.zp1, p1)?, self.cur(self.gs.zp2, p2)?)
+        };
+
+        let mut d = a.sub(&b);
+        let mut rotate = opcode & 1 != 0;
+        if d.x == 0 && d.y == 0 {
+            d = Vector::new(0x4000, 0);
+            rotate = false;
+        }
+
+        // The odd opcodes use a vector perpendicular to the line.
+        if rotate {
+            d = Vector::new(d.y.wrapping_neg(), d.x);
+        }
+
+        let (x, y) = math::normalize(d.x, d.y);
+        Ok(Vector::new(x, y))
+    }
+
+    /// The displacement of the reference point used by the shift
+    /// instructions, along with its zone and index.
+    fn point_displacement(&self, opcode: u8) -> Result<(Vector, u8, usize)> {
+        let (zone, point) = if opcode & 1 != 0 {
+            (self.gs.zp0, self.gs.rp1)
+        } else {
+            (self.gs.zp1, self.gs.rp2)
+        };
+
+        verify!(point < self.zone(zone).len());
+        let d = self.gs.project(&self.cur(zone, point)?.sub(&self.org(zone, point)?));
+        Ok((self.gs.movement(d), zone, point))
+    }
+
+    fn run(&mut self, code: &'a [u8], depth: u32) -> Result<()> {
+        verify!(depth <= MAX_CALL_DEPTH);
+        let mut ip = 0;
+
+        while ip < code.len() {
+            self.instruction_count += 1;
+            verify!(self.instruction_count <= MAX_INSTRUCTIONS);
+
+            let opcode = code[ip];
+            let len = instruction_len(&code[ip..])?;
+            let mut next = ip + len;
+
+            match opcode {
+                // SVTCA, SPVTCA, SFVTCA
+                0x00..=0x05 => {
+                    let axis = if opcode & 1 != 0 { Vector::x_axis() } else { Vector::y_axis() };
+                    if opcode < 0x04 {
+                        self.gs.projection_vector = axis;
+                        self.gs.dual_vector = axis;
+                    }
+                    if !(0x02..0x04).contains(&opcode) {
+                        self.gs.freedom_vector = axis;
+                    }
+                },
+
+                // SPVTL, SFVTL
+                0x06..=0x09 => {
+                    let p2 = self.pop()?;
+                    let p1 = self.pop()?;
+                    let v = self.set_vector_to_line(opcode, p2, p1, false)?;
+                    if opcode < 0x08 {
+                        self.gs.projection_vector = v;
+                        self.gs.dual_vector = v;
+                    } else {
+                        self.gs.freedom_vector = v;
+                    }
+                },
+
+                // SPVFS, SFVFS
+                0x0A | 0x0B => {
+                    let y = self.pop()?;
+                    let x = self.pop()?;
+                    let (x, y) = math::normalize((x as i16) as i32, (y as i16) as i32);
+                    if opcode == 0x0A {
+                        self.gs.projection_vector = Vector::new(x, y);
+                        self.gs.dual_vector = Vector::new(x, y);
+                    } else {
+                        self.gs.freedom_vector = Vector::new(x, y);
+                    }
+                },
+
+                // GPV, GFV
+                0x0C | 0x0D => {
+                    let v = if opcode == 0x0C { self.gs.projection_vector } else { self.gs.freedom_vector };
+                    self.push(v.x)?;
+                    self.push(v.y)?;
+                },
+
+                // SFVTPV
+                0x0E => self.gs.freedom_vector = self.gs.projection_vector,
+
+                // ISECT
+                0x0F => self.intersect()?,
+
+                // SRP0, SRP1, SRP2
+                0x10..=0x12 => {
+                    let p = self.pop()?;
+                    verify!(p >= 0);
+                    match opcode {
+                        0x10 => self.gs.rp0 = p as usize,
+                        0x11 => self.gs.rp1 = p as usize,
+                        _ => self.gs.rp2 = p as usize,
+                    }
+                },
+
+                // SZP0, SZP1, SZP2, SZPS
+                0x13..=0x16 => {
+                    let zone = self.pop()?;
+                    verify!(zone == 0 || zone == 1);
+                    let zone = zone as u8;
+                    match opcode {
+                        0x13 => self.gs.zp0 = zone,
+                        0x14 => self.gs.zp1 = zone,
+                        0x15 => self.gs.zp2 = zone,
+                        _ => {
+                            self.gs.zp0 = zone;
+                            self.gs.zp1 = zone;
+                            self.gs.zp2 = zone;
+                        },
+                    }
+                },
+
+                // SLOOP
+                0x17 => {
+                    let n = self.pop()?;
+                    verify!(n >= 0);
+                    self.gs.loop_count = n.min(0xFFFF);
+                },
+
+                0x18 => self.gs.round_state = RoundState::ToGrid,
+                0x19 => self.gs.round_state = RoundState::ToHalfGrid,
+                0x3D => self.gs.round_state = RoundState::ToDoubleGrid,
+                0x7A => self.gs.round_state = RoundState::Off,
+                0x7C => self.gs.round_state = RoundState::UpToGrid,
+                0x7D => self.gs.round_state = RoundState::DownToGrid,
+
+                // SROUND, S45ROUND
+                0x76 | 0x77 => {
+                    let selector = self.pop()?;
+                    self.gs.round_state = RoundState::super_round(selector, opcode == 0x77);
+                },
+
+                // SMD
+                0x1A => self.gs.min_distance = self.pop()?,
+
+                // ELSE, reached at the end of the taken branch.
+                0x1B => next = skip_conditional(code, next, true)?,
+
+                // JMPR
+                0x1C => {
+                    let offset = self.pop()?;
+                    next = jump(code, ip, offset)?;
+                },
+
+                // SCVTCI, SSWCI, SSW
+                0x1D => self.gs.control_value_cutin = self.pop()?,
+                0x1E => self.gs.single_width_cutin = self.pop()?,
+                0x1F => {
+                    let value = self.pop()?;
+                    self.gs.single_width_value = math::mul_fix(value, self.instance.scale);
+                },
+
+                // DUP
+                0x20 => {
+                    let v = *self.stack.last().ok_or(Error::InvalidData)?;
+                    self.push(v)?;
+                },
+
+                // POP
+                0x21 => { self.pop()?; },
+
+                // CLEAR
+                0x22 => self.stack.clear(),
+
+                // SWAP
+                0x23 => {
+                    let a = self.pop()?;
+                    let b = self.pop()?;
+                    self.push(a)?;
+                    self.push(b)?;
+                },
+
+                // DEPTH
+                0x24 => {
+                    let depth = self.stack.len() as i32;
+                    self.push(depth)?;
+                },
+
+                // CINDEX, MINDEX
+                0x25 | 0x26 => {
+                    let k = self.pop()?;
+                    verify!(k > 0 && k as usize <= self.stack.len());
+                    let index = self.stack.len() - k as usize;
+                    let v = if opcode == 0x25 { self.stack[index] } else { self.stack.remove(index) };
+                    self.push(v)?;
+                },
+
+                // ALIGNPTS
+                0x27 => {
+                    let p2 = self.pop_point(self.gs.zp0)?;
+                    let p1 = self.pop_point(self.gs.zp1)?;
+                    let d = self.gs.project(&self.cur(self.gs.zp0, p2)?.sub(&self.cur(self.gs.zp1, p1)?)) / 2;
+                    let (zp0, zp1) = (self.gs.zp0, self.gs.zp1);
+                    self.move_point(zp1, p1, d, true);
+                    self.move_point(zp0, p2, d.wrapping_neg(), true);
+                },
+
+                // UTP
+                0x29 => {
+                    let zone = self.gs.zp0;
+                    let p = self.pop_point(zone)?;
+                    let mut mask = 0xFF;
+                    if self.gs.freedom_vector.x != 0 {
+                        mask &= !TOUCHED_X;
+                    }
+                    if self.gs.freedom_vector.y != 0 {
+                        mask &= !TOUCHED_Y;
+                    }
+                    self.zone_mut(zone).flags[p] &= mask;
+                },
+
+                // LOOPCALL, CALL
+                0x2A | 0x2B => {
+                    let f = self.pop()?;
+                    let count = if opcode == 0x2A { self.pop()? } else { 1 };
+                    verify!(f >= 0);
+                    let body = match self.instance.functions.get(f as usize) {
+                        Some(&Some(body)) => body,
+                        _ => return Err(Error::InvalidData),
+                    };
+
+                    // Each call counts as an instruction, so that looping over
+                    // an empty function still runs out of budget.
+                    let count = count.max(0) as usize;
+                    self.instruction_count = self.instruction_count.saturating_add(count);
+                    verify!(self.instruction_count <= MAX_INSTRUCTIONS);
+
+                    for _ in 0..count {
+                        self.run(body, depth + 1)?;
+                    }
+                },
+
+                // FDEF
+                0x2C => {
+                    verify!(self.kind != ProgramKind::Glyph);
+                    let f = self.pop()?;
+                    verify!((0..0x10000).contains(&f));
+                    let (end, after) = find_endf(code, next)?;
+                    let f = f as usize;
+                    if f >= self.instance.functions.len() {
+                        self.instance.functions.resize(f + 1, None);
+                    }
+                    self.instance.functions[f] = Some(&code[next..end]);
+                    next = after;
+                },
+
+                // ENDF outside of a definition ends the program.
+                0x2D => return Ok(()),
+
+                // MDAP
+                0x2E | 0x2F => {
+                    let zone = self.gs.zp0;
+                    let p = self.pop_point(zone)?;
+                    let d = if opcode & 1 != 0 {
+                        let d = self.gs.project(&self.cur(zone, p)?);
+                        self.round(d).wrapping_sub(d)
+                    } else {
+                        0
+                    };
+                    self.move_point(zone, p, d, true);
+                    self.gs.rp0 = p;
+                    self.gs.rp1 = p;
+                },
+
+                // IUP
+                0x30 | 0x31 => self.interpolate_untouched(opcode & 1 != 0),
+
+                // SHP
+                0x32 | 0x33 => {
+                    let (by, _, _) = self.point_displacement(opcode)?;
+                    let fv = self.gs.freedom_vector;
+                    for _ in 0..self.gs.loop_count {
+                        let zone = self.gs.zp2;
+                        let p = self.pop_point(zone)?;
+                        self.shift_point(zone, p, by, fv, true);
+                    }
+                    self.gs.loop_count = 1;
+                },
+
+                // SHC
+                0x34 | 0x35 => {
+                    let (by, ref_zone, ref_point) = self.point_displacement(opcode)?;
+                    let c = self.pop()?;
+                    let zone = self.gs.zp2;
+                    let end_points = &self.zone(zone).end_points;
+                    verify!(c >= 0 && (c as usize) < end_points.len());
+                    let start = if c == 0 { 0 } else { end_points[c as usize - 1] + 1 };
+                    let end = end_points[c as usize];
+                    verify!(end < self.zone(zone).len());
+
+                    let fv = self.gs.freedom_vector;
+                    for p in start..end + 1 {
+                        if zone != ref_zone || p != ref_point {
+                            self.shift_point(zone, p, by, fv, true);
+                        }
+                    }
+                },
+
+                // SHZ
+                0x36 | 0x37 => {
+                    let (by, ref_zone, ref_point) = self.point_displacement(opcode)?;
+                    let e = self.pop()?;
+                    verify!(e == 0 || e == 1);
+
+                    // Phantom points are not shifted.
+                    let zone = self.gs.zp2;
+                    let limit = if zone == 0 {
+                        self.zone(zone).len()
+                    } else {
+                        self.zone(zone).num_contour_points()
+                    };
+
+                    let fv = self.gs.freedom_vector;
+                    for p in 0..limit {
+                        if zone != ref_zone || p != ref_point {
+                            self.shift_point(zone, p, by, fv, false);
+                        }
+                    }
+                },
+
+                // SHPIX
+                0x38 => {
+                    let amount = self.pop()?;
+                    let fv = self.gs.freedom_vector;
+                    let by = Vector::new(mul14(amount, fv.x), mul14(amount, fv.y));
+                    let in_twilight = self.in_twilight();
+
+                    for _ in 0..self.gs.loop_count {
+                        let zone = self.gs.zp2;
+                        let p = self.pop_point(zone)?;
+
+                        if self.backward_compatibility {
+                            // Only vertical moves of points already moved
+                            // vertically are kept, as with the delta instructions.
+                            let touched_y = self.zone(zone).flags[p] & TOUCHED_Y != 0;
+                            if in_twilight || (!(self.iup_x && self.iup_y) &&
+                                               ((self.is_composite && fv.y != 0) || touched_y)) {
+                                self.shift_point(zone, p, Vector::new(0, by.y), fv, true);
+                            }
+                        } else {
+                            self.shift_point(zone, p, by, fv, true);
+                        }
+                    }
+                    self.gs.loop_count = 1;
+                },
+
+                // IP
+                0x39 => self.interpolate_point()?,
+
+                // MSIRP
+                0x3A | 0x3B => {
+                    let distance = self.pop()?;
+                    let (zp0, zp1) = (self.gs.zp0, self.gs.zp1);
+                    let p = self.pop_point(zp1)?;
+                    let rp0 = self.gs.rp0;
+                    verify!(rp0 < self.zone(zp0).len());
+
+                    if zp1 == 0 {
+                        let origin = self.org(zp0, rp0)?;
+                        self.zone_mut(zp1).org[p] = origin;
+                        self.move_original(zp1, p, distance);
+                        let org = self.org(zp1, p)?;
+                        self.zone_mut(zp1).cur[p] = org;
+                    }
+
+                    let d = self.gs.project(&self.cur(zp1, p)?.sub(&self.cur(zp0, rp0)?));
+                    self.move_point(zp1, p, distance.wrapping_sub(d), true);
+                    self.gs.rp1 = rp0;
+                    self.gs.rp2 = p;
+                    if opcode & 1 != 0 {
+                        self.gs.rp0 = p;
+                    }
+                },
+
+                // ALIGNRP
+                0x3C => {
+                    let (zp0, zp1) = (self.gs.zp0, self.gs.zp1);
+                    let reference = self.cur(zp0, self.gs.rp0)?;
+                    for _ in 0..self.gs.loop_count {
+                        let p = self.pop_point(zp1)?;
+                        let d = self.gs.project(&self.cur(zp1, p)?.sub(&reference));
+                        self.move_point(zp1, p, d.wrapping_neg(), true);
+                    }
+                    self.gs.loop_count = 1;
+                },
+
+                // MIAP
+                0x3E | 0x3F => {
+                    let cvt = self.pop()?;
+                    let zone = self.gs.zp0;
+                    let p = self.pop_point(zone)?;
+                    let mut distance = self.instance.cvt[self.cvt_index(cvt)?];
+
+                    if zone == 0 {
+                        let fv = self.gs.freedom_vector;
+                        let position = Vector::new(mul14(distance, fv.x), mul14(distance, fv.y));
+                        let zone = self.zone_mut(0);
+                        zone.org[p] = position;
+                        zone.cur[p] = position;
+                    }
+
+                    let original = self.gs.project(&self.cur(zone, p)?);
+                    if opcode & 1 != 0 {
+                        if (distance.wrapping_sub(original)).abs() > self.gs.control_value_cutin {
+                            distance = original;
+                        }
+                        distance = self.round(distance);
+                    }
+
+                    self.move_point(zone, p, distance.wrapping_sub(original), true);
+                    self.gs.rp0 = p;
+                    self.gs.rp1 = p;
+                },
+
+                // NPUSHB, NPUSHW, PUSHB, PUSHW
+                0x40 | 0x41 | 0xB0..=0xBF => {
+                    let (start, words) = match opcode {
+                        0x40 => (ip + 2, false),
+                        0x41 => (ip + 2, true),
+                        0xB0..=0xB7 => (ip + 1, false),
+                        _ => (ip + 1, true),
+                    };
+
+                    if words {
+                        for w in code[start..next].chunks(2) {
+                            self.push((((w[0] as u16) << 8 | w[1] as u16) as i16) as i32)?;
+                        }
+                    } else {
+                        for &b in &code[start..next] {
+                            self.push(b as i32)?;
+                        }
+                    }
+                },
+
+                // WS
+                0x42 => {
+                    let value = self.pop()?;
+                    let index = self.pop()?;
+                    verify!(index >= 0 && (index as usize) < self.instance.storage.len());
+                    self.instance.storage[index as usize] = value;
+                },
+
+                // RS
+                0x43 => {
+                    let index = self.pop()?;
+                    verify!(index >= 0 && (index as usize) < self.instance.storage.len());
+                    let value = self.instance.storage[index as usize];
+                    self.push(value)?;
+                },
+
+                // WCVTP, WCVTF
+                0x44 | 0x70 => {
+                    let value = self.pop()?;
+                    let index = self.pop()?;
+                    let index = self.cvt_index(index)?;
+                    self.instance.cvt[index] = if opcode == 0x70 {
+                        math::mul_fix(value, self.instance.scale)
+                    } else {
+                        value
+                    };
+                },
+
+                // RCVT
+                0x45 => {
+                    let index = self.pop()?;
+                    let value = self.instance.cvt[self.cvt_index(index)?];
+                    self.push(value)?;
+                },
+
+                // GC
+                0x46 | 0x47 => {
+                    let zone = self.gs.zp2;
+                    let p = self.pop_point(zone)?;
+                    let value = if opcode == 0x46 {
+                        self.gs.project(&self.cur(zone, p)?)
+                    } else {
+                        self.gs.dual_project(&self.org(zone, p)?)
+                    };
+                    self.push(value)?;
+                },
+
+                // SCFS
+                0x48 => {
+                    let value = self.pop()?;
+                    let zone = self.gs.zp2;
+                    let p = self.pop_point(zone)?;
+                    let d = self.gs.project(&self.cur(zone, p)?);
+                    self.move_point(zone, p, value.wrapping_sub(d), true);
+                    if zone == 0 {
+                        let cur = self.cur(zone, p)?;
+                        self.zone_mut(zone).org[p] = cur;
+                    }
+                },
+
+                // MD
+                0x49 | 0x4A => {
+                    let (zp0, zp1) = (self.gs.zp0, self.gs.zp1);
+                    let p2 = self.pop_point(zp1)?;
+                    let p1 = self.pop_point(zp0)?;
+                    let d = if opcode == 0x49 {
+                        self.gs.project(&self.cur(zp0, p1)?.sub(&self.cur(zp1, p2)?))
+                    } else {
+                        self.gs.dual_project(&self.org(zp0, p1)?.sub(&self.org(zp1, p2)?))
+                    };
+                    self.push(d)?;
+                },
+
+                // MPPEM, MPS
+                0x4B | 0x4C => {
+                    let ppem = self.instance.ppem;
+                    self.push(ppem)?;
+                },
+
+                // FLIPON, FLIPOFF
+                0x4D => self.gs.auto_flip = true,
+                0x4E => self.gs.auto_flip = false,
+
+                // DEBUG, SANGW, AA, SCANCTRL, SCANTYPE
+                0x4F | 0x7E | 0x7F | 0x8D => { self.pop()?; },
+                0x85 => {
+                    let control = self.pop()?;
+                    self.gs.scan_control = control & 0xFF != 0;
+                },
+
+                // LT, LTEQ, GT, GTEQ, EQ, NEQ, AND, OR
+                0x50..=0x55 | 0x5A | 0x5B => {
+                    let b = self.pop()?;
+                    let a = self.pop()?;
+                    let result = match opcode {
+                        0x50 => a < b,
+                        0x51 => a <= b,
+                        0x52 => a > b,
+                        0x53 => a >= b,
+                        0x54 => a == b,
+                        0x55 => a != b,
+                        0x5A => a != 0 && b != 0,
+                        _ => a != 0 || b != 0,
+                    };
+                    self.push(result as i32)?;
+                },
+
+                // ODD, EVEN
+                0x56 | 0x57 => {
+                    let v = self.pop()?;
+                    let odd = self.round(v) & 127 == 64;
+                    self.push((odd == (opcode == 0x56)) as i32)?;
+                },
+
+                // IF
+                0x58 => {
+                    if self.pop()? == 0 {
+                        next = skip_conditional(code, next, false)?;
+                    }
+                },
+
+                // EIF
+                0x59 => { },
+
+                // NOT
+                0x5C => {
+                    let v = self.pop()?;
+                    self.push((v == 0) as i32)?;
+                },
+
+                // DELTAP1, DELTAP2, DELTAP3
+                0x5D | 0x71 | 0x72 => self.delta_point(opcode)?,
+
+                // DELTAC1, DELTAC2, DELTAC3
+                0x73..=0x75 => self.delta_cvt(opcode)?,
+
+                // SDB, SDS
+                0x5E => self.gs.delta_base = self.pop()?,
+                0x5F => {
+                    let shift = self.pop()?;
+                    verify!((0..=6).contains(&shift));
+                    self.gs.delta_shift = shift;
+                },
+
+                // ADD, SUB, DIV, MUL, MAX, MIN
+                0x60..=0x63 | 0x8B | 0x8C => {
+                    let b = self.pop()?;
+                    let a = self.pop()?;
+                    let result = match opcode {
+                        0x60 => a.wrapping_add(b),
+                        0x61 => a.wrapping_sub(b),
+                        0x62 => {
+                            // Division truncates, unlike multiplication.
+                            verify!(b != 0);
+                            (a as i64 * 64 / b as i64) as i32
+                        },
+                        0x63 => mul_div(a, b, 64),
+                        0x8B => a.max(b),
+                        _ => a.min(b),
+                    };
+                    self.push(result)?;
+                },
+
+                // ABS, NEG, FLOOR, CEILING, ROUND, NROUND
+                0x64..=0x6F => {
+                    let v = self.pop()?;
+                    let result = match opcode {
+                        0x64 => v.wrapping_abs(),
+                        0x65 => v.wrapping_neg(),
+                        0x66 => math::floor(v),
+                        0x67 => math::ceil(v),
+                        0x68..=0x6B => self.round(v),
+                        _ => v,
+                    };
+                    self.push(result)?;
+                },
+
+                // JROT, JROF
+                0x78 | 0x79 => {
+                    let condition = self.pop()?;
+                    let offset = self.pop()?;
+                    if (condition != 0) == (opcode == 0x78) {
+                        next = jump(code, ip, offset)?;
+                    }
+                },
+
+                // FLIPPT
+                0x80 => {
+                    for _ in 0..self.gs.loop_count {
+                        let p = self.pop_point(1)?;
+                        self.glyph.on_curve[p] = !self.glyph.on_curve[p];
+                    }
+                    self.gs.loop_count = 1;
+                },
+
+                // FLIPRGON, FLIPRGOFF
+                0x81 | 0x82 => {
+                    let high = self.pop_point(1)?;
+                    let low = self.pop_point(1)?;
+                    for p in low..high + 1 {
+                        self.glyph.on_curve[p] = opcode == 0x81;
+                    }
+                },
+
+                // SDPVTL
+                0x86 | 0x87 => {
+                    let p2 = self.pop()?;
+                    let p1 = self.pop()?;
+                    self.gs.dual_vector = self.set_vector_to_line(opcode, p2, p1, true)?;
+                    self.gs.projection_vector = self.set_vector_to_line(opcode, p2, p1, false)?;
+                },
+
+                // GETINFO
+                0x88 => {
+                    let selector = self.pop()?;
+                    let info = self.get_info(selector);
+                    self.push(info)?;
+                },
+
+                // IDEF
+                0x89 => {
+                    verify!(self.kind != ProgramKind::Glyph);
+                    let op = self.pop()?;
+                    verify!((0..256).contains(&op));
+                    let (end, after) = find_endf(code, next)?;
+                    self.instance.instructions[op as usize] = Some(&code[next..end]);
+                    next = after;
+                },
+
+                // ROLL
+                0x8A => {
+                    let a = self.pop()?;
+                    let b = self.pop()?;
+                    let c = self.pop()?;
+                    self.push(b)?;
+                    self.push(a)?;
+                    self.push(c)?;
+                },
+
+                // INSTCTRL
+                0x8E => {
+                    let selector = self.pop()?;
+                    let value = self.pop()?;
+                    verify!((1..=3).contains(&selector));
+
+                    // Only the control value program may change it.
+                    if self.kind == ProgramKind::ControlValue {
+                        let flag = 1 << (selector - 1);
+                        self.gs.instruct_control &= !flag;
+                        if value != 0 {
+                            self.gs.instruct_control |= flag;
+                        }
+                    }
+                },
+
+                // MDRP
+                0xC0..=0xDF => self.move_direct_relative(opcode)?,
+
+                // MIRP
+                0xE0..=0xFF => self.move_indirect_relative(opcode)?,
+
+                _ => {
+                    let body = match self.instance.instructions[opcode as usize] {
+                        Some(body) => body,
+                        None => return Err(Error::InvalidData),
+                    };
+                    self.run(body, depth + 1)?;
+                },
+            }
+
+            ip = next;
+        }
+
+        Ok(())
+    }
+
+    fn get_info(&self, selector: i32) -> i32 {
+        let mut info = 0;
+
+        if selector & 1 != 0 {
+            info |= if self.instance.subpixel { 40 } else { 35 };
+        }
+
+        if self.instance.subpixel {
+            // Subpixel hinting, subpixel positioning and symmetric smoothing.
+            if selector & 64 != 0 {
+                info |= 1 << 13;
+            }
+            if selector & 1024 != 0 {
+                info |= 1 << 17;
+            }
+            if selector & 2048 != 0 {
+                info |= 1 << 18;
+            }
+        } else if selector & 32 != 0 {
+            // Grayscale rendering.
+            info |= 1 << 12;
+        }
+
+        info
+    }
+
+    fn intersect(&mut self) -> Result<()> {
+        let (zp0, zp1, zp2) = (self.gs.zp0, self.gs.zp1, self.gs.zp2);
+        let b1 = self.pop_point(zp0)?;
+        let b0 = self.pop_point(zp0)?;
+        let a1 = self.pop_point(zp1)?;
+        let a0 = self.pop_point(zp1)?;
+        let p = self.pop_point(zp2)?;
+
+        let (a0, a1) = (self.cur(zp1, a0)?, self.cur(zp1, a1)?);
+        let (b0, b1) = (self.cur(zp0, b0)?, self.cur(zp0, b1)?);
+
+        let (dbx, dby) = (b1.x - b0.x, b1.y - b0.y);
+        let (dax, day) = (a1.x - a0.x, a1.y - a0.y);
+        let (dx, dy) = (b0.x - a0.x, b0.y - a0.y);
+
+        let discriminant = mul_div(dax, -dby, 0x40) + mul_div(day, dbx, 0x40);
+        let dot_product = mul_div(dax, dbx, 0x40) + mul_div(day, dby, 0x40);
+
+        // Lines within about 3 degrees of parallel meet at their middle.
+        let point = if 19 * discriminant.abs() > dot_product.abs() {
+            let v = mul_div(dx, -dby, 0x40) + mul_div(dy, dbx, 0x40);
+            Vector::new(a0.x + mul_div(v, dax, discriminant), a0.y + mul_div(v, day, discriminant))
+        } else {
+            Vector::new((a0.x + a1.x + b0.x + b1.x) / 4, (a0.y + a1.y + b0.y + b1.y) / 4)
+        };
+
+        let zone = self.zone_mut(zp2);
+        zone.cur[p] = point;
+        zone.flags[p] |= TOUCHED_X | TOUCHED_Y;
+        Ok(())
+    }
+
+    /// Moves points to keep their relative position between the
+    /// reference points rp1 and rp2, as in the original outline.
+    fn interpolate_point(&mut self) -> Result<()> {
+        let (zp0, zp1, zp2) = (self.gs.zp0, self.gs.zp1, self.gs.zp2);
+        let (rp1, rp2) = (self.gs.rp1, self.gs.rp2);
+        let twilight = zp0 == 0 || zp1 == 0 || zp2 == 0;
+
+        // Original positions are measured unscaled, unless in the twilight zone.
+        let original = |m: &Machine, zone: u8, p: usize| {
+            if twilight { m.org(zone, p) } else { m.orus(zone, p) }
+        };
+
+        let valid = rp1 < self.zone(zp0).len() && rp2 < self.zone(zp1).len();
+        let (orus_base, cur_base, old_range, cur_range) = if valid {
+            let orus_base = original(self, zp0, rp1)?;
+            let cur_base = self.cur(zp0, rp1)?;
+            (orus_base,
+             cur_base,
+             self.gs.dual_project(&original(self, zp1, rp2)?.sub(&orus_base)),
+             self.gs.project(&self.cur(zp1, rp2)?.sub(&cur_base)))
+        } else {
+            (Vector::default(), Vector::default(), 0, 0)
+        };
+
+        for _ in 0..self.gs.loop_count {
+            let p = self.pop_point(zp2)?;
+            if !valid {
+                continue
+            }
+
+            let org_dist = self.gs.dual_project(&original(self, zp2, p)?.sub(&orus_base));
+            let cur_dist = self.gs.project(&self.cur(zp2, p)?.sub(&cur_base));
+            let new_dist = match (org_dist, old_range) {
+                (0, _) => 0,
+                (_, 0) => cur_dist,
+                _ => mul_div(org_dist, cur_range, old_range),
+            };
+
+            self.move_point(zp2, p, new_dist.wrapping_sub(cur_dist), true);
+        }
+
+        self.gs.loop_count = 1;
+        Ok(())
+    }
+
+    /// Interpolates the points of the glyph not touched along an axis
+    /// between the touched points of their contour.
+    fn interpolate_untouched(&mut self, x_axis: bool) {
+        if self.backward_compatibility && self.iup_x && self.iup_y {
+            return
+        }
+
+        let flag = if x_axis { TOUCHED_X } else { TOUCHED_Y };
+        let zone = &mut *self.glyph;
+        let mut axis = Axis {
+            orus: zone.orus.iter().map(|v| if x_axis { v.x } else { v.y }).collect(),
+            org: zone.org.iter().map(|v| if x_axis { v.x } else { v.y }).collect(),
+            cur: zone.cur.iter().map(|v| if x_axis { v.x } else { v.y }).collect(),
+        };
+
+        let mut start = 0;
+        for &end in &zone.end_points {
+            if end >= zone.cur.len() || end < start {
+                break
+            }
+
+            let touched: Vec<usize> = (start..end + 1).filter(|&p| zone.flags[p] & flag != 0).collect();
+
+            match touched.len() {
+                0 => { },
+                1 => axis.shift(start, end, touched[0]),
+                _ => {
+                    for pair in touched.windows(2) {
+                        axis.interpolate(pair[0] + 1, pair[1] - 1, pair[0], pair[1]);
+                    }
+
+                    // Wrap around from the last touched point to the first.
+                    let (first, last) = (touched[0], touched[touched.len() - 1]);
+                    axis.interpolate(last + 1, end, last, first);
+                    if first > start {
+                        axis.interpolate(start, first - 1, last, first);
+                    }
+                },
+            }
+
+            start = end + 1;
+        }
+
+        for (point, &value) in zone.cur.iter_mut().zip(&axis.cur) {
+            if x_axis {
+                point.x = value;
+            } else {
+                point.y = value;
+            }
+        }
+
+        if x_axis {
+            self.iup_x = true;
+        } else {
+            self.iup_y = true;
+        }
+    }
+
+    fn delta_point(&mut self, opcode: u8) -> Result<()> {
+        let count = self.pop()?;
+        let zone = self.gs.zp0;
+        let ppem = self.instance.ppem;
+
+        for _ in 0..count.max(0) {
+            let p = self.pop()?;
+            let arg = self.pop()?;
+            let p = self.point_index(zone, p)?;
+
+            if let Some(amount) = self.delta_amount(opcode, arg, ppem) {
+                if self.backward_compatibility {
+                    let touched_y = self.zone(zone).flags[p] & TOUCHED_Y != 0;
+                    let fv = self.gs.freedom_vector;
+                    if !(self.iup_x && self.iup_y) && ((self.is_composite && fv.y != 0) || touched_y) {
+                        self.move_point(zone, p, amount, true);
+                    }
+                } else {
+                    self.move_point(zone, p, amount, true);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn delta_cvt(&mut self, opcode: u8) -> Result<()> {
+        let count = self.pop()?;
+        let ppem = self.instance.ppem;
+
+        for _ in 0..count.max(0) {
+            let index = self.pop()?;
+            let arg = self.pop()?;
+            let index = self.cvt_index(index)?;
+
+            if let Some(amount) = self.delta_amount(opcode, arg, ppem) {
+                self.instance.cvt[index] = self.instance.cvt[index].wrapping_add(amount);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Decodes the argument of a delta instruction, which applies only at one ppem.
+    fn delta_amount(&self, opcode: u8, arg: i32, ppem: i32) -> Option<i32> {
+        let range = match opcode {
+            0x5D | 0x73 => 0,
+            0x71 | 0x74 => 16,
+            _ => 32,
+        };
+
+        let at = ((arg & 0xF0) >> 4) + range + self.gs.delta_base;
+        if at != ppem {
+            return None
+        }
+
+        let mut steps = (arg & 0xF) - 8;
+        if steps >= 0 {
+            steps += 1;
+        }
+
+        Some(steps * (1 << (6 - self.gs.delta_shift)))
+    }
+
+    fn apply_min_distance(&self, opcode: u8, original: i32, distance: i32) -> i32 {
+        if opcode & 8 == 0 {
+            return distance
+        }
+
+        let min = self.gs.min_distance;
+        if original >= 0 {
+            distance.max(min)
+        } else {
+            distance.min(min.wrapping_neg())
+        }
+    }
+
+    fn move_direct_relative(&mut self, opcode: u8) -> Result<()> {
+        let (zp0, zp1) = (self.gs.zp0, self.gs.zp1);
+        let p = self.pop_point(zp1)?;
+        let rp0 = self.gs.rp0;
+        verify!(rp0 < self.zone(zp0).len());
+
+        // Outside the twilight zone the distance is measured unscaled,
+        // except in composite glyphs where it refers to the hinted components.
+        let mut original = if zp0 == 0 || zp1 == 0 {
+            self.gs.dual_project(&self.org(zp1, p)?.sub(&self.org(zp0, rp0)?))
+        } else {
+            let d = self.gs.dual_project(&self.orus(zp1, p)?.sub(&self.orus(zp0, rp0)?));
+            if self.is_composite { d } else { math::mul_fix(d, self.instance.scale) }
+        };
+
+        let (width, cutin) = (self.gs.single_width_value, self.gs.single_width_cutin);
+        if cutin > 0 && original < width + cutin && original > width - cutin {
+            original = if original >= 0 { width } else { -width };
+        }
+
+        let distance = if opcode & 4 != 0 { self.round(original) } else { original };
+        let distance = self.apply_min_distance(opcode, original, distance);
+
+        let current = self.gs.project(&self.cur(zp1, p)?.sub(&self.cur(zp0, rp0)?));
+        self.move_point(zp1, p, distance.wrapping_sub(current), true);
+
+        self.gs.rp1 = rp0;
+        self.gs.rp2 = p;
+        if opcode & 16 != 0 {
+            self.gs.rp0 = p;
+        }
+
+        Ok(())
+    }
+
+    fn move_indirect_relative(&mut self, opcode: u8) -> Result<()> {
+        let cvt = self.pop()?;
+        let (zp0, zp1) = (self.gs.zp0, self.gs.zp1);
+        let p = self.pop_point(zp1)?;
+        let rp0 = self.gs.rp0;
+        verify!(rp0 < self.zone(zp0).len());
+
+        // The entry before the table always reads as zero.
+        let mut cvt_dist = if cvt == -1 { 0 } else { self.instance.cvt[self.cvt_index(cvt)?] };
+
+        let width = self.gs.single_width_value;
+        if (cvt_dist - width).abs() < self.gs.single_width_cutin {
+            cvt_dist = if cvt_dist >= 0 { width } else { -width };
+        }
+
+        if zp1 == 0 {
+            let fv = self.gs.freedom_vector;
+            let origin = self.org(zp0, rp0)?;
+            let position = Vector::new(origin.x.wrapping_add(mul14(cvt_dist, fv.x)),
+                                       origin.y.wrapping_add(mul14(cvt_dist, fv.y)));
+            let zone = self.zone_mut(zp1);
+            zone.org[p] = position;
+            zone.cur[p] = position;
+        }
+
+        let original = self.gs.dual_project(&self.org(zp1, p)?.sub(&self.org(zp0, rp0)?));
+        let current = self.gs.project(&self.cur(zp1, p)?.sub(&self.cur(zp0, rp0)?));
+
+        if self.gs.auto_flip && (original ^ cvt_dist) < 0 {
+            cvt_dist = cvt_dist.wrapping_neg();
+        }
+
+        let distance = if opcode & 4 != 0 {
+            // The cut-in only applies between points of the same zone.
+            if zp0 == zp1 && (cvt_dist - original).abs() > self.gs.control_value_cutin {
+                cvt_dist = original;
+            }
+            self.round(cvt_dist)
+        } else {
+            cvt_dist
+        };
+        let distance = self.apply_min_distance(opcode, original, distance);
+
+        self.move_point(zp1, p, distance.wrapping_sub(current), true);
+
+        self.gs.rp1 = rp0;
+        self.gs.rp2 = p;
+        if opcode & 16 != 0 {
+            self.gs.rp0 = p;
+        }
+
+        Ok(())
+    }
+}
+
+/// Coordinates of the glyph zone along one axis, for `IUP`.
+struct Axis {
+    orus: Vec<i32>,
+    org: Vec<i32>,
+    cur: Vec<i32>,
+}
+
+impl Axis {
+    /// Moves points by the same amount as the only touched point.
+    fn shift(&mut self, start: usize, end: usize, touched: usize) {
+        let delta = self.cur[touched] - self.org[touched];
+        for p in start..end + 1 {
+            if p != touched {
+                self.cur[p] = self.cur[p].wrapping_add(delta);
+            }
+        }
+    }
+
+    fn interpolate(&mut self, start: usize, end: usize, mut ref1: usize, mut ref2: usize) {
+        if start > end {
+            return
+        }
+
+        if self.orus[ref1] > self.orus[ref2] {
+            ::std::mem::swap(&mut ref1, &mut ref2);
+        }
+
+        let (orus1, orus2) = (self.orus[ref1], self.orus[ref2]);
+        let (org1, org2) = (self.org[ref1], self.org[ref2]);
+        let (cur1, cur2) = (self.cur[ref1], self.cur[ref2]);
+        let (delta1, delta2) = (cur1 - org1, cur2 - org2);
+
+        // Points outside the references move with the nearest one, while
+        // those between are scaled to keep their relative position.
+        let scale = if orus1 != orus2 && cur1 != cur2 {
+            Some(mul_div(cur2 - cur1, 0x10000, orus2 - orus1))
+        } else {
+            None
+        };
+
+        for p in start..end + 1 {
+            let x = self.org[p];
+            self.cur[p] = if x <= org1 {
+                x + delta1
+            } else if x >= org2 {
+                x + delta2
+            } else {
+                match scale {
+                    Some(scale) => cur1 + math::mul_fix(self.orus[p] - orus1, scale),
+                    None => cur1,
+                }
+            };
+        }
+    }
+}
+
+/// Finds the instruction after the matching `ELSE` or `EIF`.  When
+/// skipping an `ELSE` branch only `EIF` ends it.
+fn skip_conditional(code: &[u8], mut ip: usize, else_branch: bool) -> Result<usize> {
+    let mut nesting = 0;
+
+    while ip < code.len() {
+        let opcode = code[ip];
+        ip += instruction_len(&code[ip..])?;
+
+        match opcode {
+            0x58 => nesting += 1,
+            0x1B if nesting == 0 && !else_branch => return Ok(ip),
+            0x59 => {
+                if nesting == 0 {
+                    return Ok(ip)
+                }
+                nesting -= 1;
+            },
+            _ => { },
+        }
+    }
+
+    Err(Error::UnexpectedEof)
+}
+
+fn jump(code: &[u8], ip: usize, offset: i32) -> Result<usize> {
+    // Jumping back to the same instruction would never end.
+    verify!(offset != 0);
+    let target = ip as i64 + offset as i64;
+    verify!(target >= 0 && target <= code.len() as i64);
+    Ok(target as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use hinting::interpreter::{Instance, Machine, ProgramKind};
+    use hinting::state::{GraphicsState, Zone};
+
+    fn instance<'a>() -> Instance<'a> {
+        Instance {
+            functions: Vec::new(),
+            instructions: vec![None; 256],
+            cvt: vec![0, 100, 200],
+            storage: vec![0; 4],
+            twilight: Zone::with_len(2),
+            ppem: 12,
+            scale: 0x10000,
+            max_stack: 64,
+            subpixel: false,
+        }
+    }
+
+    fn run(code: &[u8]) -> Vec<i32> {
+        let mut instance = instance();
+        let mut glyph = Zone::default();
+        let mut machine = Machine::new(GraphicsState::default(), &mut instance, &mut glyph,
+                                       ProgramKind::Font);
+        machine.execute(code).expect("unable to run program");
+        machine.stack.clone()
+    }
+
+    #[test]
+    fn arithmetic() {
+        // 128 + 64, 128 / 64, 96 * 128
+        assert_eq!(run(&[0xB1, 128, 64, 0x60]), vec![192]);
+        assert_eq!(run(&[0xB1, 128, 64, 0x62]), vec![128]);
+        assert_eq!(run(&[0xB8, 0xFF, 0x9C, 0x64, 0x65]), vec![-100]);
+        assert_eq!(run(&[0xB0, 96, 0x20, 0x68, 0x4B]), vec![96, 128, 12]);
+        assert_eq!(run(&[0xB2, 1, 2, 3, 0x8A, 0x24]), vec![2, 3, 1, 3]);
+        assert_eq!(run(&[0xB0, 1, 0x45, 0xB0, 2, 0xB0, 7, 0x70, 0xB0, 2, 0x45]), vec![100, 7]);
+    }
+
+    #[test]
+    fn control_flow() {
+        // IF with ELSE, nested IF skipped.
+        assert_eq!(run(&[0xB0, 0, 0x58, 0xB0, 1, 0x58, 0x59, 0x1B, 0xB0, 2, 0x59]), vec![2]);
+        assert_eq!(run(&[0xB0, 1, 0x58, 0xB0, 1, 0x1B, 0xB0, 2, 0x59]), vec![1]);
+
+        // Function 0 adds one; called three times by LOOPCALL.
+        assert_eq!(run(&[0xB0, 0, 0x2C, 0xB0, 1, 0x60, 0x2D,
+                         0xB1, 5, 3, 0xB0, 0, 0x2A]), vec![8]);
+
+        // JROF skips pushing 9 when the condition is false.
+        assert_eq!(run(&[0xB1, 3, 0, 0x79, 0xB0, 9, 0xB0, 1]), vec![1]);
+    }
+
+    #[test]
+    fn loopcall_budget() {
+        let mut instance = instance();
+        let mut glyph = Zone::default();
+        let mut machine = Machine::new(GraphicsState::default(), &mut instance, &mut glyph,
+                                       ProgramKind::Font);
+
+        // LOOPCALL of an empty function 32767 * 32767 / 64 times.
+        let code = [0xB0, 0, 0x2C, 0x2D, 0xB9, 0x7F, 0xFF, 0x7F, 0xFF, 0x63, 0xB0, 0, 0x2A];
+        assert!(machine.execute(&code).is_err());
+    }
+}
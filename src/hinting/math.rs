@@ -0,0 +1,99 @@
+//! Fixed point arithmetic matching the TrueType rasterizer.  Coordinates
+//! are 26.6 values, unit vectors 2.14 and scale factors 16.16.
+
+/// Computes `a * b / c` with rounding, saturating on division by zero.
+pub fn mul_div(a: i32, b: i32, c: i32) -> i32 {
+    let negative = (a < 0) ^ (b < 0) ^ (c < 0);
+    let (a, b, c) = ((a as i64).abs(), (b as i64).abs(), (c as i64).abs());
+
+    let d = if c > 0 { (a * b + (c >> 1)) / c } else { 0x7FFF_FFFF };
+    let d = d.min(0x7FFF_FFFF) as i32;
+    if negative { -d } else { d }
+}
+
+fn round_shift(ab: i64, shift: u32) -> i32 {
+    let half = 1i64 << (shift - 1);
+    ((ab + half + (ab >> 63)) >> shift) as i32
+}
+
+/// Multiplies by a 2.14 value.
+pub fn mul14(a: i32, b: i32) -> i32 {
+    round_shift(a as i64 * b as i64, 14)
+}
+
+/// The dot product of a 26.6 vector with a 2.14 unit vector.
+pub fn dot14(x: i32, y: i32, vx: i32, vy: i32) -> i32 {
+    round_shift(x as i64 * vx as i64 + y as i64 * vy as i64, 14)
+}
+
+/// Multiplies by a 16.16 value.
+pub fn mul_fix(a: i32, b: i32) -> i32 {
+    round_shift(a as i64 * b as i64, 16)
+}
+
+pub fn floor(x: i32) -> i32 {
+    x & !63
+}
+
+pub fn ceil(x: i32) -> i32 {
+    floor(x.wrapping_add(63))
+}
+
+pub fn round(x: i32) -> i32 {
+    floor(x.wrapping_add(32))
+}
+
+/// Scales a vector to unit length as 2.14 values.  A zero vector becomes
+/// the x axis.  Newton's iterations on integers keep the rounding of
+/// the result the same as in other rasterizers.
+pub fn normalize(x: i32, y: i32) -> (i32, i32) {
+    let (sx, sy) = (x < 0, y < 0);
+    let (mut ux, mut uy) = (x.wrapping_abs() as u32, y.wrapping_abs() as u32);
+
+    if ux == 0 && uy == 0 {
+        return (0x4000, 0)
+    } else if ux == 0 {
+        return (0, if sy { -0x4000 } else { 0x4000 })
+    } else if uy == 0 {
+        return (if sx { -0x4000 } else { 0x4000 }, 0)
+    }
+
+    // Prenormalize so that the estimated length is between 2/3 and 4/3 in 16.16.
+    let estimate = |x: u32, y: u32| if x > y { x + (y >> 1) } else { y + (x >> 1) };
+    let mut l = estimate(ux, uy);
+    let mut shift = l.leading_zeros() as i32;
+    shift -= 15 + (l >= (0xAAAA_AAAAu32 >> shift)) as i32;
+
+    if shift > 0 {
+        ux <<= shift;
+        uy <<= shift;
+        l = estimate(ux, uy);
+    } else {
+        ux >>= -shift;
+        uy >>= -shift;
+        l >>= -shift;
+    }
+
+    let mut b = 0x10000 - l as i32;
+    let (x, y) = (ux as i32, uy as i32);
+    let (mut u, mut v);
+
+    loop {
+        u = (x + (x.wrapping_mul(b) >> 16)) as u32;
+        v = (y + (y.wrapping_mul(b) >> 16)) as u32;
+
+        // The normalized squared length approaches 2^32, so the wrapped
+        // difference from it is the error.
+        let z = (u.wrapping_mul(u).wrapping_add(v.wrapping_mul(v)) as i32).wrapping_neg() / 0x200;
+        let z = z.wrapping_mul((0x10000 + b) >> 8) / 0x10000;
+        b += z;
+
+        if z <= 0 {
+            break
+        }
+    }
+
+    let x = if sx { -(u as i32) } else { u as i32 };
+    let y = if sy { -(v as i32) } else { v as i32 };
+    (x / 4, y / 4)
+}
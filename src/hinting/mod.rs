@@ -0,0 +1,456 @@
+//! A TrueType bytecode interpreter, fitting glyph outlines to the pixel grid.
+
+pub mod math;
+pub mod state;
+pub mod interpreter;
+
+use decode::{Error, Result, ReadPrimitive};
+use decode::primitives::Tag;
+use font::Font;
+use table::glyf::{self, Contour, Glyph, Outline, Point};
+use table::glyf::{ARGS_ARE_XY_VALUES, ROUND_XY_TO_GRID, SCALED_COMPONENT_OFFSET,
+                  UNSCALED_COMPONENT_OFFSET, USE_MY_METRICS};
+use table::head::Head;
+use table::maxp::Maxp;
+use hinting::interpreter::{Instance, Machine, ProgramKind};
+use hinting::state::{GraphicsState, Vector, Zone};
+
+/// The interpreter behaviour to emulate.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HintingMode {
+    /// Instructions move points along both axes, as for grayscale
+    /// rendering with the original (v35) interpreter.
+    Legacy,
+    /// The subpixel (v40) interpreter.  Unless the font opts out with
+    /// `INSTCTRL`, horizontal movement is ignored so that outlines keep
+    /// their shape while stems still snap vertically.
+    Subpixel,
+}
+
+/// How a font asks to be rendered at one size, from its `gasp` and `head` tables.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RenderingFlags {
+    /// Outlines should be hinted.
+    pub gridfit: bool,
+    /// Outlines should be anti-aliased.
+    pub grayscale: bool,
+    /// Hinting should only fit outlines vertically, as with ClearType.
+    pub symmetric_gridfit: bool,
+    /// Anti-aliasing should also be applied vertically with ClearType.
+    pub symmetric_smoothing: bool,
+    /// Sizes should be rounded to whole pixels.
+    pub integer_ppem: bool,
+    /// Hinting may change advances, so they do not scale linearly.
+    pub nonlinear_advances: bool,
+    /// The size is below the smallest the font is readable at.
+    pub below_lowest_rec_ppem: bool,
+}
+
+impl RenderingFlags {
+    /// The interpreter suited to the flags.  Fonts asking for symmetric
+    /// smoothing expect the subpixel interpreter.
+    pub fn hinting_mode(&self) -> Option<HintingMode> {
+        match (self.gridfit, self.symmetric_smoothing) {
+            (false, _) => None,
+            (true, true) => Some(HintingMode::Subpixel),
+            (true, false) => Some(HintingMode::Legacy),
+        }
+    }
+}
+
+/// A glyph fitted to the pixel grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HintedGlyph {
+    /// The outline in pixels with y up, with the origin at the hinted
+    /// left side bearing point.
+    pub outline: Outline,
+    /// The hinted advance width in pixels.
+    pub advance_width: f32,
+}
+
+/// Hints the glyphs of a TrueType font at one size.  Creating a hinter
+/// runs the font program and the control value program; each glyph
+/// then runs its own instructions against a copy of the resulting state.
+pub struct Hinter<'a> {
+    font: &'a Font<'a>,
+    instance: Instance<'a>,
+    gs: GraphicsState,
+    units_per_em: u16,
+    max_component_depth: u16,
+}
+
+impl<'a> Hinter<'a> {
+    pub fn new(font: &'a Font<'a>, ppem: u16, mode: HintingMode) -> Result<Hinter<'a>> {
+        let maxp = match font.get_table::<Maxp>() {
+            Some(Maxp::Version1(maxp)) => maxp,
+            _ => return Err(Error::InvalidData),
+        };
+
+        let units_per_em = font.get_table::<Head>().map(|head| head.units_per_em()).unwrap_or(0);
+        verify!(units_per_em > 0 && ppem > 0);
+
+        let scale = (((ppem as i64 * 64) << 16) + units_per_em as i64 / 2) / units_per_em as i64;
+        verify!(scale <= i32::MAX as i64);
+        let scale = scale as i32;
+
+        let mut cvt = Vec::new();
+        if let Some(mut data) = font.get_table_data(Tag(*b"cvt ")) {
+            while let Ok(value) = data.read::<i16>() {
+                cvt.push(math::mul_fix(value as i32, scale));
+            }
+        }
+
+        let mut instance = Instance {
+            functions: vec![None; maxp.max_function_defs as usize],
+            instructions: vec![None; 256],
+            cvt,
+            storage: vec![0; maxp.max_storage as usize],
+            twilight: Zone::with_len(maxp.max_twilight_points as usize),
+            ppem: ppem as i32,
+            scale,
+            max_stack: maxp.max_stack_elements as usize + 32,
+            subpixel: mode == HintingMode::Subpixel,
+        };
+
+        let mut glyph = Zone::default();
+
+        if let Some(fpgm) = font.get_table_data(Tag(*b"fpgm")) {
+            let mut machine = Machine::new(GraphicsState::default(), &mut instance,
+                                           &mut glyph, ProgramKind::Font);
+            machine.execute(fpgm)?;
+        }
+
+        let mut gs = GraphicsState::default();
+        if let Some(prep) = font.get_table_data(Tag(*b"prep")) {
+            let mut machine = Machine::new(gs, &mut instance, &mut glyph, ProgramKind::ControlValue);
+            machine.execute(prep)?;
+            gs = machine.gs;
+        }
+
+        // Bit 1 of instruct control asks glyphs to start from the default state.
+        if gs.instruct_control & 2 != 0 {
+            gs = GraphicsState { instruct_control: gs.instruct_control, ..GraphicsState::default() };
+        }
+        gs.reset_for_glyph();
+
+        let max_component_depth = match maxp.max_component_depth {
+            0 => glyf::MAX_COMPONENT_DEPTH,
+            depth => depth,
+        };
+
+        Ok(Hinter { font, instance, gs, units_per_em, max_component_depth })
+    }
+
+    pub fn ppem(&self) -> u16 {
+        self.instance.ppem as u16
+    }
+
+    /// Whether the backwards compatibility mode ignores horizontal movement.
+    fn backward_compatibility(&self) -> bool {
+        self.instance.subpixel && self.gs.instruct_control & 4 == 0
+    }
+
+    /// Keeps the unhinted bearings and advances in the backwards
+    /// compatibility mode, where glyphs are not fitted horizontally.
+    fn restore_phantom_points(&self, zone: &mut Zone) {
+        if self.backward_compatibility() {
+            let n = zone.len() - 4;
+            let (org, cur) = (&zone.org[n..], &mut zone.cur[n..]);
+            cur.copy_from_slice(org);
+        }
+    }
+
+    /// Runs the instructions of a glyph, returning its hinted outline.
+    pub fn hint_glyph(&self, glyph_id: u16) -> Result<HintedGlyph> {
+        let mut stack = Vec::new();
+        let zone = self.load_glyph(glyph_id, &mut stack)?;
+
+        let n = zone.len();
+        let origin = zone.cur[n - 4].x;
+        let advance = zone.cur[n - 3].x - origin;
+
+        let mut contours = Vec::with_capacity(zone.end_points.len());
+        let mut start = 0;
+        for &end in &zone.end_points {
+            let points = (start..end + 1).map(|i| Point {
+                x: (zone.cur[i].x - origin) as f32 / 64.0,
+                y: zone.cur[i].y as f32 / 64.0,
+                on_curve: zone.on_curve[i],
+            });
+            contours.push(Contour { points: points.collect() });
+            start = end + 1;
+        }
+
+        Ok(HintedGlyph {
+            outline: Outline { contours, metrics_glyph: None },
+            advance_width: math::round(advance) as f32 / 64.0,
+        })
+    }
+
+    /// The hinted advance of a glyph in whole pixels.
+    pub fn hinted_advance(&self, glyph_id: u16) -> Result<i32> {
+        self.hint_glyph(glyph_id).map(|glyph| glyph.advance_width.round() as i32)
+    }
+
+    fn scale(&self, value: i32) -> i32 {
+        math::mul_fix(value, self.instance.scale)
+    }
+
+    fn scale_points(&self, points: &[Vector; 4]) -> Vec<Vector> {
+        points.iter().map(|p| Vector::new(self.scale(p.x), self.scale(p.y))).collect()
+    }
+
+    /// The four phantom points of a glyph in font units: the horizontal
+    /// origin and advance, then the vertical origin and advance.
+    fn phantom_points(&self, glyph_id: u16, x_min: i16) -> [Vector; 4] {
+        let hmtx = self.font.get_table_hmtx();
+        let lsb = hmtx.as_ref().and_then(|h| h.get_lsb(glyph_id)).unwrap_or(0) as i32;
+        let advance = hmtx.as_ref().and_then(|h| h.get_advance(glyph_id)).unwrap_or(0) as i32;
+        let top = self.font.get_vert_origin_y(glyph_id).unwrap_or(0) as i32;
+        let height = self.font.get_vert_advance(glyph_id).unwrap_or(self.units_per_em) as i32;
+
+        let pp1 = x_min as i32 - lsb;
+        [Vector::new(pp1, 0), Vector::new(pp1 + advance, 0),
+         Vector::new(0, top), Vector::new(0, top - height)]
+    }
+
+    /// Adds phantom points to a zone, given unscaled and scaled.
+    fn push_phantom_points(zone: &mut Zone, orus: &[Vector], org: &[Vector]) {
+        for (orus, org) in orus.iter().zip(org) {
+            zone.orus.push(*orus);
+            zone.org.push(*org);
+            zone.cur.push(*org);
+            zone.flags.push(0);
+            zone.on_curve.push(true);
+        }
+    }
+
+    /// Rounds the hinted phantom points to the grid before instructions run.
+    fn round_phantom_points(zone: &mut Zone) {
+        let n = zone.len() - 4;
+        for (i, p) in zone.cur[n..].iter_mut().enumerate() {
+            if i < 2 {
+                p.x = math::round(p.x);
+            } else {
+                p.y = math::round(p.y);
+            }
+        }
+    }
+
+    fn run_glyph_program(&self, zone: &mut Zone, code: &'a [u8], is_composite: bool) -> Result<()> {
+        // Bit 0 of instruct control disables glyph programs.
+        if code.is_empty() || self.gs.instruct_control & 1 != 0 {
+            return Ok(())
+        }
+
+        let mut instance = self.instance.clone();
+        let mut machine = Machine::new(self.gs, &mut instance, zone, ProgramKind::Glyph);
+        machine.is_composite = is_composite;
+        machine.execute(code)
+    }
+
+    /// Loads and hints a glyph, returning its points followed by its
+    /// phantom points.
+    fn load_glyph(&self, glyph_id: u16, stack: &mut Vec<u16>) -> Result<Zone> {
+        let glyph = match self.font.get_glyph(glyph_id) {
+            Some(glyph) => glyph,
+            None => return Err(Error::InvalidData),
+        };
+
+        match glyph {
+            Glyph::Empty => {
+                let mut zone = Zone::default();
+                let phantom = self.phantom_points(glyph_id, 0);
+                Hinter::push_phantom_points(&mut zone, &phantom, &self.scale_points(&phantom));
+                Hinter::round_phantom_points(&mut zone);
+                self.restore_phantom_points(&mut zone);
+                Ok(zone)
+            },
+            Glyph::Simple(ref simple) => {
+                let description = &simple.description;
+                let mut zone = Zone::default();
+
+                for p in description.points() {
+                    let orus = Vector::new(p.x as i32, p.y as i32);
+                    let org = Vector::new(self.scale(orus.x), self.scale(orus.y));
+                    zone.orus.push(orus);
+                    zone.org.push(org);
+                    zone.cur.push(org);
+                    zone.flags.push(0);
+                    zone.on_curve.push(p.on_curve);
+                }
+
+                let num_points = zone.len();
+                for end in description.end_points() {
+                    verify!((end as usize) < num_points);
+                    zone.end_points.push(end as usize);
+                }
+
+                let phantom = self.phantom_points(glyph_id, simple.header.x_min);
+                Hinter::push_phantom_points(&mut zone, &phantom, &self.scale_points(&phantom));
+                Hinter::round_phantom_points(&mut zone);
+                self.run_glyph_program(&mut zone, description.instructions, false)?;
+                self.restore_phantom_points(&mut zone);
+                Ok(zone)
+            },
+            Glyph::Composite(ref composite) => {
+                verify!(stack.len() < self.max_component_depth as usize);
+                verify!(!stack.contains(&glyph_id));
+                stack.push(glyph_id);
+
+                let mut zone = Zone::default();
+                let phantom = self.phantom_points(glyph_id, composite.header.x_min);
+                let mut metrics = (phantom.to_vec(), self.scale_points(&phantom));
+
+                for component in composite.components() {
+                    let component = component?;
+                    let mut child = self.load_glyph(component.glyph_id, stack)?;
+
+                    let n = child.len() - 4;
+                    // The component's phantom points are kept as hinted.
+                    if component.flags.contains(USE_MY_METRICS) {
+                        metrics = (child.orus[n..].to_vec(), child.cur[n..].to_vec());
+                    }
+
+                    let t = &component.transform;
+                    if *t != [1.0, 0.0, 0.0, 1.0] {
+                        for p in &mut child.cur[..n] {
+                            let (x, y) = (p.x as f32, p.y as f32);
+                            *p = Vector::new((t[0] * x + t[2] * y).round() as i32,
+                                             (t[1] * x + t[3] * y).round() as i32);
+                        }
+                    }
+
+                    let offset = if component.flags.contains(ARGS_ARE_XY_VALUES) {
+                        self.component_offset(&component)
+                    } else {
+                        // Align a point of the component with one placed earlier.
+                        let parent = component.arg1 as usize;
+                        let point = component.arg2 as usize;
+                        verify!(parent < zone.len() && point < n);
+                        zone.cur[parent].sub(&child.cur[point])
+                    };
+
+                    let base = zone.len();
+                    for i in 0..n {
+                        let p = Vector::new(child.cur[i].x + offset.x, child.cur[i].y + offset.y);
+                        zone.orus.push(child.orus[i]);
+                        zone.org.push(p);
+                        zone.cur.push(p);
+                        zone.flags.push(0);
+                        zone.on_curve.push(child.on_curve[i]);
+                    }
+                    zone.end_points.extend(child.end_points.iter().map(|&end| end + base));
+                }
+
+                stack.pop();
+
+                Hinter::push_phantom_points(&mut zone, &metrics.0, &metrics.1);
+
+                // Instructions of composite glyphs see the already hinted
+                // components as their original outline.
+                let instructions = composite.instructions();
+                if !instructions.is_empty() {
+                    zone.orus = zone.cur.clone();
+                    zone.org = zone.cur.clone();
+                    Hinter::round_phantom_points(&mut zone);
+                    self.run_glyph_program(&mut zone, instructions, true)?;
+                    self.restore_phantom_points(&mut zone);
+                }
+
+                Ok(zone)
+            },
+        }
+    }
+
+    /// The scaled offset of a component positioned by `ARGS_ARE_XY_VALUES`.
+    fn component_offset(&self, component: &glyf::Component) -> Vector {
+        let (mut dx, mut dy) = (component.arg1 as f32, component.arg2 as f32);
+
+        if component.flags.contains(SCALED_COMPONENT_OFFSET)
+            && !component.flags.contains(UNSCALED_COMPONENT_OFFSET)
+        {
+            let t = &component.transform;
+            let (x, y) = (dx, dy);
+            dx = t[0] * x + t[2] * y;
+            dy = t[1] * x + t[3] * y;
+        }
+
+        let mut offset = Vector::new(self.scale(dx.round() as i32), self.scale(dy.round() as i32));
+
+        // Horizontal offsets keep their precision with subpixel hinting.
+        if component.flags.contains(ROUND_XY_TO_GRID) {
+            if !self.instance.subpixel {
+                offset.x = math::round(offset.x);
+            }
+            offset.y = math::round(offset.y);
+        }
+
+        offset
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use hinting::{Hinter, HintingMode};
+    use table::maxp::Maxp;
+
+    #[test]
+    fn hint_glyph() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        for &mode in &[HintingMode::Legacy, HintingMode::Subpixel] {
+            let hinter = font.get_hinter(12, mode).expect("unable to run font programs");
+
+            // 'H', 'o', 'Aacute'
+            for &glyph_id in &[43, 82, 131] {
+                let glyph = hinter.hint_glyph(glyph_id).expect("unable to hint glyph");
+                assert_eq!(glyph.advance_width, glyph.advance_width.round());
+                assert!(!glyph.outline.contours.is_empty());
+            }
+
+            // The flat top and bottom of 'H' land on pixel boundaries.
+            let glyph = hinter.hint_glyph(43).unwrap();
+            let ys: Vec<f32> = glyph.outline.contours.iter()
+                .flat_map(|c| c.points.iter().map(|p| p.y))
+                .collect();
+            let top = ys.iter().cloned().fold(f32::MIN, f32::max);
+            let bottom = ys.iter().cloned().fold(f32::MAX, f32::min);
+            assert_eq!(bottom, 0.0);
+            assert_eq!(top, top.round());
+        }
+
+        // The first points of 'H' in 26.6, where only the legacy
+        // interpreter fits the stems horizontally.
+        let expected = [(HintingMode::Legacy, [500, 436, 436, 141, 141, 77]),
+                        (HintingMode::Subpixel, [492, 428, 428, 139, 139, 75])];
+        for &(mode, ref xs) in &expected {
+            let glyph = font.get_hinter(12, mode).unwrap().hint_glyph(43).unwrap();
+            let points = &glyph.outline.contours[0].points[..6];
+            assert_eq!(points.iter().map(|p| (p.x * 64.0) as i32).collect::<Vec<_>>(), xs.to_vec());
+            assert_eq!(points.iter().map(|p| (p.y * 64.0) as i32).collect::<Vec<_>>(),
+                       vec![0, 0, 263, 263, 0, 0]);
+        }
+
+        // Legacy hinting reproduces the hinted widths in hdmx, for glyphs
+        // whose left side bearing agrees with their outline.
+        let buf = open_font!("data/Roboto-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let hdmx = font.get_table_hdmx().expect("no hdmx table");
+        let hmtx = font.get_table_hmtx().expect("no hmtx table");
+        let hinter = Hinter::new(&font, 9, HintingMode::Legacy).expect("unable to run font programs");
+        let num_glyphs = font.get_table::<Maxp>().unwrap().get_num_glyphs();
+
+        for glyph_id in 0..num_glyphs {
+            let x_min = font.get_glyph_header(glyph_id).map(|h| h.x_min).unwrap_or(0);
+            if hmtx.get_lsb(glyph_id) != Some(x_min) {
+                continue
+            }
+
+            assert_eq!(hinter.hinted_advance(glyph_id).unwrap(),
+                       hdmx.get_advance(glyph_id, 9).unwrap() as i32);
+        }
+    }
+}
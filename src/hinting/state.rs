@@ -0,0 +1,282 @@
+use hinting::math::{self, mul_div};
+
+/// Set on points moved along the x axis.
+pub const TOUCHED_X: u8 = 1;
+/// Set on points moved along the y axis.
+pub const TOUCHED_Y: u8 = 2;
+
+/// A 26.6 point, or a 2.14 unit vector.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Vector {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Vector {
+    pub fn new(x: i32, y: i32) -> Vector {
+        Vector { x, y }
+    }
+
+    pub fn x_axis() -> Vector {
+        Vector::new(0x4000, 0)
+    }
+
+    pub fn y_axis() -> Vector {
+        Vector::new(0, 0x4000)
+    }
+
+    pub fn sub(&self, other: &Vector) -> Vector {
+        Vector::new(self.x.wrapping_sub(other.x), self.y.wrapping_sub(other.y))
+    }
+}
+
+/// The points an instruction can refer to.  Zone 0 is the twilight zone,
+/// zone 1 holds the glyph's points followed by the four phantom points.
+#[derive(Debug, Clone, Default)]
+pub struct Zone {
+    /// Unscaled positions, used when interpolating.
+    pub orus: Vec<Vector>,
+    /// Scaled positions before hinting.
+    pub org: Vec<Vector>,
+    /// Positions being hinted.
+    pub cur: Vec<Vector>,
+    pub flags: Vec<u8>,
+    pub on_curve: Vec<bool>,
+    /// Index of the last point in each contour.
+    pub end_points: Vec<usize>,
+}
+
+impl Zone {
+    pub fn with_len(len: usize) -> Zone {
+        Zone {
+            orus: vec![Vector::default(); len],
+            org: vec![Vector::default(); len],
+            cur: vec![Vector::default(); len],
+            flags: vec![0; len],
+            on_curve: vec![false; len],
+            end_points: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cur.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cur.is_empty()
+    }
+
+    /// Number of points in contours, which excludes the phantom points.
+    pub fn num_contour_points(&self) -> usize {
+        self.end_points.last().map(|&end| end + 1).unwrap_or(0)
+    }
+}
+
+/// How distances are rounded to the pixel grid.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RoundState {
+    ToHalfGrid,
+    ToGrid,
+    ToDoubleGrid,
+    DownToGrid,
+    UpToGrid,
+    Off,
+    Super { period: i32, phase: i32, threshold: i32 },
+    /// Super rounding with a period given for a 45 degree grid.
+    Super45 { period: i32, phase: i32, threshold: i32 },
+}
+
+impl RoundState {
+    /// Decodes the operand of `SROUND` and `S45ROUND`.
+    pub fn super_round(selector: i32, diagonal: bool) -> RoundState {
+        // The grid period is 1 or sqrt(2) / 2 in 2.14, converted to 26.6 below.
+        let grid_period: i32 = if diagonal { 0x2D41 } else { 0x4000 };
+
+        let period = match selector & 0xC0 {
+            0x00 => grid_period / 2,
+            0x80 => grid_period * 2,
+            _ => grid_period,
+        };
+
+        let phase = match selector & 0x30 {
+            0x00 => 0,
+            0x10 => period / 4,
+            0x20 => period / 2,
+            _ => period * 3 / 4,
+        };
+
+        let threshold = match selector & 0x0F {
+            0 => period - 1,
+            n => (n - 4) * period / 8,
+        };
+
+        let (period, phase, threshold) = (period >> 8, phase >> 8, threshold >> 8);
+        if diagonal {
+            RoundState::Super45 { period, phase, threshold }
+        } else {
+            RoundState::Super { period, phase, threshold }
+        }
+    }
+
+    /// Rounds a distance, preserving its sign.
+    pub fn round(&self, distance: i32) -> i32 {
+        match *self {
+            RoundState::ToHalfGrid => sign_preserving(distance, |d| math::floor(d) + 32),
+            RoundState::ToGrid => sign_preserving(distance, math::round),
+            RoundState::ToDoubleGrid => sign_preserving(distance, |d| d.wrapping_add(16) & !31),
+            RoundState::DownToGrid => sign_preserving(distance, math::floor),
+            RoundState::UpToGrid => sign_preserving(distance, math::ceil),
+            RoundState::Off => distance,
+            RoundState::Super { period, phase, threshold } => {
+                if period <= 0 {
+                    return distance
+                }
+                if distance >= 0 {
+                    let d = ((distance - phase + threshold) & -period) + phase;
+                    if d < 0 { phase } else { d }
+                } else {
+                    let d = -(((threshold - phase - distance) & -period) + phase);
+                    if d > 0 { -phase } else { d }
+                }
+            },
+            RoundState::Super45 { period, phase, threshold } => {
+                if period <= 0 {
+                    return distance
+                }
+                if distance >= 0 {
+                    let d = (distance - phase + threshold) / period * period + phase;
+                    if d < 0 { phase } else { d }
+                } else {
+                    let d = -((threshold - phase - distance) / period * period + phase);
+                    if d > 0 { -phase } else { d }
+                }
+            },
+        }
+    }
+}
+
+/// Applies a rounding function to the magnitude of a distance.
+fn sign_preserving<F: Fn(i32) -> i32>(distance: i32, f: F) -> i32 {
+    if distance >= 0 {
+        f(distance).max(0)
+    } else {
+        (-f(distance.wrapping_neg())).min(0)
+    }
+}
+
+/// Variables shared between instructions, which the control value
+/// program may set as defaults for glyph programs.
+#[derive(Debug, Copy, Clone)]
+pub struct GraphicsState {
+    pub auto_flip: bool,
+    pub control_value_cutin: i32,
+    pub delta_base: i32,
+    pub delta_shift: i32,
+    pub dual_vector: Vector,
+    pub freedom_vector: Vector,
+    pub projection_vector: Vector,
+    pub instruct_control: u8,
+    pub loop_count: i32,
+    pub min_distance: i32,
+    pub round_state: RoundState,
+    pub rp0: usize,
+    pub rp1: usize,
+    pub rp2: usize,
+    pub scan_control: bool,
+    pub single_width_cutin: i32,
+    pub single_width_value: i32,
+    pub zp0: u8,
+    pub zp1: u8,
+    pub zp2: u8,
+}
+
+impl Default for GraphicsState {
+    fn default() -> GraphicsState {
+        GraphicsState {
+            auto_flip: true,
+            control_value_cutin: 68,
+            delta_base: 9,
+            delta_shift: 3,
+            dual_vector: Vector::x_axis(),
+            freedom_vector: Vector::x_axis(),
+            projection_vector: Vector::x_axis(),
+            instruct_control: 0,
+            loop_count: 1,
+            min_distance: 64,
+            round_state: RoundState::ToGrid,
+            rp0: 0,
+            rp1: 0,
+            rp2: 0,
+            scan_control: false,
+            single_width_cutin: 0,
+            single_width_value: 0,
+            zp0: 1,
+            zp1: 1,
+            zp2: 1,
+        }
+    }
+}
+
+impl GraphicsState {
+    /// Resets the variables that the control value program may not
+    /// change for glyph programs.
+    pub fn reset_for_glyph(&mut self) {
+        self.dual_vector = Vector::x_axis();
+        self.freedom_vector = Vector::x_axis();
+        self.projection_vector = Vector::x_axis();
+        self.round_state = RoundState::ToGrid;
+        self.loop_count = 1;
+        self.rp0 = 0;
+        self.rp1 = 0;
+        self.rp2 = 0;
+        self.zp0 = 1;
+        self.zp1 = 1;
+        self.zp2 = 1;
+    }
+
+    pub fn project(&self, v: &Vector) -> i32 {
+        math::dot14(v.x, v.y, self.projection_vector.x, self.projection_vector.y)
+    }
+
+    pub fn dual_project(&self, v: &Vector) -> i32 {
+        math::dot14(v.x, v.y, self.dual_vector.x, self.dual_vector.y)
+    }
+
+    /// The projection of the freedom vector onto the projection vector,
+    /// kept away from zero so that moves stay bounded.
+    pub fn f_dot_p(&self) -> i32 {
+        let f = &self.freedom_vector;
+        let p = &self.projection_vector;
+        let d = ((f.x as i64 * p.x as i64 + f.y as i64 * p.y as i64) >> 14) as i32;
+        if d.abs() < 0x400 { 0x4000 } else { d }
+    }
+
+    /// The movement along the freedom vector that changes the projected
+    /// position by `distance`.
+    pub fn movement(&self, distance: i32) -> Vector {
+        let f_dot_p = self.f_dot_p();
+        Vector::new(mul_div(distance, self.freedom_vector.x, f_dot_p),
+                    mul_div(distance, self.freedom_vector.y, f_dot_p))
+    }
+}
+
+#[test]
+fn rounding() {
+    assert_eq!(RoundState::ToGrid.round(95), 64);
+    assert_eq!(RoundState::ToGrid.round(96), 128);
+    assert_eq!(RoundState::ToGrid.round(-96), -128);
+    assert_eq!(RoundState::ToHalfGrid.round(64), 96);
+    assert_eq!(RoundState::ToHalfGrid.round(-10), -32);
+    assert_eq!(RoundState::ToDoubleGrid.round(47), 32);
+    assert_eq!(RoundState::DownToGrid.round(127), 64);
+    assert_eq!(RoundState::UpToGrid.round(65), 128);
+    assert_eq!(RoundState::UpToGrid.round(-65), -128);
+    assert_eq!(RoundState::Off.round(65), 65);
+
+    // Period of a pixel, phase of half a pixel, threshold at 3/8.
+    let state = RoundState::super_round(0x67, false);
+    assert_eq!(state, RoundState::Super { period: 64, phase: 32, threshold: 24 });
+    assert_eq!(state.round(10), 32);
+    assert_eq!(state.round(72), 96);
+    assert_eq!(state.round(-10), -32);
+}
@@ -16,5 +16,17 @@ extern crate try_opt;
 #[macro_use]
 pub mod util;
 pub mod font;
+pub mod metrics;
+pub mod buffer;
+pub mod outline;
+pub mod geometry;
+pub mod pathops;
+pub mod raster;
+pub mod synthesis;
+pub mod sdf;
+pub mod atlas;
+pub mod cache;
+pub mod hinting;
+pub mod svg;
 pub mod decode;
 pub mod table;
\ No newline at end of file
@@ -1,29 +1,21 @@
-#![allow(dead_code)]
-#![allow(unused_imports)]
-
-#[macro_use]
-extern crate derive_more;
-extern crate byteorder;
-#[macro_use]
-extern crate table_derive;
-
-#[macro_use]
-pub mod util;
-pub mod font;
-pub mod decode;
-pub mod table;
-
-use table::cmap::Cmap;
-use table::cmap::Format4;
+extern crate font_shape;
+
+use std::fs::File;
+use std::io::Read;
+
+use font_shape::font::Font;
+use font_shape::table::cmap::{Cmap, CmapHeader};
+
 #[inline(never)]
-fn get_glpf_indx(cmap: &Format4, cp: u32) -> Option<u16> {
+fn get_glpf_indx(cmap: &Cmap, cp: u32) -> Option<u16> {
     cmap.get_glyph_id(cp)
 }
 
 fn main() {
-    use font::Font;
-    use table::cmap::CmapHeader;
-    let buf: Vec<u8> = open_font!(r"data/DroidSerif.ttf");
+    let mut buf = Vec::new();
+    File::open(r"data/DroidSerif.ttf")
+        .and_then(|mut file| file.read_to_end(&mut buf))
+        .expect("unable to read font");
 
     let font = Font::from_buffer(&buf).expect("unable to parse font");
     let tbl = font.get_table::<CmapHeader>()
@@ -34,10 +26,7 @@ fn main() {
         .next()
         .unwrap();
 
-    let cmap = match cmap.get_cmap().unwrap() {
-        Cmap::Format4(c) => c,
-        _ => panic!(),
-    };
+    let cmap = cmap.get_cmap().unwrap();
 
     for idx in b'A'..b'z' {
         let idx = get_glpf_indx(&cmap, idx as u32);
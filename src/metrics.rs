@@ -0,0 +1,110 @@
+/// The size at which to scale metrics given in font units.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Size {
+    /// Pixels per em.
+    Pixels(f32),
+    /// A point size at the given resolution in dots per inch.
+    Points { size: f32, dpi: f32 },
+}
+
+impl Size {
+    pub fn ppem(&self) -> f32 {
+        match *self {
+            Size::Pixels(ppem) => ppem,
+            Size::Points { size, dpi } => size * dpi / 72.0,
+        }
+    }
+
+    /// The factor converting font units into pixels.
+    pub fn scale_factor(&self, units_per_em: u16) -> f32 {
+        self.ppem() / units_per_em as f32
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingBox<T> {
+    pub x_min: T,
+    pub y_min: T,
+    pub x_max: T,
+    pub y_max: T,
+}
+
+impl BoundingBox<i32> {
+    pub fn width(&self) -> i32 {
+        self.x_max - self.x_min
+    }
+
+    pub fn height(&self) -> i32 {
+        self.y_max - self.y_min
+    }
+
+    pub fn scale(&self, factor: f32) -> BoundingBox<f32> {
+        BoundingBox {
+            x_min: self.x_min as f32 * factor,
+            y_min: self.y_min as f32 * factor,
+            x_max: self.x_max as f32 * factor,
+            y_max: self.y_max as f32 * factor,
+        }
+    }
+}
+
+/// Horizontal metrics of a single glyph.  Glyphs without an outline
+/// or bitmap, such as the space, have no bounds.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GlyphMetrics<T> {
+    pub advance_width: T,
+    pub left_side_bearing: T,
+    pub right_side_bearing: T,
+    pub bounds: Option<BoundingBox<T>>,
+}
+
+impl GlyphMetrics<i32> {
+    pub fn scale(&self, factor: f32) -> GlyphMetrics<f32> {
+        GlyphMetrics {
+            advance_width: self.advance_width as f32 * factor,
+            left_side_bearing: self.left_side_bearing as f32 * factor,
+            right_side_bearing: self.right_side_bearing as f32 * factor,
+            bounds: self.bounds.map(|b| b.scale(factor)),
+        }
+    }
+}
+
+bitflags! {
+    /// The style of a face, as described by `head` and `OS/2`, along with
+    /// the styles added by synthesis.  Synthesized styles also set the
+    /// style they imitate.
+    pub struct Style: u8 {
+        const BOLD              = 1 << 0;
+        const ITALIC            = 1 << 1;
+        /// Slanted rather than a cursive italic.  Also sets `ITALIC`.
+        const OBLIQUE           = 1 << 2;
+        const SYNTHETIC_BOLD    = 1 << 3;
+        const SYNTHETIC_OBLIQUE = 1 << 4;
+    }
+}
+
+/// Font-wide horizontal line metrics.  The descender is negative
+/// when below the baseline.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FontMetrics<T> {
+    pub ascender: T,
+    pub descender: T,
+    pub line_gap: T,
+    pub style: Style,
+}
+
+impl FontMetrics<i32> {
+    /// The distance between consecutive baselines.
+    pub fn line_height(&self) -> i32 {
+        self.ascender - self.descender + self.line_gap
+    }
+
+    pub fn scale(&self, factor: f32) -> FontMetrics<f32> {
+        FontMetrics {
+            ascender: self.ascender as f32 * factor,
+            descender: self.descender as f32 * factor,
+            line_gap: self.line_gap as f32 * factor,
+            style: self.style,
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use table::glyf::Point;
+
+/// A sink for glyph outlines.  Glyph decoders drive the builder with
+/// the segments of each contour as they are read.
+pub trait OutlineBuilder {
+    fn move_to(&mut self, x: f32, y: f32);
+    fn line_to(&mut self, x: f32, y: f32);
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32);
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32);
+    fn close(&mut self);
+}
+
+/// An affine transform mapping `(x, y)` to
+/// `(xx * x + yx * y + dx, xy * x + yy * y + dy)`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub xx: f32,
+    pub xy: f32,
+    pub yx: f32,
+    pub yy: f32,
+    pub dx: f32,
+    pub dy: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform::identity()
+    }
+}
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform { xx: 1.0, xy: 0.0, yx: 0.0, yy: 1.0, dx: 0.0, dy: 0.0 }
+    }
+
+    pub fn scale(sx: f32, sy: f32) -> Transform {
+        Transform { xx: sx, yy: sy, ..Transform::identity() }
+    }
+
+    pub fn translate(dx: f32, dy: f32) -> Transform {
+        Transform { dx, dy, ..Transform::identity() }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Transform::identity()
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (self.xx * x + self.yx * y + self.dx,
+         self.xy * x + self.yy * y + self.dy)
+    }
+
+    /// The transform applying `self`, followed by `other`.
+    pub fn then(&self, other: &Transform) -> Transform {
+        let (dx, dy) = other.apply(self.dx, self.dy);
+        Transform {
+            xx: other.xx * self.xx + other.yx * self.xy,
+            xy: other.xy * self.xx + other.yy * self.xy,
+            yx: other.xx * self.yx + other.yx * self.yy,
+            yy: other.xy * self.yx + other.yy * self.yy,
+            dx,
+            dy,
+        }
+    }
+}
+
+/// Applies a transform to every point before passing it on.
+pub struct TransformBuilder<'a, B: OutlineBuilder + 'a> {
+    inner: &'a mut B,
+    transform: Transform,
+}
+
+impl<'a, B: OutlineBuilder + 'a> TransformBuilder<'a, B> {
+    pub fn new(inner: &'a mut B, transform: Transform) -> TransformBuilder<'a, B> {
+        TransformBuilder { inner, transform }
+    }
+}
+
+impl<'a, B: OutlineBuilder + 'a> OutlineBuilder for TransformBuilder<'a, B> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.transform.apply(x, y);
+        self.inner.move_to(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let (x, y) = self.transform.apply(x, y);
+        self.inner.line_to(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x1, y1) = self.transform.apply(x1, y1);
+        let (x, y) = self.transform.apply(x, y);
+        self.inner.quad_to(x1, y1, x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (x1, y1) = self.transform.apply(x1, y1);
+        let (x2, y2) = self.transform.apply(x2, y2);
+        let (x, y) = self.transform.apply(x, y);
+        self.inner.curve_to(x1, y1, x2, y2, x, y);
+    }
+
+    fn close(&mut self) {
+        self.inner.close();
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Verb {
+    MoveTo,
+    LineTo,
+    QuadTo,
+    CurveTo,
+    Close,
+}
+
+impl Verb {
+    fn num_points(&self) -> usize {
+        match *self {
+            Verb::MoveTo | Verb::LineTo => 1,
+            Verb::QuadTo => 2,
+            Verb::CurveTo => 3,
+            Verb::Close => 0,
+        }
+    }
+}
+
+/// Stores an outline, so that it can be changed as a whole before
+/// being passed on.
+#[derive(Debug, Clone, Default)]
+pub struct PathRecorder {
+    verbs: Vec<Verb>,
+    points: Vec<(f32, f32)>,
+}
+
+impl PathRecorder {
+    pub fn new() -> PathRecorder {
+        PathRecorder::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.verbs.is_empty()
+    }
+
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// The points can be moved, but not added or removed.
+    pub fn points_mut(&mut self) -> &mut [(f32, f32)] {
+        &mut self.points
+    }
+
+    pub fn replay<B: OutlineBuilder>(&self, builder: &mut B) {
+        let mut points = self.points.iter();
+        let mut next = || *points.next().unwrap();
+        for verb in &self.verbs {
+            match *verb {
+                Verb::MoveTo => {
+                    let p = next();
+                    builder.move_to(p.0, p.1);
+                },
+                Verb::LineTo => {
+                    let p = next();
+                    builder.line_to(p.0, p.1);
+                },
+                Verb::QuadTo => {
+                    let (p1, p) = (next(), next());
+                    builder.quad_to(p1.0, p1.1, p.0, p.1);
+                },
+                Verb::CurveTo => {
+                    let (p1, p2, p) = (next(), next(), next());
+                    builder.curve_to(p1.0, p1.1, p2.0, p2.1, p.0, p.1);
+                },
+                Verb::Close => builder.close(),
+            }
+        }
+    }
+
+    /// The range of points in each contour.
+    pub fn contours(&self) -> Vec<(usize, usize)> {
+        let mut contours = Vec::new();
+        let mut index = 0;
+        for verb in &self.verbs {
+            if *verb == Verb::MoveTo {
+                contours.push((index, index));
+            }
+            index += verb.num_points();
+            if let Some(contour) = contours.last_mut() {
+                contour.1 = index;
+            }
+        }
+        contours
+    }
+}
+
+impl OutlineBuilder for PathRecorder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.verbs.push(Verb::MoveTo);
+        self.points.push((x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.verbs.push(Verb::LineTo);
+        self.points.push((x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.verbs.push(Verb::QuadTo);
+        self.points.extend_from_slice(&[(x1, y1), (x, y)]);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.verbs.push(Verb::CurveTo);
+        self.points.extend_from_slice(&[(x1, y1), (x2, y2), (x, y)]);
+    }
+
+    fn close(&mut self) {
+        self.verbs.push(Verb::Close);
+    }
+}
+
+/// Converts a stream of TrueType on and off curve points into segments,
+/// inserting the implied on curve points between consecutive off curve
+/// points.  Call `finish` at the end of every contour.
+pub struct ContourBuilder<'a, B: OutlineBuilder + 'a> {
+    inner: &'a mut B,
+    transform: Transform,
+    first_on: Option<Point>,
+    first_off: Option<Point>,
+    last_off: Option<Point>,
+}
+
+impl<'a, B: OutlineBuilder + 'a> ContourBuilder<'a, B> {
+    pub fn new(inner: &'a mut B, transform: Transform) -> ContourBuilder<'a, B> {
+        ContourBuilder {
+            inner,
+            transform,
+            first_on: None,
+            first_off: None,
+            last_off: None,
+        }
+    }
+
+    pub fn push(&mut self, p: Point) {
+        let (x, y) = self.transform.apply(p.x, p.y);
+        let p = Point { x, y, on_curve: p.on_curve };
+
+        if self.first_on.is_none() {
+            if p.on_curve {
+                self.first_on = Some(p);
+                self.inner.move_to(p.x, p.y);
+            } else if let Some(off) = self.first_off {
+                let mid = off.midpoint(&p);
+                self.first_on = Some(mid);
+                self.last_off = Some(p);
+                self.inner.move_to(mid.x, mid.y);
+            } else {
+                self.first_off = Some(p);
+            }
+            return
+        }
+
+        match (self.last_off, p.on_curve) {
+            (Some(off), true) => {
+                self.last_off = None;
+                self.inner.quad_to(off.x, off.y, p.x, p.y);
+            },
+            (Some(off), false) => {
+                let mid = off.midpoint(&p);
+                self.last_off = Some(p);
+                self.inner.quad_to(off.x, off.y, mid.x, mid.y);
+            },
+            (None, true) => self.inner.line_to(p.x, p.y),
+            (None, false) => self.last_off = Some(p),
+        }
+    }
+
+    /// Closes the current contour.
+    pub fn finish(&mut self) {
+        if let (Some(first_off), Some(last_off)) = (self.first_off, self.last_off) {
+            let mid = last_off.midpoint(&first_off);
+            self.last_off = None;
+            self.inner.quad_to(last_off.x, last_off.y, mid.x, mid.y);
+        }
+
+        if let Some(start) = self.first_on {
+            match self.first_off.or(self.last_off) {
+                Some(off) => self.inner.quad_to(off.x, off.y, start.x, start.y),
+                None => self.inner.line_to(start.x, start.y),
+            }
+            self.inner.close();
+        }
+
+        self.first_on = None;
+        self.first_off = None;
+        self.last_off = None;
+    }
+}
+
+/// Finds the control box of an outline, which contains every point.
+#[derive(Debug, Copy, Clone)]
+pub struct BoundsBuilder {
+    pub x_min: f32,
+    pub y_min: f32,
+    pub x_max: f32,
+    pub y_max: f32,
+}
+
+impl Default for BoundsBuilder {
+    fn default() -> BoundsBuilder {
+        BoundsBuilder::new()
+    }
+}
+
+impl BoundsBuilder {
+    pub fn new() -> BoundsBuilder {
+        BoundsBuilder {
+            x_min: f32::INFINITY,
+            y_min: f32::INFINITY,
+            x_max: f32::NEG_INFINITY,
+            y_max: f32::NEG_INFINITY,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x_min > self.x_max
+    }
+
+    fn add(&mut self, x: f32, y: f32) {
+        self.x_min = self.x_min.min(x);
+        self.y_min = self.y_min.min(y);
+        self.x_max = self.x_max.max(x);
+        self.y_max = self.y_max.max(y);
+    }
+}
+
+impl OutlineBuilder for BoundsBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.add(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.add(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.add(x1, y1);
+        self.add(x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.add(x1, y1);
+        self.add(x2, y2);
+        self.add(x, y);
+    }
+
+    fn close(&mut self) { }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use outline::{OutlineBuilder, Transform};
+    use std::fmt::Write;
+
+    struct PathBuilder(String);
+
+    impl OutlineBuilder for PathBuilder {
+        fn move_to(&mut self, x: f32, y: f32) {
+            write!(self.0, "M{} {} ", x, y).unwrap();
+        }
+
+        fn line_to(&mut self, x: f32, y: f32) {
+            write!(self.0, "L{} {} ", x, y).unwrap();
+        }
+
+        fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+            write!(self.0, "Q{} {} {} {} ", x1, y1, x, y).unwrap();
+        }
+
+        fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+            write!(self.0, "C{} {} {} {} {} {} ", x1, y1, x2, y2, x, y).unwrap();
+        }
+
+        fn close(&mut self) {
+            self.0.push_str("Z ");
+        }
+    }
+
+    #[test]
+    fn outline_glyph() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // 'A'
+        let mut path = PathBuilder(String::new());
+        assert!(font.outline_glyph(36, &mut path));
+        assert_eq!(path.0,
+            "M1120 0 L938 465 L352 465 L172 0 L0 0 L578 1468 L721 1468 L1296 0 L1120 0 Z \
+             M885 618 L715 1071 Q682 1157 647 1282 Q625 1186 584 1071 L412 618 L885 618 Z ");
+
+        // Streaming composite glyphs matches their resolved outlines.
+        for &glyph_id in &[36, 82, 131] {
+            let mut streamed = PathBuilder(String::new());
+            let mut resolved = PathBuilder(String::new());
+            let transform = Transform::scale(0.5, 0.5).then(&Transform::translate(10.0, -4.0));
+
+            assert!(font.outline_glyph_transformed(glyph_id, &transform, &mut streamed));
+            font.get_glyph_outline(glyph_id)
+                .expect("unable to resolve outline")
+                .draw(&transform, &mut resolved);
+
+            assert_eq!(streamed.0, resolved.0);
+        }
+
+        // ' ' has no outline.
+        let mut path = PathBuilder(String::new());
+        assert!(font.outline_glyph(3, &mut path));
+        assert!(path.0.is_empty());
+    }
+
+    #[test]
+    fn transform() {
+        let t = Transform::scale(2.0, 3.0).then(&Transform::translate(1.0, 1.0));
+        assert_eq!(t.apply(1.0, 1.0), (3.0, 4.0));
+
+        let t = Transform::translate(1.0, 1.0).then(&Transform::scale(2.0, 3.0));
+        assert_eq!(t.apply(1.0, 1.0), (4.0, 6.0));
+
+        // A quarter turn followed by a shear.
+        let rotate = Transform { xx: 0.0, xy: 1.0, yx: -1.0, yy: 0.0, dx: 0.0, dy: 0.0 };
+        let shear = Transform { yx: 0.5, ..Transform::identity() };
+        assert_eq!(rotate.then(&shear).apply(2.0, 0.0), (1.0, 2.0));
+    }
+}
@@ -0,0 +1,555 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use outline::{OutlineBuilder, PathRecorder};
+use geometry::{flatten_quad, flatten_cubic};
+
+/// Maximum distance between curves and the lines standing in for them
+/// while looking for intersections, in the units of the outline.
+const TOLERANCE: f32 = 0.25;
+
+/// Points are rounded to this many steps per unit, so that the ends of
+/// split edges meet exactly.
+const GRID: f64 = 256.0;
+
+/// How far either side of an edge the winding is sampled.
+const OFFSET: f64 = 1.0 / 1024.0;
+
+type Point = (f64, f64);
+
+/// How two shapes are combined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BooleanOp {
+    Union,
+    Intersection,
+    /// The first shape without the second.
+    Difference,
+    Xor,
+}
+
+impl BooleanOp {
+    fn is_inside(&self, a: bool, b: bool) -> bool {
+        match *self {
+            BooleanOp::Union => a || b,
+            BooleanOp::Intersection => a && b,
+            BooleanOp::Difference => a && !b,
+            BooleanOp::Xor => a != b,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Segment {
+    Line,
+    Quad((f32, f32)),
+    Curve((f32, f32), (f32, f32)),
+}
+
+/// A segment of the input, kept so that curves can be restored when
+/// they come through unchanged.
+#[derive(Debug, Copy, Clone)]
+struct Source {
+    segment: Segment,
+    pieces: usize,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Edge {
+    from: Point,
+    to: Point,
+    shape: usize,
+    source: usize,
+    /// The index of the line among those replacing the source.
+    piece: usize,
+    /// Whether the edge is a whole piece, not split by another edge.
+    whole: bool,
+}
+
+fn snap(p: (f32, f32)) -> Point {
+    // Adding zero turns negative zeros positive, so equal points hash
+    // the same.
+    ((p.0 as f64 * GRID).round() / GRID + 0.0, (p.1 as f64 * GRID).round() / GRID + 0.0)
+}
+
+fn cross(a: Point, b: Point) -> f64 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+/// Collects the outlines of both shapes as lines.
+#[derive(Debug, Default)]
+struct EdgeCollector {
+    shape: usize,
+    edges: Vec<Edge>,
+    sources: Vec<Source>,
+    start: (f32, f32),
+    current: (f32, f32),
+    open: bool,
+}
+
+impl EdgeCollector {
+    fn add(&mut self, segment: Segment, points: &[(f32, f32)], end: (f32, f32)) {
+        let source = self.sources.len();
+        let mut pieces = 0;
+        let mut from = snap(self.current);
+        for &p in points {
+            let to = snap(p);
+            if to != from {
+                self.edges.push(Edge { from, to, shape: self.shape, source, piece: pieces, whole: true });
+                pieces += 1;
+                from = to;
+            }
+        }
+
+        if pieces > 0 {
+            self.sources.push(Source { segment, pieces });
+        }
+        self.current = end;
+    }
+
+    fn signed_area(&self, shape: usize) -> f64 {
+        self.edges.iter()
+            .filter(|edge| edge.shape == shape)
+            .map(|edge| cross(edge.from, edge.to))
+            .sum::<f64>() / 2.0
+    }
+}
+
+impl OutlineBuilder for EdgeCollector {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.start = (x, y);
+        self.current = (x, y);
+        self.open = true;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.add(Segment::Line, &[(x, y)], (x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let mut points = Vec::new();
+        flatten_quad(self.current, (x1, y1), (x, y), TOLERANCE, |p| points.push(p));
+        self.add(Segment::Quad((x1, y1)), &points, (x, y));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let mut points = Vec::new();
+        flatten_cubic(self.current, (x1, y1), (x2, y2), (x, y), TOLERANCE, |p| points.push(p));
+        self.add(Segment::Curve((x1, y1), (x2, y2)), &points, (x, y));
+    }
+
+    fn close(&mut self) {
+        if self.open {
+            let start = self.start;
+            self.add(Segment::Line, &[start], start);
+            self.open = false;
+        }
+    }
+}
+
+/// Finds where two edges meet, adding the points inside either of them
+/// to its splits.
+fn intersect(a: &Edge, b: &Edge, splits_a: &mut Vec<Point>, splits_b: &mut Vec<Point>) {
+    if a.from.0.max(a.to.0) < b.from.0.min(b.to.0) || b.from.0.max(b.to.0) < a.from.0.min(a.to.0)
+        || a.from.1.max(a.to.1) < b.from.1.min(b.to.1) || b.from.1.max(b.to.1) < a.from.1.min(a.to.1) {
+        return
+    }
+
+    let (r, s) = (sub(a.to, a.from), sub(b.to, b.from));
+    let qp = sub(b.from, a.from);
+    let denom = cross(r, s);
+    let (r_len, s_len) = (cross(r, (-r.1, r.0)), cross(s, (-s.1, s.0)));
+
+    if denom * denom > 1e-18 * r_len * s_len {
+        let t = cross(qp, s) / denom;
+        let u = cross(qp, r) / denom;
+        let eps = 1e-9;
+        if t < -eps || t > 1.0 + eps || u < -eps || u > 1.0 + eps {
+            return
+        }
+
+        // Ends keep their exact position, so that edges meeting there
+        // are not split.
+        let p = if t <= eps {
+            a.from
+        } else if t >= 1.0 - eps {
+            a.to
+        } else if u <= eps {
+            b.from
+        } else if u >= 1.0 - eps {
+            b.to
+        } else {
+            snap(((a.from.0 + t * r.0) as f32, (a.from.1 + t * r.1) as f32))
+        };
+
+        if p != a.from && p != a.to {
+            splits_a.push(p);
+        }
+        if p != b.from && p != b.to {
+            splits_b.push(p);
+        }
+    } else if cross(qp, r).abs() <= 1e-9 * r_len.sqrt() {
+        // Collinear edges are split where the other one ends.
+        let inside = |p: Point, from: Point, d: Point, len: f64| {
+            let t = (sub(p, from).0 * d.0 + sub(p, from).1 * d.1) / len;
+            t > 0.0 && t < 1.0
+        };
+        for &p in &[b.from, b.to] {
+            if inside(p, a.from, r, r_len) && p != a.from && p != a.to {
+                splits_a.push(p);
+            }
+        }
+        for &p in &[a.from, a.to] {
+            if inside(p, b.from, s, s_len) && p != b.from && p != b.to {
+                splits_b.push(p);
+            }
+        }
+    }
+}
+
+/// Splits every edge where it meets another one.
+fn split(edges: &[Edge]) -> Vec<Edge> {
+    let mut splits: Vec<Vec<Point>> = vec![Vec::new(); edges.len()];
+    for i in 0..edges.len() {
+        let (head, tail) = splits.split_at_mut(i + 1);
+        for j in i + 1..edges.len() {
+            intersect(&edges[i], &edges[j], &mut head[i], &mut tail[j - i - 1]);
+        }
+    }
+
+    let mut result = Vec::with_capacity(edges.len());
+    for (edge, mut points) in edges.iter().zip(splits) {
+        if points.is_empty() {
+            result.push(*edge);
+            continue
+        }
+
+        let d = sub(edge.to, edge.from);
+        let along = |p: &Point| sub(*p, edge.from).0 * d.0 + sub(*p, edge.from).1 * d.1;
+        points.sort_by(|p, q| along(p).partial_cmp(&along(q)).unwrap_or(Ordering::Equal));
+        points.dedup();
+        points.push(edge.to);
+
+        let mut from = edge.from;
+        for to in points {
+            if to != from {
+                result.push(Edge { from, to, whole: false, ..*edge });
+                from = to;
+            }
+        }
+    }
+    result
+}
+
+/// The non-zero winding number of a shape at a point.
+fn winding(edges: &[Edge], shape: usize, p: Point) -> i32 {
+    edges.iter().filter(|edge| edge.shape == shape).map(|edge| {
+        let (a, b) = (edge.from, edge.to);
+        let side = cross(sub(b, a), sub(p, a));
+        if a.1 <= p.1 && b.1 > p.1 && side > 0.0 {
+            1
+        } else if b.1 <= p.1 && a.1 > p.1 && side < 0.0 {
+            -1
+        } else {
+            0
+        }
+    }).sum()
+}
+
+/// An edge of the result, with the inside on its left.
+#[derive(Debug, Copy, Clone)]
+struct Directed {
+    edge: Edge,
+    /// Whether the edge runs the same way as its source.
+    forward: bool,
+}
+
+impl Directed {
+    fn from(&self) -> Point {
+        if self.forward { self.edge.from } else { self.edge.to }
+    }
+
+    fn to(&self) -> Point {
+        if self.forward { self.edge.to } else { self.edge.from }
+    }
+
+    /// Whether `next` carries on along the same curve.
+    fn continues_into(&self, next: &Directed) -> bool {
+        let (a, b) = (&self.edge, &next.edge);
+        a.whole && b.whole && a.source == b.source && self.forward == next.forward
+            && if self.forward { b.piece == a.piece + 1 } else { b.piece + 1 == a.piece }
+    }
+}
+
+/// Keeps the edges with the inside of the result on one side only.
+/// Winding numbers are found from the split edges, whose ends may have
+/// moved slightly off the original edges when snapped.
+fn classify(edges: Vec<Edge>, op: BooleanOp) -> Vec<Directed> {
+    // Edges drawn more than once are only kept once.
+    let key = |p: Point| (p.0.to_bits(), p.1.to_bits());
+    let mut groups: HashMap<_, Vec<Edge>> = HashMap::new();
+    let mut order = Vec::new();
+    for &edge in &edges {
+        let (a, b) = (key(edge.from), key(edge.to));
+        let id = if a < b { (a, b) } else { (b, a) };
+        let group = groups.entry(id).or_insert_with(Vec::new);
+        if group.is_empty() {
+            order.push(id);
+        }
+        group.push(edge);
+    }
+
+    let inside = |p: Point| {
+        op.is_inside(winding(&edges, 0, p) != 0, winding(&edges, 1, p) != 0)
+    };
+
+    let mut result = Vec::new();
+    for id in order {
+        let group = &groups[&id];
+        let edge = group[0];
+        let d = sub(edge.to, edge.from);
+        let len = (d.0 * d.0 + d.1 * d.1).sqrt();
+        let mid = ((edge.from.0 + edge.to.0) / 2.0, (edge.from.1 + edge.to.1) / 2.0);
+        let normal = (-d.1 / len * OFFSET, d.0 / len * OFFSET);
+        let left = inside((mid.0 + normal.0, mid.1 + normal.1));
+        let right = inside((mid.0 - normal.0, mid.1 - normal.1));
+        if left == right {
+            continue
+        }
+
+        // Prefer an edge already running the right way, keeping it
+        // part of its curve.
+        let from = if left { edge.from } else { edge.to };
+        let edge = group.iter().find(|e| e.from == from && e.whole).cloned().unwrap_or(edge);
+        result.push(Directed { edge, forward: edge.from == from });
+    }
+    result
+}
+
+/// Links edges end to end into closed contours.
+fn link(edges: &[Directed]) -> Vec<Vec<Directed>> {
+    let key = |p: Point| (p.0.to_bits(), p.1.to_bits());
+    let mut outgoing: HashMap<_, Vec<usize>> = HashMap::new();
+    for (i, edge) in edges.iter().enumerate() {
+        outgoing.entry(key(edge.from())).or_insert_with(Vec::new).push(i);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut contours = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue
+        }
+
+        used[first] = true;
+        let start = edges[first].from();
+        let mut contour = vec![edges[first]];
+        loop {
+            let last = *contour.last().unwrap();
+            if last.to() == start {
+                break
+            }
+
+            let next = outgoing.get(&key(last.to())).and_then(|candidates| {
+                let mut free = candidates.iter().cloned().filter(|&i| !used[i]);
+                let first_free = free.next();
+                candidates.iter().cloned()
+                    .find(|&i| !used[i] && last.continues_into(&edges[i]))
+                    .or(first_free)
+            });
+
+            match next {
+                Some(i) => {
+                    used[i] = true;
+                    contour.push(edges[i]);
+                },
+                None => break,
+            }
+        }
+
+        // Start at the beginning of a curve rather than in the middle.
+        if let Some(i) = (0..contour.len()).find(|&i| {
+            let previous = &contour[(i + contour.len() - 1) % contour.len()];
+            !previous.continues_into(&contour[i])
+        }) {
+            contour.rotate_left(i);
+        }
+        contours.push(contour);
+    }
+    contours
+}
+
+fn emit<B: OutlineBuilder>(contours: &[Vec<Directed>], sources: &[Source], reverse: bool, builder: &mut B) {
+    for contour in contours {
+        let mut contour = contour.clone();
+        if reverse {
+            contour.reverse();
+            for edge in &mut contour {
+                edge.forward = !edge.forward;
+            }
+        }
+
+        let start = contour[0].from();
+        builder.move_to(start.0 as f32, start.1 as f32);
+
+        let mut i = 0;
+        while i < contour.len() {
+            let edge = contour[i];
+            let source = sources[edge.edge.source];
+            let first_piece = if edge.forward { 0 } else { source.pieces - 1 };
+
+            // A curve is restored when all of its pieces are present.
+            let mut run = 1;
+            if edge.edge.whole && edge.edge.piece == first_piece {
+                while run < source.pieces && i + run < contour.len()
+                    && contour[i + run - 1].continues_into(&contour[i + run]) {
+                    run += 1;
+                }
+            }
+
+            if run < source.pieces {
+                run = 1;
+            }
+
+            let to = contour[i + run - 1].to();
+            let (x, y) = (to.0 as f32, to.1 as f32);
+            match source.segment {
+                _ if run < source.pieces => builder.line_to(x, y),
+                Segment::Line => builder.line_to(x, y),
+                Segment::Quad(p1) => builder.quad_to(p1.0, p1.1, x, y),
+                Segment::Curve(p1, p2) if edge.forward => builder.curve_to(p1.0, p1.1, p2.0, p2.1, x, y),
+                Segment::Curve(p1, p2) => builder.curve_to(p2.0, p2.1, p1.0, p1.1, x, y),
+            }
+
+            i += run;
+        }
+        builder.close();
+    }
+}
+
+fn combine<B: OutlineBuilder>(a: &PathRecorder, b: Option<&PathRecorder>, op: BooleanOp, builder: &mut B) {
+    let mut collector = EdgeCollector::default();
+    a.replay(&mut collector);
+    collector.close();
+    if let Some(b) = b {
+        collector.shape = 1;
+        b.replay(&mut collector);
+        collector.close();
+    }
+
+    let edges = split(&collector.edges);
+    let kept = classify(edges, op);
+    let contours = link(&kept);
+
+    // Contours are found counter-clockwise, and turned around to match
+    // outlines drawn the other way, such as TrueType ones.
+    let reverse = collector.signed_area(0) < 0.0;
+    emit(&contours, &collector.sources, reverse, builder);
+}
+
+/// Redraws an outline without overlapping or self-intersecting contours,
+/// covering the same area under the non-zero rule.  Curves are kept
+/// where they are not cut, and contours keep the direction the outline
+/// used.
+pub fn simplify<B: OutlineBuilder>(path: &PathRecorder, builder: &mut B) {
+    combine(path, None, BooleanOp::Union, builder);
+}
+
+/// Combines two outlines, each filled with the non-zero rule.
+pub fn boolean<B: OutlineBuilder>(a: &PathRecorder, b: &PathRecorder, op: BooleanOp, builder: &mut B) {
+    combine(a, Some(b), op, builder);
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use outline::{OutlineBuilder, PathRecorder, Transform};
+    use geometry::FlattenBuilder;
+    use pathops::{self, BooleanOp};
+    use svg::SvgPathBuilder;
+
+    fn square(x: f32, y: f32, size: f32) -> PathRecorder {
+        let mut path = PathRecorder::new();
+        path.move_to(x, y);
+        path.line_to(x + size, y);
+        path.line_to(x + size, y + size);
+        path.line_to(x, y + size);
+        path.close();
+        path
+    }
+
+    fn areas(path: &PathRecorder) -> Vec<f32> {
+        let mut builder = FlattenBuilder::new(0.25);
+        path.replay(&mut builder);
+        builder.into_contours().iter().map(|c| c.signed_area()).collect()
+    }
+
+    #[test]
+    fn boolean() {
+        let (a, b) = (square(0.0, 0.0, 10.0), square(5.0, 5.0, 10.0));
+        let combine = |op| {
+            let mut path = PathRecorder::new();
+            pathops::boolean(&a, &b, op, &mut path);
+            path
+        };
+
+        let union = combine(BooleanOp::Union);
+        assert_eq!(areas(&union), vec![175.0]);
+        assert_eq!(union.points().len(), 9);
+        assert_eq!(areas(&combine(BooleanOp::Intersection)), vec![25.0]);
+        assert_eq!(areas(&combine(BooleanOp::Difference)), vec![75.0]);
+        assert_eq!(areas(&combine(BooleanOp::Xor)).iter().sum::<f32>(), 150.0);
+
+        // Shapes that do not meet.
+        let mut path = PathRecorder::new();
+        pathops::boolean(&a, &square(20.0, 0.0, 5.0), BooleanOp::Intersection, &mut path);
+        assert!(path.is_empty());
+    }
+
+    #[test]
+    fn simplify() {
+        // Curves that are not cut come through unchanged, in the
+        // direction they were drawn.
+        let mut path = PathRecorder::new();
+        path.move_to(0.0, 0.0);
+        path.quad_to(5.0, 10.0, 10.0, 0.0);
+        path.close();
+        let mut svg = SvgPathBuilder::new();
+        pathops::simplify(&path, &mut svg);
+        assert_eq!(svg.as_str(), "M0 0Q5 10 10 0L0 0Z");
+
+        // A contour drawn twice is only kept once.
+        let mut path = square(0.0, 0.0, 10.0);
+        square(0.0, 0.0, 10.0).replay(&mut path);
+        let mut simplified = PathRecorder::new();
+        pathops::simplify(&path, &mut simplified);
+        assert_eq!(areas(&simplified), vec![100.0]);
+    }
+
+    #[test]
+    fn glyph_overlaps() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // 'Aring', whose ring overlaps the 'A' in this font.
+        let gid = font.get_glyph_buffer("\u{c5}").expect("Unable to map text").glyphs[0].glyph_id;
+        let mut path = PathRecorder::new();
+        assert!(font.outline_glyph(gid, &mut path));
+        let before: f32 = areas(&path).iter().sum();
+
+        let mut simplified = PathRecorder::new();
+        assert!(font.outline_glyph_simplified(gid, &Transform::identity(), &mut simplified));
+        let after: f32 = areas(&simplified).iter().sum();
+        assert!(after.abs() < before.abs());
+        assert!(after < 0.0);
+
+        // Simplifying again changes nothing.
+        let mut again = PathRecorder::new();
+        pathops::simplify(&simplified, &mut again);
+        assert_eq!(areas(&again).len(), areas(&simplified).len());
+        assert!((areas(&again).iter().sum::<f32>() - after).abs() < 1.0);
+    }
+}
@@ -0,0 +1,227 @@
+use outline::OutlineBuilder;
+use geometry;
+
+/// Maximum distance in pixels between a curve and the lines approximating it.
+const TOLERANCE: f32 = 0.1;
+
+/// An 8-bit anti-aliased coverage bitmap of a glyph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    /// Distance in pixels from the pen position to the left edge.
+    pub left: i32,
+    /// Distance in pixels from the baseline up to the top edge.
+    pub top: i32,
+    /// Rows of coverage values, from the top.
+    pub coverage: Vec<u8>,
+}
+
+/// Scan converts outlines by accumulating the signed area each edge
+/// covers in every pixel, giving exact coverage for non-overlapping
+/// contours.  Overlapping contours with the same winding are clamped
+/// to full coverage, approximating the non-zero fill rule.
+///
+/// Coordinates are in pixels, with y pointing down from the top row.
+pub struct Rasterizer {
+    width: usize,
+    height: usize,
+    accumulator: Vec<f32>,
+    start: (f32, f32),
+    current: (f32, f32),
+}
+
+impl Rasterizer {
+    pub fn new(width: usize, height: usize) -> Rasterizer {
+        Rasterizer {
+            width,
+            height,
+            // Edges at the right border spill into the following pixel.
+            accumulator: vec![0.0; width * height + 4],
+            start: (0.0, 0.0),
+            current: (0.0, 0.0),
+        }
+    }
+
+    pub fn draw_line(&mut self, p0: (f32, f32), p1: (f32, f32)) {
+        if p0.1 == p1.1 {
+            return
+        }
+
+        let (dir, p0, p1) = if p0.1 < p1.1 { (1.0, p0, p1) } else { (-1.0, p1, p0) };
+        let dxdy = (p1.0 - p0.0) / (p1.1 - p0.1);
+        let width = self.width as f32;
+
+        let mut x = p0.0;
+        if p0.1 < 0.0 {
+            x -= p0.1 * dxdy;
+        }
+
+        let y_start = p0.1.max(0.0) as usize;
+        let y_end = (p1.1.ceil().max(0.0) as usize).min(self.height);
+
+        for y in y_start..y_end {
+            let row = y * self.width;
+            let dy = ((y + 1) as f32).min(p1.1) - (y as f32).max(p0.1);
+            let x_next = x + dxdy * dy;
+            let d = dy * dir;
+
+            let (x0, x1) = if x < x_next { (x, x_next) } else { (x_next, x) };
+            let (x0, x1) = (x0.max(0.0).min(width), x1.max(0.0).min(width));
+            let x0_floor = x0.floor();
+            let x0i = x0_floor as usize;
+            let x1_ceil = x1.ceil();
+            let x1i = x1_ceil as usize;
+
+            if x1i <= x0i + 1 {
+                // The edge stays within a single pixel of this row.
+                let x_mid = 0.5 * (x0 + x1) - x0_floor;
+                self.accumulator[row + x0i] += d - d * x_mid;
+                self.accumulator[row + x0i + 1] += d * x_mid;
+            } else {
+                let s = (x1 - x0).recip();
+                let x0_fract = x0 - x0_floor;
+                let a0 = 0.5 * s * (1.0 - x0_fract) * (1.0 - x0_fract);
+                let x1_fract = x1 - x1_ceil + 1.0;
+                let a_max = 0.5 * s * x1_fract * x1_fract;
+
+                self.accumulator[row + x0i] += d * a0;
+                if x1i == x0i + 2 {
+                    self.accumulator[row + x0i + 1] += d * (1.0 - a0 - a_max);
+                } else {
+                    let a1 = s * (1.5 - x0_fract);
+                    self.accumulator[row + x0i + 1] += d * (a1 - a0);
+                    for xi in x0i + 2..x1i - 1 {
+                        self.accumulator[row + xi] += d * s;
+                    }
+                    let a2 = a1 + (x1i - x0i - 3) as f32 * s;
+                    self.accumulator[row + x1i - 1] += d * (1.0 - a2 - a_max);
+                }
+                self.accumulator[row + x1i] += d * a_max;
+            }
+
+            x = x_next;
+        }
+    }
+
+    pub fn draw_quad(&mut self, p0: (f32, f32), p1: (f32, f32), p2: (f32, f32)) {
+        let mut p = p0;
+        geometry::flatten_quad(p0, p1, p2, TOLERANCE, |next| {
+            self.draw_line(p, next);
+            p = next;
+        });
+    }
+
+    pub fn draw_cubic(&mut self, p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32)) {
+        let mut p = p0;
+        geometry::flatten_cubic(p0, p1, p2, p3, TOLERANCE, |next| {
+            self.draw_line(p, next);
+            p = next;
+        });
+    }
+
+    /// Sums the accumulated areas into coverage values, row by row.
+    pub fn accumulate(&self) -> Vec<u8> {
+        let mut sum = 0.0;
+        self.accumulator[..self.width * self.height]
+            .iter()
+            .map(|area| {
+                sum += area;
+                (sum.abs().min(1.0) * 255.0 + 0.5) as u8
+            })
+            .collect()
+    }
+}
+
+impl OutlineBuilder for Rasterizer {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.start = (x, y);
+        self.current = (x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let current = self.current;
+        self.draw_line(current, (x, y));
+        self.current = (x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let current = self.current;
+        self.draw_quad(current, (x1, y1), (x, y));
+        self.current = (x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let current = self.current;
+        self.draw_cubic(current, (x1, y1), (x2, y2), (x, y));
+        self.current = (x, y);
+    }
+
+    fn close(&mut self) {
+        let (current, start) = (self.current, self.start);
+        self.draw_line(current, start);
+        self.current = start;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use outline::OutlineBuilder;
+    use raster::Rasterizer;
+
+    fn rect(r: &mut Rasterizer, x0: f32, y0: f32, x1: f32, y1: f32) {
+        r.move_to(x0, y0);
+        r.line_to(x1, y0);
+        r.line_to(x1, y1);
+        r.line_to(x0, y1);
+        r.close();
+    }
+
+    #[test]
+    fn coverage() {
+        let mut r = Rasterizer::new(3, 3);
+        rect(&mut r, 0.5, 0.5, 2.5, 2.5);
+        assert_eq!(r.accumulate(), vec![
+            64, 128, 64,
+            128, 255, 128,
+            64, 128, 64,
+        ]);
+
+        // A diagonal halves the pixels it crosses.
+        let mut r = Rasterizer::new(2, 2);
+        r.move_to(0.0, 0.0);
+        r.line_to(2.0, 2.0);
+        r.line_to(0.0, 2.0);
+        r.close();
+        assert_eq!(r.accumulate(), vec![128, 0, 255, 128]);
+
+        // A quadratic curve within a single pixel row.
+        let mut r = Rasterizer::new(4, 1);
+        r.move_to(0.0, 0.0);
+        r.quad_to(2.0, 0.0, 4.0, 0.0);
+        r.line_to(4.0, 1.0);
+        r.line_to(0.0, 1.0);
+        r.close();
+        assert_eq!(r.accumulate(), vec![255; 4]);
+    }
+
+    #[test]
+    fn winding() {
+        // Overlapping contours with the same direction stay covered.
+        let mut r = Rasterizer::new(3, 1);
+        rect(&mut r, 0.0, 0.0, 2.0, 1.0);
+        rect(&mut r, 1.0, 0.0, 3.0, 1.0);
+        assert_eq!(r.accumulate(), vec![255, 255, 255]);
+
+        // A reversed inner contour cuts a hole.
+        let mut r = Rasterizer::new(3, 3);
+        rect(&mut r, 0.0, 0.0, 3.0, 3.0);
+        rect(&mut r, 1.0, 2.0, 2.0, 1.0);
+        assert_eq!(r.accumulate(), vec![
+            255, 255, 255,
+            255, 0, 255,
+            255, 255, 255,
+        ]);
+    }
+}
@@ -0,0 +1,464 @@
+use outline::OutlineBuilder;
+use geometry::{self, Contour};
+use metrics::BoundingBox;
+
+/// Maximum distance in pixels between a curve and the lines measured
+/// in its place.
+const TOLERANCE: f32 = 0.02;
+
+/// Sine of the smallest turn between two edges that makes a corner.
+const CORNER_THRESHOLD: f32 = 0.14;
+
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const CYAN: u8 = GREEN | BLUE;
+const MAGENTA: u8 = RED | BLUE;
+const WHITE: u8 = RED | GREEN | BLUE;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FieldKind {
+    /// A single channel holding the distance to the nearest edge.
+    Sdf,
+    /// Red, green and blue channels, each measuring the distance to
+    /// edges of its color.  The median of the three keeps corners sharp.
+    Msdf,
+}
+
+impl FieldKind {
+    pub fn channels(&self) -> usize {
+        match *self {
+            FieldKind::Sdf => 1,
+            FieldKind::Msdf => 3,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FieldOptions {
+    pub kind: FieldKind,
+    /// Width in pixels of the band of distances that can be stored, half
+    /// inside and half outside the outline.
+    pub range: f32,
+    /// Pixels added around the outline on each side.
+    pub padding: u32,
+}
+
+impl Default for FieldOptions {
+    fn default() -> FieldOptions {
+        FieldOptions { kind: FieldKind::Sdf, range: 4.0, padding: 2 }
+    }
+}
+
+/// A distance field of a glyph, where 128 lies on the outline and
+/// larger values are inside.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistanceField {
+    pub width: usize,
+    pub height: usize,
+    pub channels: usize,
+    /// Rows of interleaved channels, from the top.
+    pub data: Vec<u8>,
+    /// The area covered by the bitmap in font units, for placing it
+    /// relative to the glyph origin at any size.
+    pub bounds: BoundingBox<f32>,
+    /// The range in font units.
+    pub range: f32,
+}
+
+/// A curve or line of an outline, flattened into lines.
+#[derive(Debug, Clone)]
+struct Edge {
+    points: Vec<(f32, f32)>,
+    start_tangent: (f32, f32),
+    end_tangent: (f32, f32),
+    color: u8,
+}
+
+impl Edge {
+    fn reverse(&mut self) {
+        self.points.reverse();
+        let (start, end) = (self.start_tangent, self.end_tangent);
+        self.start_tangent = (-end.0, -end.1);
+        self.end_tangent = (-start.0, -start.1);
+    }
+
+    /// Splits the edge into one edge per line.
+    fn split(&self) -> Vec<Edge> {
+        self.points.windows(2).map(|line| {
+            let direction = sub(line[1], line[0]);
+            Edge { points: line.to_vec(), start_tangent: direction, end_tangent: direction, color: self.color }
+        }).collect()
+    }
+}
+
+fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn dot(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.0 + a.1 * b.1
+}
+
+fn cross(a: (f32, f32), b: (f32, f32)) -> f32 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn normalize(v: (f32, f32)) -> (f32, f32) {
+    let len = dot(v, v).sqrt();
+    if len == 0.0 { (0.0, 0.0) } else { (v.0 / len, v.1 / len) }
+}
+
+/// The tangent of a curve at its start, from the first control point
+/// that differs from the start.
+fn tangent(points: &[(f32, f32)]) -> (f32, f32) {
+    points[1..].iter()
+        .map(|&p| sub(p, points[0]))
+        .find(|&d| d != (0.0, 0.0))
+        .unwrap_or((0.0, 0.0))
+}
+
+/// Collects an outline as edges for measuring distances.  Coordinates
+/// are in pixels, with the bitmap's top left corner at the origin.
+#[derive(Debug, Clone, Default)]
+pub struct DistanceFieldBuilder {
+    contours: Vec<Vec<Edge>>,
+    current: Vec<Edge>,
+    start: (f32, f32),
+    last: (f32, f32),
+}
+
+impl DistanceFieldBuilder {
+    pub fn new() -> DistanceFieldBuilder {
+        DistanceFieldBuilder::default()
+    }
+
+    fn push(&mut self, controls: &[(f32, f32)], points: Vec<(f32, f32)>) {
+        let start_tangent = tangent(controls);
+        if start_tangent == (0.0, 0.0) {
+            return
+        }
+
+        let reversed: Vec<(f32, f32)> = controls.iter().rev().cloned().collect();
+        let end = tangent(&reversed);
+        self.current.push(Edge { points, start_tangent, end_tangent: (-end.0, -end.1), color: WHITE });
+        self.last = *controls.last().unwrap();
+    }
+
+    /// Orients contours for non-zero filling, so that the inside lies to
+    /// the left of every edge.
+    fn orient(&mut self) {
+        let polygons: Vec<Contour> = self.contours.iter().map(|edges| {
+            Contour { points: edges.iter().flat_map(|e| e.points[1..].iter().cloned()).collect() }
+        }).collect();
+
+        for ((edges, polygon), nesting) in self.contours.iter_mut().zip(&polygons).zip(geometry::nesting(&polygons)) {
+            if polygon.orientation() != nesting.orientation() {
+                edges.reverse();
+                for edge in edges.iter_mut() {
+                    edge.reverse();
+                }
+            }
+        }
+    }
+
+    /// Measures the distance from the center of every pixel to the
+    /// outline.
+    pub fn render(mut self, width: usize, height: usize, options: &FieldOptions) -> Vec<u8> {
+        self.close();
+        self.orient();
+        if options.kind == FieldKind::Msdf {
+            for edges in &mut self.contours {
+                color_edges(edges);
+            }
+        }
+
+        let channels = options.kind.channels();
+        let mut data = Vec::with_capacity(width * height * channels);
+        let encode = |distance: f32| {
+            ((0.5 + distance / options.range).clamp(0.0, 1.0) * 255.0 + 0.5) as u8
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let p = (x as f32 + 0.5, y as f32 + 0.5);
+                match options.kind {
+                    FieldKind::Sdf => {
+                        let distance = self.nearest(p, WHITE).distance;
+                        let sign = if self.winding(p) != 0 { 1.0 } else { -1.0 };
+                        data.push(encode(sign * distance));
+                    },
+                    FieldKind::Msdf => {
+                        for &channel in &[RED, GREEN, BLUE] {
+                            data.push(encode(self.nearest(p, channel).pseudo_distance));
+                        }
+                    },
+                }
+            }
+        }
+
+        data
+    }
+
+    /// Finds the closest edge having any of the given channels.
+    fn nearest(&self, p: (f32, f32), channels: u8) -> Candidate {
+        let mut best = Candidate {
+            distance: f32::INFINITY,
+            orthogonality: 0.0,
+            pseudo_distance: -f32::INFINITY,
+        };
+
+        for edge in self.contours.iter().flatten() {
+            if edge.color & channels == 0 {
+                continue
+            }
+
+            let last = edge.points.len() - 2;
+            for (i, line) in edge.points.windows(2).enumerate() {
+                let candidate = Candidate::new(p, line[0], line[1], i == 0, i == last);
+                if candidate.is_closer(&best) {
+                    best = candidate;
+                }
+            }
+        }
+
+        best
+    }
+
+    /// The non-zero winding number of the outline around a point.
+    fn winding(&self, p: (f32, f32)) -> i32 {
+        let mut winding = 0;
+        for edge in self.contours.iter().flatten() {
+            for line in edge.points.windows(2) {
+                let (a, b) = (line[0], line[1]);
+                let side = cross(sub(b, a), sub(p, a));
+                if a.1 <= p.1 && p.1 < b.1 && side > 0.0 {
+                    winding += 1;
+                } else if b.1 <= p.1 && p.1 < a.1 && side < 0.0 {
+                    winding -= 1;
+                }
+            }
+        }
+        winding
+    }
+}
+
+impl OutlineBuilder for DistanceFieldBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.close();
+        self.start = (x, y);
+        self.last = (x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let last = self.last;
+        self.push(&[last, (x, y)], vec![last, (x, y)]);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let last = self.last;
+        let mut points = vec![last];
+        geometry::flatten_quad(last, (x1, y1), (x, y), TOLERANCE, |p| points.push(p));
+        self.push(&[last, (x1, y1), (x, y)], points);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let last = self.last;
+        let mut points = vec![last];
+        geometry::flatten_cubic(last, (x1, y1), (x2, y2), (x, y), TOLERANCE, |p| points.push(p));
+        self.push(&[last, (x1, y1), (x2, y2), (x, y)], points);
+    }
+
+    fn close(&mut self) {
+        let start = self.start;
+        self.line_to(start.0, start.1);
+        if !self.current.is_empty() {
+            let edges = ::std::mem::take(&mut self.current);
+            self.contours.push(edges);
+        }
+    }
+}
+
+/// The distance from a point to a line of an edge.
+#[derive(Debug, Copy, Clone)]
+struct Candidate {
+    distance: f32,
+    /// How close to perpendicular the line is to the direction of the
+    /// point, which decides between lines meeting at a vertex.
+    orthogonality: f32,
+    /// The signed distance to the line extended past the ends of its
+    /// edge, positive inside.
+    pseudo_distance: f32,
+}
+
+impl Candidate {
+    fn new(p: (f32, f32), a: (f32, f32), b: (f32, f32), first: bool, last: bool) -> Candidate {
+        let direction = sub(b, a);
+        let length = dot(direction, direction).sqrt();
+        let t = dot(sub(p, a), direction) / (length * length);
+        let side = cross(direction, sub(p, a));
+        let sign = if side > 0.0 { 1.0 } else { -1.0 };
+
+        let nearest = if t <= 0.0 { a } else if t >= 1.0 { b } else {
+            (a.0 + t * direction.0, a.1 + t * direction.1)
+        };
+        let distance = dot(sub(p, nearest), sub(p, nearest)).sqrt();
+
+        let orthogonality = if t > 0.0 && t < 1.0 {
+            1.0
+        } else {
+            cross(normalize(direction), normalize(sub(p, nearest))).abs()
+        };
+
+        let extended = (t < 0.0 && first) || (t > 1.0 && last);
+        let pseudo_distance = if extended { side / length } else { sign * distance };
+
+        Candidate { distance, orthogonality, pseudo_distance }
+    }
+
+    fn is_closer(&self, other: &Candidate) -> bool {
+        let epsilon = 1e-5;
+        if (self.distance - other.distance).abs() <= epsilon {
+            self.orthogonality > other.orthogonality
+        } else {
+            self.distance < other.distance
+        }
+    }
+}
+
+/// Alternates edge colors at corners, so that the two edges meeting at
+/// a corner always share exactly one channel.
+fn switch_color(color: u8, banned: u8) -> u8 {
+    let combined = color & banned;
+    if combined == RED || combined == GREEN || combined == BLUE {
+        return combined ^ WHITE
+    }
+
+    if color == 0 || color == WHITE {
+        return CYAN
+    }
+
+    let shifted = color << 1;
+    (shifted | shifted >> 3) & WHITE
+}
+
+fn is_corner(incoming: (f32, f32), outgoing: (f32, f32)) -> bool {
+    let (a, b) = (normalize(incoming), normalize(outgoing));
+    dot(a, b) <= 0.0 || cross(a, b).abs() > CORNER_THRESHOLD
+}
+
+/// Colors the edges of a contour, following the simple strategy of
+/// msdfgen.
+fn color_edges(edges: &mut Vec<Edge>) {
+    let n = edges.len();
+    let corners: Vec<usize> = (0..n)
+        .filter(|&i| is_corner(edges[(i + n - 1) % n].end_tangent, edges[i].start_tangent))
+        .collect();
+
+    match corners.len() {
+        0 => {
+            for edge in edges.iter_mut() {
+                edge.color = WHITE;
+            }
+        },
+        1 => {
+            // A teardrop has no second corner to switch colors at, so
+            // it is split into thirds.
+            if n < 3 {
+                let start = corners[0];
+                *edges = (0..n).flat_map(|i| edges[(start + i) % n].split()).collect();
+                if edges.len() < 3 {
+                    return
+                }
+                color_edges(edges);
+                return
+            }
+
+            let colors = [CYAN, WHITE, MAGENTA];
+            let start = corners[0];
+            for i in 0..n {
+                edges[(start + i) % n].color = colors[3 * i / n];
+            }
+        },
+        count => {
+            let start = corners[0];
+            let initial = switch_color(0, 0);
+            let mut color = initial;
+            let mut spline = 0;
+            for i in 0..n {
+                let index = (start + i) % n;
+                if spline + 1 < count && corners[spline + 1] == index {
+                    spline += 1;
+                    color = switch_color(color, if spline == count - 1 { initial } else { 0 });
+                }
+                edges[index].color = color;
+            }
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use metrics::Size;
+    use sdf::{FieldKind, FieldOptions};
+
+    fn median(a: u8, b: u8, c: u8) -> u8 {
+        a.max(b).min(a.min(b).max(c))
+    }
+
+    #[test]
+    fn distance_field() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let size = Size::Pixels(32.0);
+        let options = FieldOptions { kind: FieldKind::Sdf, range: 4.0, padding: 3 };
+
+        // 'A', 'o' and 'B'
+        for &glyph_id in &[36, 82, 37] {
+            let sdf = font.get_glyph_distance_field(glyph_id, size, &options)
+                .expect("Unable to generate SDF");
+            let bitmap = font.rasterize_glyph(glyph_id, size, (0.0, 0.0))
+                .expect("Unable to rasterize glyph");
+
+            // The padding surrounds the rasterized glyph.
+            assert_eq!((sdf.width, sdf.height), (bitmap.width + 6, bitmap.height + 6));
+            assert_eq!(sdf.channels, 1);
+            assert_eq!(sdf.range, 4.0 * 2048.0 / 32.0);
+            assert_eq!(sdf.bounds.x_min * 32.0 / 2048.0, (bitmap.left - 3) as f32);
+            assert_eq!(sdf.bounds.y_max * 32.0 / 2048.0, (bitmap.top + 3) as f32);
+
+            // Pixels fully covered are inside, and empty pixels outside.
+            for y in 0..bitmap.height {
+                for x in 0..bitmap.width {
+                    let coverage = bitmap.coverage[y * bitmap.width + x];
+                    let distance = sdf.data[(y + 3) * sdf.width + x + 3];
+                    if coverage == 255 {
+                        assert!(distance > 128, "glyph {} at {}, {}", glyph_id, x, y);
+                    } else if coverage == 0 {
+                        assert!(distance < 128, "glyph {} at {}, {}", glyph_id, x, y);
+                    }
+                }
+            }
+
+            // The median of the channels agrees with the single channel
+            // away from the outline.
+            let msdf = font.get_glyph_distance_field(glyph_id, size,
+                                                     &FieldOptions { kind: FieldKind::Msdf, ..options })
+                .expect("Unable to generate MSDF");
+            assert_eq!(msdf.channels, 3);
+            assert_eq!(msdf.data.len(), sdf.data.len() * 3);
+            for (i, &distance) in sdf.data.iter().enumerate() {
+                let m = median(msdf.data[3 * i], msdf.data[3 * i + 1], msdf.data[3 * i + 2]);
+                if !(116..=140).contains(&distance) {
+                    assert_eq!(m > 128, distance > 128, "glyph {} at {}", glyph_id, i);
+                }
+            }
+        }
+
+        // ' ' has no outline.
+        let space = font.get_glyph_distance_field(3, size, &options).expect("Unable to generate SDF");
+        assert!(space.data.is_empty());
+    }
+}
+
@@ -0,0 +1,219 @@
+use std::fmt::Write;
+use outline::OutlineBuilder;
+use metrics::BoundingBox;
+
+/// Writes an outline as the `d` attribute of an SVG path.  Coordinates
+/// are written as given, so outlines in font units should be flipped
+/// to point y down.
+#[derive(Debug, Clone, Default)]
+pub struct SvgPathBuilder {
+    d: String,
+}
+
+impl SvgPathBuilder {
+    pub fn new() -> SvgPathBuilder {
+        SvgPathBuilder::default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.d
+    }
+
+    pub fn into_string(self) -> String {
+        self.d
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.d.is_empty()
+    }
+
+    fn command(&mut self, command: char, coords: &[f32]) {
+        self.d.push(command);
+        for (i, &value) in coords.iter().enumerate() {
+            if i > 0 {
+                self.d.push(' ');
+            }
+            write_number(&mut self.d, value);
+        }
+    }
+}
+
+impl OutlineBuilder for SvgPathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.command('M', &[x, y]);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.command('L', &[x, y]);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.command('Q', &[x1, y1, x, y]);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.command('C', &[x1, y1, x2, y2, x, y]);
+    }
+
+    fn close(&mut self) {
+        self.d.push('Z');
+    }
+}
+
+/// Writes a number with at most two decimals and no trailing zeros.
+fn write_number(out: &mut String, value: f32) {
+    let rounded = (value * 100.0).round() / 100.0;
+    if rounded == rounded.trunc() {
+        write!(out, "{}", rounded as i64).unwrap();
+    } else {
+        write!(out, "{}", rounded).unwrap();
+    }
+}
+
+/// The area of a glyph run shown by an SVG document.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ViewBox {
+    /// The bounding box of all glyphs from `head`, placed at each glyph.
+    /// Runs of the same length share the same box.
+    FontBounds,
+    /// The bounds of the outlines in the run.
+    Extents,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SvgOptions {
+    pub view_box: ViewBox,
+    /// Define each distinct glyph once as a `<symbol>`, placed by `<use>`.
+    pub symbols: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions { view_box: ViewBox::FontBounds, symbols: false }
+    }
+}
+
+/// Assembles an SVG document from glyph paths in font units, with y down.
+#[derive(Debug, Clone)]
+pub struct SvgDocument {
+    symbols: bool,
+    defined: Vec<u16>,
+    defs: String,
+    body: String,
+}
+
+impl SvgDocument {
+    pub fn new(symbols: bool) -> SvgDocument {
+        SvgDocument { symbols, defined: Vec::new(), defs: String::new(), body: String::new() }
+    }
+
+    /// Whether a glyph needs its path, either because symbols are not
+    /// used, or because it has no symbol yet.
+    pub fn needs_path(&self, glyph_id: u16) -> bool {
+        !self.symbols || !self.defined.contains(&glyph_id)
+    }
+
+    /// Places a glyph.  Without symbols the path must already be
+    /// positioned, while symbols are defined at the origin and placed at
+    /// `(x, y)`.  The path is ignored for glyphs that have a symbol.
+    pub fn add_glyph(&mut self, glyph_id: u16, d: &str, x: f32, y: f32) {
+        if !self.symbols {
+            if !d.is_empty() {
+                writeln!(self.body, "<path d=\"{}\"/>", d).unwrap();
+            }
+            return
+        }
+
+        if !self.defined.contains(&glyph_id) {
+            self.defined.push(glyph_id);
+            writeln!(self.defs, "<symbol id=\"glyph{}\" overflow=\"visible\"><path d=\"{}\"/></symbol>",
+                     glyph_id, d).unwrap();
+        }
+
+        write!(self.body, "<use xlink:href=\"#glyph{}\" x=\"", glyph_id).unwrap();
+        write_number(&mut self.body, x);
+        self.body.push_str("\" y=\"");
+        write_number(&mut self.body, y);
+        self.body.push_str("\"/>\n");
+    }
+
+    /// Writes the document, showing the given area in font units with y up.
+    pub fn finish(self, bounds: &BoundingBox<f32>) -> String {
+        let mut svg = String::from("<svg xmlns=\"http://www.w3.org/2000/svg\"");
+        if self.symbols {
+            svg.push_str(" xmlns:xlink=\"http://www.w3.org/1999/xlink\"");
+        }
+
+        svg.push_str(" viewBox=\"");
+        for (i, &value) in [bounds.x_min, -bounds.y_max,
+                            bounds.x_max - bounds.x_min, bounds.y_max - bounds.y_min].iter().enumerate() {
+            if i > 0 {
+                svg.push(' ');
+            }
+            write_number(&mut svg, value);
+        }
+        svg.push_str("\">\n");
+
+        if !self.defs.is_empty() {
+            svg.push_str("<defs>\n");
+            svg.push_str(&self.defs);
+            svg.push_str("</defs>\n");
+        }
+
+        svg.push_str(&self.body);
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use buffer::GlyphBuffer;
+    use outline::OutlineBuilder;
+    use svg::{SvgOptions, SvgPathBuilder, ViewBox};
+
+    #[test]
+    fn path() {
+        let mut path = SvgPathBuilder::new();
+        path.move_to(0.0, -0.0);
+        path.line_to(10.5, 2.25);
+        path.quad_to(1.0 / 3.0, 4.0, -5.0, 6.0);
+        path.curve_to(1.0, 2.0, 3.0, 4.0, 5.0, 6.0);
+        path.close();
+
+        assert_eq!(path.as_str(), "M0 0L10.5 2.25Q0.33 4 -5 6C1 2 3 4 5 6Z");
+    }
+
+    #[test]
+    fn glyph_svg() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // 'A', flipped so that y points down.
+        assert_eq!(font.get_glyph_svg_path(36).expect("unable to read outline"),
+            "M1120 0L938 -465L352 -465L172 0L0 0L578 -1468L721 -1468L1296 0L1120 0Z\
+             M885 -618L715 -1071Q682 -1157 647 -1282Q625 -1186 584 -1071L412 -618L885 -618Z");
+
+        let buffer = font.get_glyph_buffer("A A").expect("Unable to map text");
+        let options = SvgOptions { view_box: ViewBox::Extents, symbols: true };
+        let svg = font.get_glyph_run_svg(&buffer, &options).expect("unable to write SVG");
+
+        // The space has an empty symbol, and 'A' is defined once.
+        let advance = buffer.positions[0].x_advance + buffer.positions[1].x_advance;
+        assert!(svg.starts_with(&format!("<svg xmlns=\"http://www.w3.org/2000/svg\" \
+            xmlns:xlink=\"http://www.w3.org/1999/xlink\" viewBox=\"0 -1468 {} 1468\">\n<defs>\n",
+            advance + 1296)));
+        assert_eq!(svg.matches("<symbol").count(), 2);
+        assert!(svg.contains("<use xlink:href=\"#glyph3\" x=\"1296\" y=\"0\"/>\n"));
+        assert!(svg.ends_with(&format!("<use xlink:href=\"#glyph36\" x=\"{}\" y=\"0\"/>\n</svg>\n", advance)));
+
+        // Without symbols every glyph with an outline has a positioned path.
+        let svg = font.get_glyph_run_svg(&buffer, &SvgOptions::default()).unwrap();
+        assert_eq!(svg.matches("<path").count(), 2);
+        assert!(svg.contains(&format!("<path d=\"M{} 0L", 1120 + advance)));
+        assert!(svg.contains(&format!("viewBox=\"-1126 -2146 {} 2701\"", advance + 2466 + 1126)));
+
+        assert!(font.get_glyph_run_svg(&GlyphBuffer::new(), &options).is_none());
+    }
+}
@@ -0,0 +1,208 @@
+use outline::{PathRecorder, Transform};
+use metrics::{self, Style};
+
+/// The slant of synthetic obliques when the font has no caret slope,
+/// about 12 degrees as in FreeType.
+pub const DEFAULT_SKEW: f32 = 0.2126;
+
+/// Changes applied to outlines to imitate a missing bold or oblique face.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Synthesis {
+    /// How much thicker strokes get, in font units.  Glyphs grow by
+    /// this much to the right and top, and advances by as much.
+    pub embolden: f32,
+    /// The horizontal shift per unit of height.
+    pub skew: f32,
+}
+
+impl Default for Synthesis {
+    fn default() -> Synthesis {
+        Synthesis { embolden: 0.0, skew: 0.0 }
+    }
+}
+
+impl Synthesis {
+    /// The stroke thickening used for synthetic bold, a 24th of the em
+    /// as in FreeType.
+    pub fn bold_strength(units_per_em: u16) -> f32 {
+        units_per_em as f32 / 24.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.embolden == 0.0 && self.skew == 0.0
+    }
+
+    /// The shear slanting outlines about the baseline.
+    pub fn transform(&self) -> Transform {
+        Transform { yx: self.skew, ..Transform::identity() }
+    }
+
+    /// The rounded change to advances in font units.
+    pub fn advance_delta(&self) -> i32 {
+        self.embolden.round() as i32
+    }
+
+    /// The styles added, in addition to those of the face.
+    pub fn style(&self) -> Style {
+        let mut style = Style::empty();
+        if self.embolden != 0.0 {
+            style |= metrics::BOLD | metrics::SYNTHETIC_BOLD;
+        }
+        if self.skew != 0.0 {
+            style |= metrics::ITALIC | metrics::OBLIQUE | metrics::SYNTHETIC_OBLIQUE;
+        }
+        style
+    }
+}
+
+/// Thickens every stroke of a path by moving each point, on or off the
+/// curve, out along the bisector of its neighbouring edges, following
+/// FreeType's `FT_Outline_EmboldenXY`.  The outline then moves by half
+/// the strength, keeping its left and bottom edges in place.
+pub fn embolden(path: &mut PathRecorder, strength: f32) {
+    let contours = path.contours();
+    let points = path.points_mut();
+
+    // Whether holes run clockwise depends on the outer contours.
+    let area: f32 = contours.iter().map(|&(start, end)| {
+        let points = &points[start..end];
+        (0..points.len()).map(|i| {
+            let (p, q) = (points[i], points[(i + 1) % points.len()]);
+            p.0 * q.1 - q.0 * p.1
+        }).sum::<f32>()
+    }).sum();
+
+    for (start, end) in contours {
+        embolden_contour(&mut points[start..end], strength / 2.0, area < 0.0);
+    }
+}
+
+fn normalize(v: (f32, f32)) -> ((f32, f32), f32) {
+    let len = (v.0 * v.0 + v.1 * v.1).sqrt();
+    if len == 0.0 { ((0.0, 0.0), 0.0) } else { ((v.0 / len, v.1 / len), len) }
+}
+
+/// Moves the points of a closed contour outwards by `strength`, and
+/// right and up by as much.  Repeated points move together.
+fn embolden_contour(points: &mut [(f32, f32)], strength: f32, clockwise: bool) {
+    // Runs of equal points, with the last merged into the first when
+    // the contour ends where it started.
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for i in 0..points.len() {
+        match runs.last_mut() {
+            Some(run) if points[run.0] == points[i] => run.1 = i + 1,
+            _ => runs.push((i, i + 1)),
+        }
+    }
+    let wraps = runs.len() > 1 && points[0] == points[points.len() - 1];
+
+    let n = if wraps { runs.len() - 1 } else { runs.len() };
+    let position = |run: usize| points[runs[run % n].0];
+
+    let moved: Vec<(f32, f32)> = (0..n).map(|i| {
+        let p = position(i);
+        let (incoming, l_in) = normalize((p.0 - position(i + n - 1).0, p.1 - position(i + n - 1).1));
+        let (outgoing, l_out) = normalize((position(i + 1).0 - p.0, position(i + 1).1 - p.1));
+
+        // Sharp turns of more than about 160 degrees are not shifted.
+        let d = incoming.0 * outgoing.0 + incoming.1 * outgoing.1;
+        if n < 2 || d <= -0.9375 {
+            return (p.0 + strength, p.1 + strength)
+        }
+
+        let d = d + 1.0;
+        let (mut shift_x, mut shift_y) = (incoming.1 + outgoing.1, incoming.0 + outgoing.0);
+        let mut q = outgoing.0 * incoming.1 - outgoing.1 * incoming.0;
+        if clockwise {
+            shift_x = -shift_x;
+            q = -q;
+        } else {
+            shift_y = -shift_y;
+        }
+
+        // Limit the shift where short edges would collapse.
+        let l = l_in.min(l_out);
+        let limit = |shift: f32| if strength * q <= l * d { shift * strength / d } else { shift * l / q };
+        (p.0 + strength + limit(shift_x), p.1 + strength + limit(shift_y))
+    }).collect();
+
+    for (i, run) in runs.iter().enumerate() {
+        for point in &mut points[run.0..run.1] {
+            *point = moved[i % n];
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use metrics::{self, BoundingBox};
+    use outline::Transform;
+    use outline::BoundsBuilder;
+    use synthesis::{self, Synthesis};
+    use outline::{OutlineBuilder, PathRecorder};
+
+    #[test]
+    fn embolden() {
+        // A clockwise square with a repeated closing point, as glyf
+        // contours are drawn, and a counter-clockwise hole.
+        let mut path = PathRecorder::new();
+        path.move_to(0.0, 0.0);
+        path.line_to(0.0, 10.0);
+        path.line_to(10.0, 10.0);
+        path.line_to(10.0, 0.0);
+        path.line_to(0.0, 0.0);
+        path.close();
+        path.move_to(3.0, 3.0);
+        path.line_to(7.0, 3.0);
+        path.line_to(7.0, 7.0);
+        path.line_to(3.0, 7.0);
+        path.close();
+
+        synthesis::embolden(&mut path, 2.0);
+        assert_eq!(&path.points()[..5], &[(0.0, 0.0), (0.0, 12.0), (12.0, 12.0), (12.0, 0.0), (0.0, 0.0)]);
+        assert_eq!(&path.points()[5..], &[(5.0, 5.0), (7.0, 5.0), (7.0, 7.0), (5.0, 7.0)]);
+    }
+
+    #[test]
+    fn synthesize() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // No caret slope, so the default angle is used.
+        let synthesis = font.get_synthesis(true, true);
+        assert_eq!(synthesis, Synthesis { embolden: 2048.0 / 24.0, skew: 0.2126 });
+
+        let metrics = font.get_font_metrics_synthesized(&synthesis).expect("Unable to read metrics");
+        assert_eq!(metrics.style, metrics::BOLD | metrics::ITALIC | metrics::OBLIQUE
+                                  | metrics::SYNTHETIC_BOLD | metrics::SYNTHETIC_OBLIQUE);
+        assert_eq!(font.get_font_metrics().unwrap().style, metrics::Style::empty());
+
+        // 'l' grows by the strength, rounded out, to the right and top.
+        let bold = Synthesis { skew: 0.0, ..synthesis };
+        let regular = font.get_glyph_metrics(79).expect("Unable to read metrics");
+        let bounds = regular.bounds.unwrap();
+        let metrics = font.get_glyph_metrics_synthesized(79, &bold).expect("Unable to read metrics");
+        assert_eq!(metrics.advance_width, regular.advance_width + 85);
+        assert_eq!(metrics.left_side_bearing, regular.left_side_bearing);
+        assert_eq!(metrics.bounds, Some(BoundingBox {
+            x_min: bounds.x_min,
+            y_min: bounds.y_min,
+            x_max: bounds.x_max + 86,
+            y_max: bounds.y_max + 86,
+        }));
+
+        // The top of the stem leans to the right.
+        let mut builder = BoundsBuilder::new();
+        let oblique = Synthesis { embolden: 0.0, ..synthesis };
+        assert!(font.outline_glyph_synthesized(79, &oblique, &Transform::identity(), &mut builder));
+        assert_eq!(builder.x_min, bounds.x_min as f32);
+        assert_eq!(builder.x_max, bounds.x_max as f32 + bounds.y_max as f32 * 0.2126);
+
+        // Faces that already have the style are left alone.
+        let buf = open_font!("data/DroidSerif-BoldItalic.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        assert!(font.get_synthesis(true, true).is_empty());
+        assert_eq!(font.get_font_metrics().unwrap().style, metrics::BOLD | metrics::ITALIC);
+    }
+}
@@ -0,0 +1,291 @@
+use decode::{Error, Result, Table, Primitive, ReadPrimitive};
+use outline::OutlineBuilder;
+use table::variations::ItemVariationStore;
+use table::cff::{dict, charstring, FdSelect};
+use table::cff::index::Index;
+use table::cff::charstring::{Charstring, Variations};
+
+/// The `CFF2` table, holding PostScript outlines of variable fonts.
+#[derive(Debug)]
+pub struct Cff2<'tbl> {
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub global_subrs: Index<'tbl>,
+    pub charstrings: Index<'tbl>,
+    pub font_matrix: [f32; 6],
+    pub variation_store: Option<ItemVariationStore<'tbl>>,
+    fd_array: Vec<FontDict<'tbl>>,
+    fd_select: Option<FdSelect<'tbl>>,
+}
+
+/// The parts of a Font DICT, and its Private DICT, used by charstrings.
+#[derive(Debug, Copy, Clone)]
+struct FontDict<'tbl> {
+    local_subrs: Index<'tbl>,
+    vsindex: u16,
+}
+
+impl<'tbl> FontDict<'tbl> {
+    fn parse(table: &'tbl [u8], data: &[u8]) -> Result<FontDict<'tbl>> {
+        let mut private = None;
+
+        dict::parse_dict(data, |op, operands| {
+            if op == dict::PRIVATE && operands.len() >= 2 {
+                private = Some((dict::offset(operands[0])?, dict::offset(operands[1])?));
+            }
+            Ok(())
+        })?;
+
+        let (size, offset) = match private {
+            Some(private) => private,
+            None => return Err(Error::InvalidData),
+        };
+
+        let data = match table.get(offset..).and_then(|t| t.get(..size)) {
+            Some(data) => data,
+            None => return Err(Error::UnexpectedEof),
+        };
+        let mut subrs = None;
+        let mut vsindex = 0;
+
+        // Blended values are left unresolved, as neither of these may be blended.
+        dict::parse_dict(data, |op, operands| {
+            match op {
+                dict::SUBRS => subrs = operands.first().cloned().map(dict::offset).transpose()?,
+                dict::VSINDEX => vsindex = operands.first().map(|&v| v as u16).unwrap_or(0),
+                _ => { },
+            }
+            Ok(())
+        })?;
+
+        let local_subrs = match subrs {
+            Some(subrs) => match offset.checked_add(subrs).and_then(|start| table.get(start..)) {
+                Some(mut buf) => Index::read_cff2(&mut buf)?,
+                None => return Err(Error::UnexpectedEof),
+            },
+            None => Index::empty(),
+        };
+
+        Ok(FontDict { local_subrs, vsindex })
+    }
+}
+
+impl<'tbl> Table<'tbl> for Cff2<'tbl> {
+    fn parse(table: &'tbl [u8]) -> Result<Cff2<'tbl>> {
+        let mut buffer = table;
+        let major_version = buffer.read::<u8>()?;
+        let minor_version = buffer.read::<u8>()?;
+        let header_size = buffer.read::<u8>()? as usize;
+        let top_dict_length = buffer.read::<u16>()? as usize;
+
+        verify!(major_version == 2);
+        required_len!(table, header_size + top_dict_length);
+
+        let mut char_strings = None;
+        let mut vstore = None;
+        let mut fd_array = None;
+        let mut fd_select = None;
+        let mut font_matrix = [0.001, 0.0, 0.0, 0.001, 0.0, 0.0];
+
+        let top_dict = &table[header_size..header_size + top_dict_length];
+        dict::parse_dict(top_dict, |op, operands| {
+            let first = operands.first().cloned().map(dict::offset).transpose();
+
+            match op {
+                dict::CHAR_STRINGS => char_strings = first?,
+                dict::VSTORE => vstore = first?,
+                dict::FD_ARRAY => fd_array = first?,
+                dict::FD_SELECT => fd_select = first?,
+                dict::FONT_MATRIX if operands.len() >= 6 => {
+                    for (m, &v) in font_matrix.iter_mut().zip(operands) {
+                        *m = v as f32;
+                    }
+                },
+                _ => { },
+            }
+
+            Ok(())
+        })?;
+
+        let mut buffer = &table[header_size + top_dict_length..];
+        let global_subrs = Index::read_cff2(&mut buffer)?;
+
+        let charstrings = match char_strings.and_then(|offset| table.get(offset..)) {
+            Some(mut buf) => Index::read_cff2(&mut buf)?,
+            None => return Err(Error::InvalidData),
+        };
+
+        // The variation store is preceded by its length.
+        let variation_store = match vstore {
+            Some(offset) => match offset.checked_add(2).and_then(|start| table.get(start..)) {
+                Some(buf) => Some(ItemVariationStore::parse(buf)?),
+                None => return Err(Error::UnexpectedEof),
+            },
+            None => None,
+        };
+
+        let fd_array = match fd_array.and_then(|offset| table.get(offset..)) {
+            Some(mut buf) => Index::read_cff2(&mut buf)?,
+            None => return Err(Error::InvalidData),
+        };
+
+        let fd_array = fd_array.iter()
+            .map(|font_dict| FontDict::parse(table, font_dict))
+            .collect::<Result<Vec<_>>>()?;
+
+        let num_glyphs = charstrings.len() as u16;
+        let fd_select = match fd_select {
+            Some(offset) => match table.get(offset..) {
+                Some(buf) => Some(FdSelect::parse(buf, num_glyphs)?),
+                None => return Err(Error::UnexpectedEof),
+            },
+            None => None,
+        };
+
+        // Without FDSelect every glyph uses the only Font DICT.
+        verify!(fd_select.is_some() || fd_array.len() == 1);
+
+        Ok(Cff2 {
+            major_version,
+            minor_version,
+            global_subrs,
+            charstrings,
+            font_matrix,
+            variation_store,
+            fd_array,
+            fd_select,
+        })
+    }
+}
+
+impl<'tbl> Cff2<'tbl> {
+    pub fn num_glyphs(&self) -> u16 {
+        self.charstrings.len() as u16
+    }
+
+    /// Number of variation axes, or zero if the font does not vary.
+    pub fn axis_count(&self) -> u16 {
+        self.variation_store.map(|store| store.axis_count()).unwrap_or(0)
+    }
+
+    /// Draws the glyph's cubic outline in font units, instanced at the
+    /// normalized coordinates.  Empty coordinates give the default instance.
+    pub fn outline<B: OutlineBuilder>(&self, glyph_id: u16, coords: &[f32],
+                                      builder: &mut B) -> Result<()> {
+        let data = match self.charstrings.get(glyph_id as u32) {
+            Some(data) => data,
+            None => return Err(Error::InvalidData),
+        };
+
+        let fd = match self.fd_select {
+            Some(ref fd_select) => fd_select.get_fd(glyph_id),
+            None => Some(0),
+        };
+
+        let font_dict = match fd.and_then(|fd| self.fd_array.get(fd as usize)) {
+            Some(font_dict) => font_dict,
+            None => return Err(Error::InvalidData),
+        };
+
+        let charstring = Charstring {
+            data,
+            global_subrs: self.global_subrs,
+            local_subrs: font_dict.local_subrs,
+            default_width_x: 0.0,
+            nominal_width_x: 0.0,
+            is_cff2: true,
+            variations: self.variation_store.map(|store| Variations {
+                store,
+                coords,
+                vsindex: font_dict.vsindex,
+            }),
+        };
+
+        charstring::execute(&charstring, builder).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use decode::{Error, Table};
+    use table::cff::cff2::{Cff2, FontDict};
+    use table::cff::test::{PathBuilder, ops, int};
+
+    fn index(items: &[&[u8]]) -> Vec<u8> {
+        let mut buf = vec![0, 0];
+        buf.extend(super::super::test::index(items));
+        buf
+    }
+
+    fn draw(cff2: &Cff2, glyph_id: u16, coords: &[f32]) -> String {
+        let mut builder = PathBuilder(String::new());
+        cff2.outline(glyph_id, coords, &mut builder).expect("unable to draw glyph");
+        builder.0.trim().to_owned()
+    }
+
+    #[test]
+    fn blend() {
+        // One axis, with a region from the default to the maximum.
+        let vstore: &[u8] = &[
+            0x00, 0x1E,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x01, 0x00, 0x00, 0x00, 0x16,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x40, 0x00, 0x40, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+        ];
+
+        let charstrings = index(&[
+            &ops(false, &[&[0, 0, 21], &[-107, 10]]),
+            &ops(false, &[&[0, 0, 21], &[0, 15], &[100, 0, 50, -20, 2, 16], &[5]]),
+        ]);
+        let subrs = index(&[&ops(false, &[&[100, 50, 1, 16], &[0, 5], &[0, 100, 5]])]);
+
+        let top_size = 3 * 6 + 1;
+        let charstrings_offset = 5 + top_size + 4;
+        let vstore_offset = charstrings_offset + charstrings.len();
+        let private_offset = vstore_offset + vstore.len();
+        let private = [int(true, 6), vec![19]].concat();
+        let subrs_offset = private_offset + private.len();
+        let fd_array_offset = subrs_offset + subrs.len();
+        let fd_array = index(&[&ops(true, &[&[6, private_offset as i32, 18]])]);
+
+        let top = ops(true, &[
+            &[charstrings_offset as i32, 17],
+            &[vstore_offset as i32, 24],
+            &[fd_array_offset as i32, 1236],
+        ]);
+        assert_eq!(top.len(), top_size);
+
+        let mut data = vec![2, 0, 5, 0, top_size as u8];
+        data.extend(top);
+        data.extend(&[0, 0, 0, 0]);
+        data.extend(charstrings);
+        data.extend(vstore);
+        data.extend(private);
+        data.extend(subrs);
+        data.extend(fd_array);
+
+        let cff2 = Cff2::parse(&data).expect("unable to parse CFF2");
+        assert_eq!(cff2.num_glyphs(), 2);
+        assert_eq!(cff2.axis_count(), 1);
+
+        assert_eq!(draw(&cff2, 0, &[]), "M0 0 L100 0 L100 100 Z");
+        assert_eq!(draw(&cff2, 0, &[0.5]), "M0 0 L125 0 L125 100 Z");
+        assert_eq!(draw(&cff2, 0, &[1.0]), "M0 0 L150 0 L150 100 Z");
+        assert_eq!(draw(&cff2, 0, &[-1.0]), "M0 0 L100 0 L100 100 Z");
+        assert_eq!(draw(&cff2, 1, &[1.0]), "M0 0 L150 -20 Z");
+    }
+
+    #[test]
+    fn dict_offsets() {
+        let private = ops(true, &[&[0, 22]]);
+        let size = private.len() as i32;
+        let table = [vec![0; 10], private].concat();
+        assert!(FontDict::parse(&table, &ops(true, &[&[size, 10, 18]])).is_ok());
+
+        // A Private DICT offset of 1E30, and a Subrs offset of 1E30.
+        let font_dict = [int(true, size), vec![30, 0x1B, 0x30, 0xFF, 18]].concat();
+        assert!(matches!(FontDict::parse(&table, &font_dict), Err(Error::InvalidData)));
+        let table = [vec![0; 10], vec![30, 0x1B, 0x30, 0xFF, 19]].concat();
+        assert!(matches!(FontDict::parse(&table, &ops(true, &[&[5, 10, 18]])), Err(Error::InvalidData)));
+    }
+}
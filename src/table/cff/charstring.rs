@@ -0,0 +1,613 @@
+use decode::{Error, Result, Primitive, ReadPrimitive};
+use outline::OutlineBuilder;
+use table::cff::index::Index;
+use table::variations::ItemVariationStore;
+
+const MAX_STACK: usize = 513;
+const MAX_CALL_DEPTH: u8 = 10;
+const TRANSIENT_SIZE: usize = 32;
+
+/// A Type 2 charstring along with the subroutines it may call.
+#[derive(Debug, Copy, Clone)]
+pub struct Charstring<'a> {
+    pub data: &'a [u8],
+    pub global_subrs: Index<'a>,
+    pub local_subrs: Index<'a>,
+    pub default_width_x: f32,
+    pub nominal_width_x: f32,
+    /// `CFF2` charstrings have no width, `endchar` or `return`.
+    pub is_cff2: bool,
+    pub variations: Option<Variations<'a>>,
+}
+
+/// The location at which `CFF2` blends are evaluated.
+#[derive(Debug, Copy, Clone)]
+pub struct Variations<'a> {
+    pub store: ItemVariationStore<'a>,
+    /// Normalized coordinates for each axis.
+    pub coords: &'a [f32],
+    /// The item variation data used until a `vsindex` operator.
+    pub vsindex: u16,
+}
+
+/// A request from `endchar` to draw an accented character, composed
+/// from two glyphs given by their Standard Encoding codes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Seac {
+    pub adx: f32,
+    pub ady: f32,
+    pub base: u8,
+    pub accent: u8,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CharstringResult {
+    pub width: f32,
+    pub seac: Option<Seac>,
+}
+
+/// Interprets a charstring, passing the outline to the builder.  Hints
+/// are read and discarded.
+pub fn execute<B: OutlineBuilder>(charstring: &Charstring, builder: &mut B) -> Result<CharstringResult> {
+    let mut interpreter = Interpreter {
+        cs: charstring,
+        builder,
+        stack: [0.0; MAX_STACK],
+        sp: 0,
+        transient: [0.0; TRANSIENT_SIZE],
+        x: 0.0,
+        y: 0.0,
+        open: false,
+        num_hints: 0,
+        width: None,
+        seac: None,
+        vsindex: charstring.variations.map(|v| v.vsindex).unwrap_or(0),
+        scalars: None,
+    };
+
+    interpreter.run(charstring.data, 0)?;
+    interpreter.close_path();
+
+    Ok(CharstringResult {
+        width: interpreter.width.unwrap_or(charstring.default_width_x),
+        seac: interpreter.seac,
+    })
+}
+
+/// The bias added to subroutine numbers, which depends on the number of subroutines.
+pub fn subr_bias(count: u32) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+struct Interpreter<'a, 'b, B: OutlineBuilder + 'b> {
+    cs: &'b Charstring<'a>,
+    builder: &'b mut B,
+    stack: [f32; MAX_STACK],
+    sp: usize,
+    transient: [f32; TRANSIENT_SIZE],
+    x: f32,
+    y: f32,
+    open: bool,
+    num_hints: usize,
+    width: Option<f32>,
+    seac: Option<Seac>,
+    vsindex: u16,
+    /// Region scalars for the current `vsindex`, computed on the first blend.
+    scalars: Option<Vec<f32>>,
+}
+
+impl<'a, 'b, B: OutlineBuilder + 'b> Interpreter<'a, 'b, B> {
+    fn push(&mut self, v: f32) -> Result<()> {
+        verify!(self.sp < MAX_STACK);
+        self.stack[self.sp] = v;
+        self.sp += 1;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<f32> {
+        verify!(self.sp > 0);
+        self.sp -= 1;
+        Ok(self.stack[self.sp])
+    }
+
+    /// Consumes the optional width preceding the first stack clearing
+    /// operator.  `has_width` is whether the operator found an extra argument.
+    fn take_width(&mut self, has_width: bool) -> usize {
+        if self.width.is_some() || self.cs.is_cff2 {
+            return 0
+        }
+
+        if has_width && self.sp > 0 {
+            self.width = Some(self.cs.nominal_width_x + self.stack[0]);
+            1
+        } else {
+            self.width = Some(self.cs.default_width_x);
+            0
+        }
+    }
+
+    fn close_path(&mut self) {
+        if self.open {
+            self.builder.close();
+            self.open = false;
+        }
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.close_path();
+        self.x += dx;
+        self.y += dy;
+        self.builder.move_to(self.x, self.y);
+        self.open = true;
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        self.builder.line_to(self.x, self.y);
+    }
+
+    fn curve_to(&mut self, dx1: f32, dy1: f32, dx2: f32, dy2: f32, dx3: f32, dy3: f32) {
+        let x1 = self.x + dx1;
+        let y1 = self.y + dy1;
+        let x2 = x1 + dx2;
+        let y2 = y1 + dy2;
+        self.x = x2 + dx3;
+        self.y = y2 + dy3;
+        self.builder.curve_to(x1, y1, x2, y2, self.x, self.y);
+    }
+
+    fn call_subr(&mut self, global: bool, depth: u8) -> Result<bool> {
+        verify!(depth < MAX_CALL_DEPTH);
+
+        let subrs = if global { self.cs.global_subrs } else { self.cs.local_subrs };
+        let index = self.pop()? as i32 + subr_bias(subrs.len());
+        verify!(index >= 0);
+
+        match subrs.get(index as u32) {
+            Some(data) => self.run(data, depth + 1),
+            None => Err(Error::InvalidData),
+        }
+    }
+
+    /// Replaces default values and their deltas on the stack with the
+    /// interpolated values.
+    fn blend(&mut self) -> Result<()> {
+        let n = self.pop()?;
+        verify!(n >= 0.0);
+        let n = n as usize;
+
+        let scalars = match self.scalars.take() {
+            Some(scalars) => scalars,
+            None => match self.cs.variations {
+                Some(ref v) => v.store.region_scalars(self.vsindex, v.coords)?,
+                None => return Err(Error::InvalidData),
+            },
+        };
+
+        let k = scalars.len();
+        verify!(self.sp >= n * (k + 1));
+        let base = self.sp - n * (k + 1);
+
+        for i in 0..n {
+            let deltas = &self.stack[base + n + i * k..][..k];
+            let delta: f32 = deltas.iter().zip(&scalars).map(|(d, s)| d * s).sum();
+            self.stack[base + i] += delta;
+        }
+
+        self.sp = base + n;
+        self.scalars = Some(scalars);
+        Ok(())
+    }
+
+    /// Runs a charstring or subroutine.  Returns true if the glyph has ended.
+    fn run(&mut self, mut data: &'a [u8], depth: u8) -> Result<bool> {
+        while !data.is_empty() {
+            let b0 = data.read::<u8>()?;
+
+            match b0 {
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    let i = self.take_width(self.sp % 2 == 1);
+                    self.num_hints += (self.sp - i) / 2;
+                    self.sp = 0;
+                },
+
+                // hintmask, cntrmask
+                19 | 20 => {
+                    // Arguments are an implied vstem.
+                    let i = self.take_width(self.sp % 2 == 1);
+                    self.num_hints += (self.sp - i) / 2;
+                    self.sp = 0;
+
+                    let size = self.num_hints.div_ceil(8);
+                    required_len!(data, size);
+                    data = &data[size..];
+                },
+
+                // rmoveto
+                21 => {
+                    let i = self.take_width(self.sp > 2);
+                    verify!(self.sp >= i + 2);
+                    let (dx, dy) = (self.stack[i], self.stack[i + 1]);
+                    self.move_to(dx, dy);
+                    self.sp = 0;
+                },
+
+                // hmoveto
+                22 => {
+                    let i = self.take_width(self.sp > 1);
+                    verify!(self.sp > i);
+                    let dx = self.stack[i];
+                    self.move_to(dx, 0.0);
+                    self.sp = 0;
+                },
+
+                // vmoveto
+                4 => {
+                    let i = self.take_width(self.sp > 1);
+                    verify!(self.sp > i);
+                    let dy = self.stack[i];
+                    self.move_to(0.0, dy);
+                    self.sp = 0;
+                },
+
+                // rlineto
+                5 => {
+                    verify!(self.open);
+                    let mut i = 0;
+                    while i + 2 <= self.sp {
+                        let (dx, dy) = (self.stack[i], self.stack[i + 1]);
+                        self.line_to(dx, dy);
+                        i += 2;
+                    }
+                    self.sp = 0;
+                },
+
+                // hlineto, vlineto
+                6 | 7 => {
+                    verify!(self.open);
+                    let mut horizontal = b0 == 6;
+                    for i in 0..self.sp {
+                        let d = self.stack[i];
+                        if horizontal {
+                            self.line_to(d, 0.0);
+                        } else {
+                            self.line_to(0.0, d);
+                        }
+                        horizontal = !horizontal;
+                    }
+                    self.sp = 0;
+                },
+
+                // rrcurveto
+                8 => {
+                    verify!(self.open);
+                    let mut i = 0;
+                    while i + 6 <= self.sp {
+                        let s = self.stack;
+                        self.curve_to(s[i], s[i + 1], s[i + 2], s[i + 3], s[i + 4], s[i + 5]);
+                        i += 6;
+                    }
+                    self.sp = 0;
+                },
+
+                // callsubr, callgsubr
+                10 | 29 => {
+                    if self.call_subr(b0 == 29, depth)? {
+                        return Ok(true)
+                    }
+                },
+
+                // return
+                11 => return Ok(false),
+
+                // vsindex
+                15 if self.cs.is_cff2 => {
+                    let index = self.pop()?;
+                    verify!(index >= 0.0);
+                    self.vsindex = index as u16;
+                    self.scalars = None;
+                    self.sp = 0;
+                },
+
+                // blend
+                16 if self.cs.is_cff2 => self.blend()?,
+
+                // endchar
+                14 => {
+                    let i = self.take_width(self.sp == 1 || self.sp == 5);
+                    if self.sp >= i + 4 {
+                        let s = &self.stack[i..];
+                        self.seac = Some(Seac {
+                            adx: s[0],
+                            ady: s[1],
+                            base: s[2] as u8,
+                            accent: s[3] as u8,
+                        });
+                    }
+                    self.sp = 0;
+                    self.close_path();
+                    return Ok(true)
+                },
+
+                // rcurveline
+                24 => {
+                    verify!(self.open && self.sp >= 8);
+                    let s = self.stack;
+                    let mut i = 0;
+                    while i + 8 <= self.sp {
+                        self.curve_to(s[i], s[i + 1], s[i + 2], s[i + 3], s[i + 4], s[i + 5]);
+                        i += 6;
+                    }
+                    self.line_to(s[i], s[i + 1]);
+                    self.sp = 0;
+                },
+
+                // rlinecurve
+                25 => {
+                    verify!(self.open && self.sp >= 8);
+                    let s = self.stack;
+                    let mut i = 0;
+                    while i + 8 <= self.sp {
+                        self.line_to(s[i], s[i + 1]);
+                        i += 2;
+                    }
+                    self.curve_to(s[i], s[i + 1], s[i + 2], s[i + 3], s[i + 4], s[i + 5]);
+                    self.sp = 0;
+                },
+
+                // vvcurveto
+                26 => {
+                    verify!(self.open);
+                    let s = self.stack;
+                    let mut i = 0;
+                    let mut dx1 = 0.0;
+                    if self.sp % 2 == 1 {
+                        dx1 = s[0];
+                        i = 1;
+                    }
+                    while i + 4 <= self.sp {
+                        self.curve_to(dx1, s[i], s[i + 1], s[i + 2], 0.0, s[i + 3]);
+                        dx1 = 0.0;
+                        i += 4;
+                    }
+                    self.sp = 0;
+                },
+
+                // hhcurveto
+                27 => {
+                    verify!(self.open);
+                    let s = self.stack;
+                    let mut i = 0;
+                    let mut dy1 = 0.0;
+                    if self.sp % 2 == 1 {
+                        dy1 = s[0];
+                        i = 1;
+                    }
+                    while i + 4 <= self.sp {
+                        self.curve_to(s[i], dy1, s[i + 1], s[i + 2], s[i + 3], 0.0);
+                        dy1 = 0.0;
+                        i += 4;
+                    }
+                    self.sp = 0;
+                },
+
+                // vhcurveto, hvcurveto
+                30 | 31 => {
+                    verify!(self.open);
+                    let s = self.stack;
+                    let mut horizontal = b0 == 31;
+                    let mut i = 0;
+                    while i + 4 <= self.sp {
+                        // The final curve may have an extra argument.
+                        let last = if self.sp - i == 5 { s[i + 4] } else { 0.0 };
+                        if horizontal {
+                            self.curve_to(s[i], 0.0, s[i + 1], s[i + 2], last, s[i + 3]);
+                        } else {
+                            self.curve_to(0.0, s[i], s[i + 1], s[i + 2], s[i + 3], last);
+                        }
+                        horizontal = !horizontal;
+                        i += 4;
+                    }
+                    self.sp = 0;
+                },
+
+                // shortint
+                28 => {
+                    let v = data.read::<i16>()?;
+                    self.push(v as f32)?;
+                },
+
+                12 => {
+                    let b1 = data.read::<u8>()?;
+                    self.escape(b1)?;
+                },
+
+                32..=246 => self.push(b0 as f32 - 139.0)?,
+
+                247..=250 => {
+                    let b1 = data.read::<u8>()? as f32;
+                    self.push((b0 as f32 - 247.0) * 256.0 + b1 + 108.0)?;
+                },
+
+                251..=254 => {
+                    let b1 = data.read::<u8>()? as f32;
+                    self.push(-(b0 as f32 - 251.0) * 256.0 - b1 - 108.0)?;
+                },
+
+                // 16.16 fixed
+                255 => {
+                    let v = data.read::<i32>()?;
+                    self.push(v as f32 / 65536.0)?;
+                },
+
+                _ => return Err(Error::InvalidData),
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn escape(&mut self, op: u8) -> Result<()> {
+        let s = self.stack;
+
+        match op {
+            // dotsection
+            0 => self.sp = 0,
+
+            // flex
+            35 => {
+                verify!(self.open && self.sp >= 13);
+                self.curve_to(s[0], s[1], s[2], s[3], s[4], s[5]);
+                self.curve_to(s[6], s[7], s[8], s[9], s[10], s[11]);
+                self.sp = 0;
+            },
+
+            // hflex
+            34 => {
+                verify!(self.open && self.sp >= 7);
+                self.curve_to(s[0], 0.0, s[1], s[2], s[3], 0.0);
+                self.curve_to(s[4], 0.0, s[5], -s[2], s[6], 0.0);
+                self.sp = 0;
+            },
+
+            // hflex1
+            36 => {
+                verify!(self.open && self.sp >= 9);
+                self.curve_to(s[0], s[1], s[2], s[3], s[4], 0.0);
+                self.curve_to(s[5], 0.0, s[6], s[7], s[8], -(s[1] + s[3] + s[7]));
+                self.sp = 0;
+            },
+
+            // flex1
+            37 => {
+                verify!(self.open && self.sp >= 11);
+                let dx = s[0] + s[2] + s[4] + s[6] + s[8];
+                let dy = s[1] + s[3] + s[5] + s[7] + s[9];
+                let (dx6, dy6) = if dx.abs() > dy.abs() { (s[10], -dy) } else { (-dx, s[10]) };
+                self.curve_to(s[0], s[1], s[2], s[3], s[4], s[5]);
+                self.curve_to(s[6], s[7], s[8], s[9], dx6, dy6);
+                self.sp = 0;
+            },
+
+            // and, or, eq
+            3 | 4 | 15 => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let v = match op {
+                    3 => a != 0.0 && b != 0.0,
+                    4 => a != 0.0 || b != 0.0,
+                    _ => a == b,
+                };
+                self.push(if v { 1.0 } else { 0.0 })?;
+            },
+
+            // not
+            5 => {
+                let a = self.pop()?;
+                self.push(if a == 0.0 { 1.0 } else { 0.0 })?;
+            },
+
+            // abs, neg, sqrt
+            9 | 14 | 26 => {
+                let a = self.pop()?;
+                let v = match op {
+                    9 => a.abs(),
+                    14 => -a,
+                    _ => a.abs().sqrt(),
+                };
+                self.push(v)?;
+            },
+
+            // add, sub, div, mul
+            10 | 11 | 12 | 24 => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                let v = match op {
+                    10 => a + b,
+                    11 => a - b,
+                    12 => if b == 0.0 { 0.0 } else { a / b },
+                    _ => a * b,
+                };
+                self.push(v)?;
+            },
+
+            // drop
+            18 => { self.pop()?; },
+
+            // put
+            20 => {
+                let i = self.pop()? as usize;
+                let v = self.pop()?;
+                verify!(i < TRANSIENT_SIZE);
+                self.transient[i] = v;
+            },
+
+            // get
+            21 => {
+                let i = self.pop()? as usize;
+                verify!(i < TRANSIENT_SIZE);
+                let v = self.transient[i];
+                self.push(v)?;
+            },
+
+            // ifelse
+            22 => {
+                let v2 = self.pop()?;
+                let v1 = self.pop()?;
+                let s2 = self.pop()?;
+                let s1 = self.pop()?;
+                self.push(if v1 <= v2 { s1 } else { s2 })?;
+            },
+
+            // random, which must be in (0, 1]. Output should be reproducible.
+            23 => self.push(0.5)?,
+
+            // dup
+            27 => {
+                let a = self.pop()?;
+                self.push(a)?;
+                self.push(a)?;
+            },
+
+            // exch
+            28 => {
+                let b = self.pop()?;
+                let a = self.pop()?;
+                self.push(b)?;
+                self.push(a)?;
+            },
+
+            // index
+            29 => {
+                let i = self.pop()?;
+                let i = if i < 0.0 { 0 } else { i as usize };
+                verify!(i < self.sp);
+                let v = self.stack[self.sp - 1 - i];
+                self.push(v)?;
+            },
+
+            // roll
+            30 => {
+                let j = self.pop()? as i32;
+                let n = self.pop()? as i32;
+                verify!(n > 0 && n as usize <= self.sp);
+                let n = n as usize;
+                let start = self.sp - n;
+                let shift = ((j % n as i32) + n as i32) as usize % n;
+                self.stack[start..self.sp].rotate_right(shift);
+            },
+
+            _ => return Err(Error::InvalidData),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use decode::{Error, Result, Primitive, ReadPrimitive};
+
+/// The maximum number of operands before a DICT operator.  `CFF2` raises
+/// the limit from 48 to make room for `blend` in Private DICTs.
+pub const MAX_OPERANDS: usize = 513;
+
+// Two byte operators are stored as `1200 + op`.
+pub const CHARSET: u16 = 15;
+pub const ENCODING: u16 = 16;
+pub const CHAR_STRINGS: u16 = 17;
+pub const PRIVATE: u16 = 18;
+pub const SUBRS: u16 = 19;
+pub const DEFAULT_WIDTH_X: u16 = 20;
+pub const NOMINAL_WIDTH_X: u16 = 21;
+pub const VSINDEX: u16 = 22;
+pub const BLEND: u16 = 23;
+pub const VSTORE: u16 = 24;
+pub const CHARSTRING_TYPE: u16 = 1206;
+pub const FONT_MATRIX: u16 = 1207;
+pub const ROS: u16 = 1230;
+pub const FD_ARRAY: u16 = 1236;
+pub const FD_SELECT: u16 = 1237;
+
+/// Parses a DICT, calling `f` with each operator and its operands.
+pub fn parse_dict<F>(mut data: &[u8], mut f: F) -> Result<()>
+    where F: FnMut(u16, &[f64]) -> Result<()>
+{
+    let mut operands = Vec::new();
+
+    while !data.is_empty() {
+        let b0 = data.read::<u8>()?;
+
+        match b0 {
+            0..=11 | 13..=27 | 31 => {
+                f(b0 as u16, &operands)?;
+                operands.clear();
+            },
+
+            12 => {
+                let b1 = data.read::<u8>()?;
+                f(1200 + b1 as u16, &operands)?;
+                operands.clear();
+            },
+
+            28 => operands.push(data.read::<i16>()? as f64),
+            29 => operands.push(data.read::<i32>()? as f64),
+            30 => operands.push(read_real(&mut data)?),
+            32..=246 => operands.push(b0 as f64 - 139.0),
+
+            247..=250 => {
+                let b1 = data.read::<u8>()? as f64;
+                operands.push((b0 as f64 - 247.0) * 256.0 + b1 + 108.0);
+            },
+
+            251..=254 => {
+                let b1 = data.read::<u8>()? as f64;
+                operands.push(-(b0 as f64 - 251.0) * 256.0 - b1 - 108.0);
+            },
+
+            _ => return Err(Error::InvalidData),
+        }
+
+        verify!(operands.len() <= MAX_OPERANDS);
+    }
+
+    Ok(())
+}
+
+/// An operand giving an offset or size, which must be a whole number
+/// that fits in 32 bits.
+pub fn offset(value: f64) -> Result<usize> {
+    verify!(value >= 0.0 && value <= u32::MAX as f64 && value.fract() == 0.0);
+    Ok(value as usize)
+}
+
+/// Reads a real number, encoded as a string of nibbles.
+fn read_real(data: &mut &[u8]) -> Result<f64> {
+    let mut s = String::new();
+
+    'bytes: loop {
+        let b = data.read::<u8>()?;
+        for &nibble in &[b >> 4, b & 0x0F] {
+            match nibble {
+                0..=9 => s.push((b'0' + nibble) as char),
+                0xA => s.push('.'),
+                0xB => s.push('E'),
+                0xC => s.push_str("E-"),
+                0xE => s.push('-'),
+                0xF => break 'bytes,
+                _ => return Err(Error::InvalidData),
+            }
+        }
+    }
+
+    s.parse::<f64>().map_err(|_| Error::InvalidData)
+}
+
+#[test]
+fn dict() {
+    let buf: &[u8] = &[
+        0x8B,                   // 0
+        0xEF,                   // 100
+        0xFA, 0x7C,             // 1000
+        0xFE, 0x7C,             // -1000
+        0x1C, 0x27, 0x10,       // 10000
+        0x1D, 0x00, 0x01, 0x86, 0xA0, // 100000
+        0x1E, 0xE2, 0xA2, 0x5F, // -2.25
+        0x1E, 0x0A, 0x14, 0x05, 0x41, 0xC3, 0xFF, // 0.140541E-3
+        0x11,                   // CharStrings
+        0x0C, 0x24,             // FDArray
+    ];
+
+    let mut entries = Vec::new();
+    parse_dict(buf, |op, operands| {
+        entries.push((op, operands.to_vec()));
+        Ok(())
+    }).expect("unable to parse DICT");
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].0, CHAR_STRINGS);
+    assert_eq!(&entries[0].1[..7], &[0.0, 100.0, 1000.0, -1000.0, 10000.0, 100000.0, -2.25]);
+    assert!((entries[0].1[7] - 0.140541E-3).abs() < 1e-12);
+    assert_eq!(entries[1], (FD_ARRAY, vec![]));
+}
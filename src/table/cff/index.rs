@@ -0,0 +1,135 @@
+use decode::{Error, Result, Primitive, ReadPrimitive};
+use decode::primitives::U24;
+
+/// An array of variable sized objects, used throughout `CFF` and `CFF2`.
+#[derive(Debug, Copy, Clone)]
+pub struct Index<'tbl> {
+    count: u32,
+    off_size: u8,
+    offsets: &'tbl [u8],
+    data: &'tbl [u8],
+}
+
+impl<'tbl> Index<'tbl> {
+    pub fn empty() -> Index<'tbl> {
+        Index { count: 0, off_size: 1, offsets: &[], data: &[] }
+    }
+
+    /// Reads a `CFF` INDEX, which has a 16 bit count, advancing the buffer past it.
+    pub fn read(buffer: &mut &'tbl [u8]) -> Result<Index<'tbl>> {
+        let count = buffer.read::<u16>()? as u32;
+        Index::read_body(buffer, count)
+    }
+
+    /// Reads a `CFF2` INDEX, which has a 32 bit count, advancing the buffer past it.
+    pub fn read_cff2(buffer: &mut &'tbl [u8]) -> Result<Index<'tbl>> {
+        let count = buffer.read::<u32>()?;
+        Index::read_body(buffer, count)
+    }
+
+    fn read_body(buffer: &mut &'tbl [u8], count: u32) -> Result<Index<'tbl>> {
+        if count == 0 {
+            return Ok(Index::empty())
+        }
+
+        let off_size = buffer.read::<u8>()?;
+        verify!((1..=4).contains(&off_size));
+
+        let offsets_size = (count as usize + 1) * off_size as usize;
+        required_len!(buffer, offsets_size);
+        let (offsets, rest) = buffer.split_at(offsets_size);
+
+        // Offsets are 1 based, relative to the byte before the data.
+        let last = read_offset(offsets, off_size, count as usize)?;
+        verify!(last >= 1);
+        required_len!(rest, last - 1);
+
+        let (data, rest) = rest.split_at(last - 1);
+        *buffer = rest;
+
+        Ok(Index { count, off_size, offsets, data })
+    }
+
+    pub fn len(&self) -> u32 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn get(&self, index: u32) -> Option<&'tbl [u8]> {
+        if index >= self.count {
+            return None
+        }
+
+        let start = try_opt!(read_offset(self.offsets, self.off_size, index as usize).ok());
+        let end = try_opt!(read_offset(self.offsets, self.off_size, index as usize + 1).ok());
+
+        if start < 1 || start > end {
+            return None
+        }
+
+        self.data.get(start - 1..end - 1)
+    }
+
+    pub fn iter(&self) -> IndexIter<'tbl> {
+        IndexIter { index: *self, current: 0 }
+    }
+}
+
+fn read_offset(offsets: &[u8], off_size: u8, index: usize) -> Result<usize> {
+    let mut buf = match offsets.get(index * off_size as usize..) {
+        Some(buf) => buf,
+        None => return Err(Error::UnexpectedEof),
+    };
+
+    Ok(match off_size {
+        1 => buf.read::<u8>()? as usize,
+        2 => buf.read::<u16>()? as usize,
+        3 => buf.read::<U24>()?.0 as usize,
+        _ => buf.read::<u32>()? as usize,
+    })
+}
+
+pub struct IndexIter<'a> {
+    index: Index<'a>,
+    current: u32,
+}
+
+impl<'a> Iterator for IndexIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let item = try_opt!(self.index.get(self.current));
+        self.current += 1;
+        Some(item)
+    }
+}
+
+#[test]
+fn index() {
+    let buf: &[u8] = &[
+        0x00, 0x03, // count
+        0x01,       // offset size
+        0x01, 0x03, 0x03, 0x06,
+        b'a', b'b', b'c', b'd', b'e',
+        0xFF,
+    ];
+
+    let mut buffer = buf;
+    let index = Index::read(&mut buffer).expect("unable to read INDEX");
+
+    assert_eq!(index.len(), 3);
+    assert_eq!(index.get(0), Some(&b"ab"[..]));
+    assert_eq!(index.get(1), Some(&b""[..]));
+    assert_eq!(index.get(2), Some(&b"cde"[..]));
+    assert_eq!(index.get(3), None);
+    assert_eq!(index.iter().count(), 3);
+    assert_eq!(buffer, &[0xFF]);
+
+    let mut empty: &[u8] = &[0x00, 0x00, 0xFF];
+    let index = Index::read(&mut empty).expect("unable to read empty INDEX");
+    assert!(index.is_empty());
+    assert_eq!(empty, &[0xFF]);
+}
@@ -0,0 +1,822 @@
+pub mod index;
+pub mod dict;
+pub mod charstring;
+pub mod cff2;
+
+use decode::{Error, Result, Table, Primitive, ReadPrimitive};
+use outline::{OutlineBuilder, TransformBuilder, Transform};
+use self::index::Index;
+use self::charstring::{Charstring, CharstringResult};
+
+/// Compact Font Format table, holding PostScript outlines.
+#[derive(Debug)]
+pub struct Cff<'tbl> {
+    pub major_version: u8,
+    pub minor_version: u8,
+    pub names: Index<'tbl>,
+    pub strings: Index<'tbl>,
+    pub global_subrs: Index<'tbl>,
+    pub charstrings: Index<'tbl>,
+    pub font_matrix: [f32; 6],
+    pub charset: Charset<'tbl>,
+    kind: FontKind<'tbl>,
+}
+
+#[derive(Debug)]
+enum FontKind<'tbl> {
+    Sid(PrivateDict<'tbl>),
+    /// CID-keyed fonts select a Private DICT for each glyph.
+    Cid {
+        fd_array: Vec<PrivateDict<'tbl>>,
+        fd_select: FdSelect<'tbl>,
+    },
+}
+
+#[derive(Debug, Default)]
+struct TopDict {
+    charset: usize,
+    char_strings: Option<usize>,
+    private: Option<(usize, usize)>,
+    font_matrix: Option<[f32; 6]>,
+    charstring_type: Option<f64>,
+    ros: bool,
+    fd_array: Option<usize>,
+    fd_select: Option<usize>,
+}
+
+impl TopDict {
+    fn parse(data: &[u8]) -> Result<TopDict> {
+        let mut top = TopDict::default();
+
+        dict::parse_dict(data, |op, operands| {
+            let first = operands.first().cloned();
+
+            match op {
+                dict::CHARSET => top.charset = dict::offset(first.unwrap_or(0.0))?,
+                dict::CHAR_STRINGS => top.char_strings = first.map(dict::offset).transpose()?,
+                dict::PRIVATE if operands.len() >= 2 => {
+                    top.private = Some((dict::offset(operands[0])?, dict::offset(operands[1])?));
+                },
+                dict::FONT_MATRIX if operands.len() >= 6 => {
+                    let mut m = [0.0; 6];
+                    for (m, &v) in m.iter_mut().zip(operands) {
+                        *m = v as f32;
+                    }
+                    top.font_matrix = Some(m);
+                },
+                dict::CHARSTRING_TYPE => top.charstring_type = first,
+                dict::ROS => top.ros = true,
+                dict::FD_ARRAY => top.fd_array = first.map(dict::offset).transpose()?,
+                dict::FD_SELECT => top.fd_select = first.map(dict::offset).transpose()?,
+                _ => { },
+            }
+
+            Ok(())
+        })?;
+
+        Ok(top)
+    }
+}
+
+/// The parts of a Private DICT needed to interpret charstrings.
+#[derive(Debug, Copy, Clone)]
+pub struct PrivateDict<'tbl> {
+    pub local_subrs: Index<'tbl>,
+    pub default_width_x: f32,
+    pub nominal_width_x: f32,
+}
+
+impl<'tbl> PrivateDict<'tbl> {
+    /// `table` is the whole `CFF` table, as the private dict is
+    /// located by its size and offset into it.
+    fn parse(table: &'tbl [u8], size: usize, offset: usize) -> Result<PrivateDict<'tbl>> {
+        let data = match table.get(offset..).and_then(|t| t.get(..size)) {
+            Some(data) => data,
+            None => return Err(Error::UnexpectedEof),
+        };
+
+        let mut subrs = None;
+        let mut default_width_x = 0.0;
+        let mut nominal_width_x = 0.0;
+
+        dict::parse_dict(data, |op, operands| {
+            let first = operands.first().cloned();
+
+            match op {
+                dict::SUBRS => subrs = first.map(dict::offset).transpose()?,
+                dict::DEFAULT_WIDTH_X => default_width_x = first.unwrap_or(0.0) as f32,
+                dict::NOMINAL_WIDTH_X => nominal_width_x = first.unwrap_or(0.0) as f32,
+                _ => { },
+            }
+
+            Ok(())
+        })?;
+
+        // Local subroutines are relative to the Private DICT.
+        let local_subrs = match subrs {
+            Some(subrs) => {
+                let mut buf = match offset.checked_add(subrs).and_then(|start| table.get(start..)) {
+                    Some(buf) => buf,
+                    None => return Err(Error::UnexpectedEof),
+                };
+                Index::read(&mut buf)?
+            },
+            None => Index::empty(),
+        };
+
+        Ok(PrivateDict { local_subrs, default_width_x, nominal_width_x })
+    }
+}
+
+impl<'tbl> Table<'tbl> for Cff<'tbl> {
+    fn parse(table: &'tbl [u8]) -> Result<Cff<'tbl>> {
+        required_len!(table, 4);
+
+        let mut buffer = table;
+        let major_version = buffer.read::<u8>()?;
+        let minor_version = buffer.read::<u8>()?;
+        let header_size = buffer.read::<u8>()? as usize;
+
+        verify!(major_version == 1);
+        required_len!(table, header_size);
+
+        let mut buffer = &table[header_size..];
+        let names = Index::read(&mut buffer)?;
+        let top_dicts = Index::read(&mut buffer)?;
+        let strings = Index::read(&mut buffer)?;
+        let global_subrs = Index::read(&mut buffer)?;
+
+        // OpenType fonts contain a single font.
+        let top = match top_dicts.get(0) {
+            Some(data) => TopDict::parse(data)?,
+            None => return Err(Error::InvalidData),
+        };
+
+        verify!(top.charstring_type.map(|t| t == 2.0).unwrap_or(true));
+
+        let charstrings = match top.char_strings.and_then(|offset| table.get(offset..)) {
+            Some(mut buf) => Index::read(&mut buf)?,
+            None => return Err(Error::InvalidData),
+        };
+
+        let num_glyphs = charstrings.len() as u16;
+        let charset = Charset::parse(table, top.charset, num_glyphs)?;
+
+        let kind = if top.ros {
+            let fd_array = match top.fd_array.and_then(|offset| table.get(offset..)) {
+                Some(mut buf) => Index::read(&mut buf)?,
+                None => return Err(Error::InvalidData),
+            };
+
+            let fd_array = fd_array.iter()
+                .map(|font_dict| {
+                    match TopDict::parse(font_dict)?.private {
+                        Some((size, offset)) => PrivateDict::parse(table, size, offset),
+                        None => Err(Error::InvalidData),
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            let fd_select = match top.fd_select.and_then(|offset| table.get(offset..)) {
+                Some(buf) => FdSelect::parse(buf, num_glyphs)?,
+                None => return Err(Error::InvalidData),
+            };
+
+            FontKind::Cid { fd_array, fd_select }
+        } else {
+            match top.private {
+                Some((size, offset)) => FontKind::Sid(PrivateDict::parse(table, size, offset)?),
+                None => return Err(Error::InvalidData),
+            }
+        };
+
+        Ok(Cff {
+            major_version,
+            minor_version,
+            names,
+            strings,
+            global_subrs,
+            charstrings,
+            font_matrix: top.font_matrix.unwrap_or([0.001, 0.0, 0.0, 0.001, 0.0, 0.0]),
+            charset,
+            kind,
+        })
+    }
+}
+
+/// Discards the outline, for when only the width is needed.
+struct NullBuilder;
+
+impl OutlineBuilder for NullBuilder {
+    fn move_to(&mut self, _: f32, _: f32) { }
+    fn line_to(&mut self, _: f32, _: f32) { }
+    fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) { }
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) { }
+    fn close(&mut self) { }
+}
+
+impl<'tbl> Cff<'tbl> {
+    pub fn num_glyphs(&self) -> u16 {
+        self.charstrings.len() as u16
+    }
+
+    pub fn is_cid_keyed(&self) -> bool {
+        match self.kind {
+            FontKind::Cid { .. } => true,
+            FontKind::Sid(_) => false,
+        }
+    }
+
+    /// Strings with an id below 391 are the predefined standard
+    /// strings, and are not stored in the font.
+    pub fn get_string(&self, sid: u16) -> Option<&'tbl [u8]> {
+        sid.checked_sub(391).and_then(|i| self.strings.get(i as u32))
+    }
+
+    fn private_dict(&self, glyph_id: u16) -> Option<&PrivateDict<'tbl>> {
+        match self.kind {
+            FontKind::Sid(ref private) => Some(private),
+            FontKind::Cid { ref fd_array, ref fd_select } => {
+                let fd = try_opt!(fd_select.get_fd(glyph_id));
+                fd_array.get(fd as usize)
+            },
+        }
+    }
+
+    fn charstring(&self, glyph_id: u16) -> Result<Charstring<'tbl>> {
+        let data = match self.charstrings.get(glyph_id as u32) {
+            Some(data) => data,
+            None => return Err(Error::InvalidData),
+        };
+
+        let private = match self.private_dict(glyph_id) {
+            Some(private) => private,
+            None => return Err(Error::InvalidData),
+        };
+
+        Ok(Charstring {
+            data,
+            global_subrs: self.global_subrs,
+            local_subrs: private.local_subrs,
+            default_width_x: private.default_width_x,
+            nominal_width_x: private.nominal_width_x,
+            is_cff2: false,
+            variations: None,
+        })
+    }
+
+    /// Draws the glyph's cubic outline in font units, returning its advance width.
+    pub fn outline<B: OutlineBuilder>(&self, glyph_id: u16, builder: &mut B) -> Result<f32> {
+        let CharstringResult { width, seac } = charstring::execute(&self.charstring(glyph_id)?, builder)?;
+
+        // Accented characters draw the base, followed by the offset accent.
+        if let Some(seac) = seac {
+            let base = self.get_standard_glyph(seac.base);
+            let accent = self.get_standard_glyph(seac.accent);
+
+            match (base, accent) {
+                (Some(base), Some(accent)) => {
+                    charstring::execute(&self.charstring(base)?, builder)?;
+
+                    let offset = Transform::translate(seac.adx, seac.ady);
+                    let mut accent_builder = TransformBuilder::new(builder, offset);
+                    charstring::execute(&self.charstring(accent)?, &mut accent_builder)?;
+                },
+                _ => return Err(Error::InvalidData),
+            }
+        }
+
+        Ok(width)
+    }
+
+    pub fn get_glyph_width(&self, glyph_id: u16) -> Result<f32> {
+        charstring::execute(&self.charstring(glyph_id)?, &mut NullBuilder).map(|r| r.width)
+    }
+
+    /// Finds the glyph for a Standard Encoding code, as used by `seac`.
+    fn get_standard_glyph(&self, code: u8) -> Option<u16> {
+        match STANDARD_ENCODING[code as usize] {
+            0 => None,
+            sid => self.charset.get_glyph_id(sid as u16),
+        }
+    }
+}
+
+/// Maps glyph ids to string ids, or to CIDs in CID-keyed fonts.
+#[derive(Debug)]
+pub enum Charset<'tbl> {
+    IsoAdobe,
+    Expert,
+    ExpertSubset,
+    Format0(&'tbl [u8]),
+    Format1(&'tbl [u8]),
+    Format2(&'tbl [u8]),
+}
+
+impl<'tbl> Charset<'tbl> {
+    fn parse(table: &'tbl [u8], offset: usize, num_glyphs: u16) -> Result<Charset<'tbl>> {
+        match offset {
+            0 => return Ok(Charset::IsoAdobe),
+            1 => return Ok(Charset::Expert),
+            2 => return Ok(Charset::ExpertSubset),
+            _ => { },
+        }
+
+        let mut buf = match table.get(offset..) {
+            Some(buf) => buf,
+            None => return Err(Error::UnexpectedEof),
+        };
+
+        let format = buf.read::<u8>()?;
+        let remaining = num_glyphs.saturating_sub(1) as usize;
+
+        match format {
+            0 => {
+                required_len!(buf, 2 * remaining);
+                Ok(Charset::Format0(&buf[..2 * remaining]))
+            },
+
+            1 | 2 => {
+                // Ranges are read until every glyph is covered.
+                let range_size = if format == 1 { 3 } else { 4 };
+                let mut covered = 0;
+                let mut size = 0;
+
+                while covered < remaining {
+                    let mut range = match buf.get(size..size + range_size) {
+                        Some(range) => range,
+                        None => return Err(Error::UnexpectedEof),
+                    };
+
+                    let _ /* first */ = range.read::<u16>()?;
+                    let n_left = if format == 1 {
+                        range.read::<u8>()? as usize
+                    } else {
+                        range.read::<u16>()? as usize
+                    };
+
+                    covered += n_left + 1;
+                    size += range_size;
+                }
+
+                if format == 1 {
+                    Ok(Charset::Format1(&buf[..size]))
+                } else {
+                    Ok(Charset::Format2(&buf[..size]))
+                }
+            },
+
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    /// Iterates over the ranges of ids of glyphs 1 and onwards,
+    /// as `(first id, number of glyphs)`.
+    fn ranges(&self) -> CharsetRanges<'tbl> {
+        match *self {
+            Charset::Format0(buf) => CharsetRanges { buf, format: 0 },
+            Charset::Format1(buf) => CharsetRanges { buf, format: 1 },
+            Charset::Format2(buf) => CharsetRanges { buf, format: 2 },
+            _ => CharsetRanges { buf: &[], format: 0 },
+        }
+    }
+
+    /// The string id, or CID, of a glyph.
+    pub fn get_id(&self, glyph_id: u16) -> Option<u16> {
+        if glyph_id == 0 {
+            return Some(0)
+        }
+
+        match *self {
+            Charset::IsoAdobe => return if glyph_id <= 228 { Some(glyph_id) } else { None },
+            Charset::Expert | Charset::ExpertSubset => return None,
+            _ => { },
+        }
+
+        let mut gid = 1u32;
+        for (first, count) in self.ranges() {
+            if (glyph_id as u32) < gid + count {
+                return first.checked_add((glyph_id as u32 - gid) as u16)
+            }
+            gid += count;
+        }
+
+        None
+    }
+
+    /// The glyph with the given string id, or CID.
+    pub fn get_glyph_id(&self, id: u16) -> Option<u16> {
+        if id == 0 {
+            return Some(0)
+        }
+
+        match *self {
+            Charset::IsoAdobe => return if id <= 228 { Some(id) } else { None },
+            Charset::Expert | Charset::ExpertSubset => return None,
+            _ => { },
+        }
+
+        let mut gid = 1u32;
+        for (first, count) in self.ranges() {
+            if id >= first && ((id - first) as u32) < count {
+                let glyph_id = gid + (id - first) as u32;
+                return if glyph_id <= u16::MAX as u32 { Some(glyph_id as u16) } else { None }
+            }
+
+            gid += count;
+            if gid > u16::MAX as u32 {
+                break
+            }
+        }
+
+        None
+    }
+}
+
+struct CharsetRanges<'tbl> {
+    buf: &'tbl [u8],
+    format: u8,
+}
+
+impl<'tbl> Iterator for CharsetRanges<'tbl> {
+    type Item = (u16, u32);
+
+    fn next(&mut self) -> Option<(u16, u32)> {
+        let first = try_opt!(self.buf.read::<u16>().ok());
+        let count = match self.format {
+            0 => 1,
+            1 => try_opt!(self.buf.read::<u8>().ok()) as u32 + 1,
+            _ => try_opt!(self.buf.read::<u16>().ok()) as u32 + 1,
+        };
+        Some((first, count))
+    }
+}
+
+/// Maps glyphs to the Font DICT of a CID-keyed font.
+#[derive(Debug)]
+pub enum FdSelect<'tbl> {
+    Format0(&'tbl [u8]),
+    Format3 { ranges: &'tbl [u8], sentinel: u16 },
+    /// Only used by `CFF2`.
+    Format4 { ranges: &'tbl [u8], sentinel: u32 },
+}
+
+impl<'tbl> FdSelect<'tbl> {
+    pub fn parse(mut buffer: &'tbl [u8], num_glyphs: u16) -> Result<FdSelect<'tbl>> {
+        let format = buffer.read::<u8>()?;
+
+        match format {
+            0 => {
+                required_len!(buffer, num_glyphs as usize);
+                Ok(FdSelect::Format0(&buffer[..num_glyphs as usize]))
+            },
+
+            3 => {
+                let num_ranges = buffer.read::<u16>()? as usize;
+                required_len!(buffer, 3 * num_ranges + 2);
+                let (ranges, mut rest) = buffer.split_at(3 * num_ranges);
+                let sentinel = rest.read::<u16>()?;
+                Ok(FdSelect::Format3 { ranges, sentinel })
+            },
+
+            4 => {
+                let num_ranges = buffer.read::<u32>()? as usize;
+                required_len!(buffer, 6 * num_ranges + 4);
+                let (ranges, mut rest) = buffer.split_at(6 * num_ranges);
+                let sentinel = rest.read::<u32>()?;
+                Ok(FdSelect::Format4 { ranges, sentinel })
+            },
+
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    pub fn get_fd(&self, glyph_id: u16) -> Option<u16> {
+        match *self {
+            FdSelect::Format0(fds) => fds.get(glyph_id as usize).map(|&fd| fd as u16),
+            FdSelect::Format3 { ranges, sentinel } => {
+                if glyph_id >= sentinel {
+                    return None
+                }
+
+                // Ranges are sorted by their first glyph.
+                let mut fd = None;
+                for mut range in ranges.chunks(3) {
+                    let first = try_opt!(range.read::<u16>().ok());
+                    if first > glyph_id {
+                        break
+                    }
+                    fd = range.read::<u8>().ok().map(|fd| fd as u16);
+                }
+
+                fd
+            },
+            FdSelect::Format4 { ranges, sentinel } => {
+                if glyph_id as u32 >= sentinel {
+                    return None
+                }
+
+                let mut fd = None;
+                for mut range in ranges.chunks(6) {
+                    let first = try_opt!(range.read::<u32>().ok());
+                    if first > glyph_id as u32 {
+                        break
+                    }
+                    fd = range.read::<u16>().ok();
+                }
+
+                fd
+            },
+        }
+    }
+}
+
+/// Standard Encoding, mapping character codes to string ids.
+static STANDARD_ENCODING: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16,
+    17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32,
+    33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48,
+    49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63, 64,
+    65, 66, 67, 68, 69, 70, 71, 72, 73, 74, 75, 76, 77, 78, 79, 80,
+    81, 82, 83, 84, 85, 86, 87, 88, 89, 90, 91, 92, 93, 94, 95, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 96, 97, 98, 99, 100, 101, 102, 103, 104, 105, 106, 107, 108, 109, 110,
+    0, 111, 112, 113, 114, 0, 115, 116, 117, 118, 119, 120, 121, 122, 0, 123,
+    0, 124, 125, 126, 127, 128, 129, 130, 131, 0, 132, 133, 0, 134, 135, 136,
+    137, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 138, 0, 139, 0, 0, 0, 0, 140, 141, 142, 143, 0, 0, 0, 0,
+    0, 144, 0, 0, 0, 145, 0, 0, 146, 147, 148, 149, 0, 0, 0, 0,
+];
+
+#[cfg(test)]
+pub mod test {
+    use decode::{Error, Table};
+    use outline::OutlineBuilder;
+    use table::cff::{Cff, Charset};
+    use table::cff::charstring::{self, Charstring};
+    use table::cff::index::Index;
+    use std::fmt::Write;
+
+    pub struct PathBuilder(pub String);
+
+    impl OutlineBuilder for PathBuilder {
+        fn move_to(&mut self, x: f32, y: f32) {
+            write!(self.0, "M{} {} ", x, y).unwrap();
+        }
+
+        fn line_to(&mut self, x: f32, y: f32) {
+            write!(self.0, "L{} {} ", x, y).unwrap();
+        }
+
+        fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+            write!(self.0, "Q{} {} {} {} ", x1, y1, x, y).unwrap();
+        }
+
+        fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+            write!(self.0, "C{} {} {} {} {} {} ", x1, y1, x2, y2, x, y).unwrap();
+        }
+
+        fn close(&mut self) {
+            self.0.push_str("Z ");
+        }
+    }
+
+    pub fn index(items: &[&[u8]]) -> Vec<u8> {
+        let mut buf = vec![0, items.len() as u8, 2];
+        let mut offset = 1;
+        buf.extend(&[0, 1]);
+        for item in items {
+            offset += item.len();
+            buf.extend(&[(offset >> 8) as u8, offset as u8]);
+        }
+        for item in items {
+            buf.extend(*item);
+        }
+        buf
+    }
+
+    /// Encodes numbers as 32 bit integers in dicts, or 16 bit integers in
+    /// charstrings, so that offsets can be patched without moving data.
+    pub fn int(dict: bool, v: i32) -> Vec<u8> {
+        if dict {
+            vec![29, (v >> 24) as u8, (v >> 16) as u8, (v >> 8) as u8, v as u8]
+        } else {
+            vec![28, (v >> 8) as u8, v as u8]
+        }
+    }
+
+    pub fn ops(dict: bool, items: &[&[i32]]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for item in items {
+            let (operands, op) = item.split_at(item.len() - 1);
+            for &v in operands {
+                buf.extend(int(dict, v));
+            }
+            if op[0] >= 1200 {
+                buf.extend(&[12, (op[0] - 1200) as u8]);
+            } else {
+                buf.push(op[0] as u8);
+            }
+        }
+        buf
+    }
+
+    /// Builds a `CFF` table, laying out the data after the top dict
+    /// in the order given.  `top` receives the offset of each block.
+    pub fn build<F>(blocks: &[Vec<u8>], top: F) -> Vec<u8>
+        where F: Fn(&[usize]) -> Vec<u8>
+    {
+        let names = index(&[b"Test"]);
+        let strings = index(&[b"Aacute"]);
+        let global_subrs = index(&[]);
+
+        // The top dict has the same size however offsets are filled in.
+        let top_size = index(&[&top(&vec![0; blocks.len()])]).len();
+        let mut offset = 4 + names.len() + top_size + strings.len() + global_subrs.len();
+        let mut offsets = Vec::new();
+        for block in blocks {
+            offsets.push(offset);
+            offset += block.len();
+        }
+
+        let mut cff = vec![1, 0, 4, 2];
+        cff.extend(names);
+        cff.extend(index(&[&top(&offsets)]));
+        cff.extend(strings);
+        cff.extend(global_subrs);
+        for block in blocks {
+            cff.extend(block);
+        }
+        cff
+    }
+
+    fn draw(cff: &Cff, glyph_id: u16) -> (String, f32) {
+        let mut builder = PathBuilder(String::new());
+        let width = cff.outline(glyph_id, &mut builder).expect("unable to draw glyph");
+        (builder.0.trim().to_owned(), width)
+    }
+
+    #[test]
+    fn sid_keyed() {
+        let a = ops(false, &[
+            &[500, 0, 50, 1],       // width, hstem
+            &[10, 20, 19],          // implied vstem, hintmask
+        ]);
+        let a = [a, vec![0xC0], ops(false, &[&[10, 0, 21], &[-107, 10], &[14]])].concat();
+
+        let charstrings = index(&[
+            &ops(false, &[&[14]]),
+            &a,
+            &ops(false, &[&[50, 200, 21], &[20, 30, -10, -40, 10, 0, 8], &[14]]),
+            &ops(false, &[&[600, 5, 10, 65, 194, 14]]),
+        ]);
+
+        // .notdef, A, acute, Aacute
+        let charset = vec![0, 0, 34, 0, 125, 1, 135];
+        let subrs = index(&[&ops(false, &[&[100, 0, 5], &[0, 100, 5], &[11]])]);
+        let private_size = ops(true, &[&[500, 20], &[100, 21], &[0, 19]]).len() as i32;
+        let private = ops(true, &[&[500, 20], &[100, 21], &[private_size, 19]]);
+        let size = private.len() as i32;
+
+        let data = build(&[charset, charstrings, private, subrs], |o| {
+            ops(true, &[
+                &[o[0] as i32, 15],
+                &[o[1] as i32, 17],
+                &[size, o[2] as i32, 18],
+            ])
+        });
+
+        let cff = Cff::parse(&data).expect("unable to parse CFF");
+        assert_eq!(cff.num_glyphs(), 4);
+        assert!(!cff.is_cid_keyed());
+        assert_eq!(cff.names.get(0), Some(&b"Test"[..]));
+        assert_eq!(cff.get_string(391), Some(&b"Aacute"[..]));
+        assert_eq!(cff.charset.get_id(3), Some(391));
+        assert_eq!(cff.charset.get_glyph_id(125), Some(2));
+
+        assert_eq!(draw(&cff, 0), ("".to_owned(), 500.0));
+        assert_eq!(draw(&cff, 1), ("M10 0 L110 0 L110 100 Z".to_owned(), 600.0));
+        assert_eq!(draw(&cff, 2), ("M50 200 C70 230 60 190 70 190 Z".to_owned(), 500.0));
+        assert_eq!(draw(&cff, 3),
+                   ("M10 0 L110 0 L110 100 Z M55 210 C75 240 65 200 75 200 Z".to_owned(), 700.0));
+        assert_eq!(cff.get_glyph_width(1).ok(), Some(600.0));
+        assert!(cff.outline(4, &mut PathBuilder(String::new())).is_err());
+    }
+
+    #[test]
+    fn charstring_curves() {
+        let data = ops(false, &[
+            &[0, 0, 21],
+            &[10, 20, 30, 40, 5, 31],               // hvcurveto with a final dx
+            &[10, 0, 10, 10, 10, 0, 10, 0, 10, -10, 10, 0, 50, 1235], // flex
+            &[10, 10, 10, 10, 10, 10, 10, 1234],    // hflex
+            &[2, 3, 1210], &[4, 1224], &[-70, 5],   // (2 + 3) * 4, rlineto
+        ]);
+        let charstring = Charstring {
+            data: &data,
+            global_subrs: Index::empty(),
+            local_subrs: Index::empty(),
+            default_width_x: 0.0,
+            nominal_width_x: 0.0,
+            is_cff2: false,
+            variations: None,
+        };
+
+        let mut builder = PathBuilder(String::new());
+        charstring::execute(&charstring, &mut builder).expect("unable to run charstring");
+        assert_eq!(builder.0.trim(),
+                   "M0 0 C10 0 30 30 35 70 C45 70 55 80 65 80 C75 80 85 70 95 70 \
+                    C105 70 115 80 125 80 C135 80 145 70 155 70 L175 0 Z");
+    }
+
+    #[test]
+    fn cid_keyed() {
+        let charstrings = index(&[
+            &ops(false, &[&[100, 14]]),
+            &ops(false, &[&[100, 14]]),
+            &ops(false, &[&[100, 14]]),
+        ]);
+
+        // CIDs 1 and 2 in a format 2 range.
+        let charset = vec![2, 0, 1, 0, 1];
+        let fd_select = vec![3, 0, 2, 0, 0, 0, 0, 2, 1, 0, 3];
+        let private_a = ops(true, &[&[400, 21]]);
+        let private_b = ops(true, &[&[800, 21]]);
+
+        let blocks = [charset, charstrings, fd_select, private_a, private_b];
+        let placeholder = build(&blocks, |_| ops(true, &[
+            &[0, 15], &[0, 17], &[0, 0, 0, 1230], &[0, 1236], &[0, 1237],
+        ]));
+
+        // The FD array goes at the end, once the private dicts are placed.
+        let size = blocks[3].len() as i32;
+        let private_offset = (placeholder.len() - blocks[3].len() - blocks[4].len()) as i32;
+        let fd_array = index(&[
+            &ops(true, &[&[size, private_offset, 18]]),
+            &ops(true, &[&[size, private_offset + size, 18]]),
+        ]);
+        let fd_array_offset = placeholder.len();
+
+        let mut data = build(&blocks, |o| ops(true, &[
+            &[o[0] as i32, 15],
+            &[o[1] as i32, 17],
+            &[0, 0, 0, 1230],
+            &[fd_array_offset as i32, 1236],
+            &[o[2] as i32, 1237],
+        ]));
+        data.extend(fd_array);
+
+        let cff = Cff::parse(&data).expect("unable to parse CFF");
+        assert!(cff.is_cid_keyed());
+        assert_eq!(cff.charset.get_id(2), Some(2));
+        assert_eq!(cff.get_glyph_width(0).ok(), Some(500.0));
+        assert_eq!(cff.get_glyph_width(1).ok(), Some(500.0));
+        assert_eq!(cff.get_glyph_width(2).ok(), Some(900.0));
+    }
+
+    #[test]
+    fn dict_offsets() {
+        let charstrings = index(&[&ops(false, &[&[14]])]);
+        let private = ops(true, &[&[0, 20]]);
+        let size = private.len() as i32;
+
+        // Private DICT offsets too large for 32 bits, negative and fractional.
+        for real in &[&[0x1B, 0x30, 0xFF][..], &[0xE1, 0xFF], &[0x1A, 0x5F]] {
+            let data = build(&[vec![0], charstrings.clone(), private.clone()], |o| {
+                let top = ops(true, &[&[o[0] as i32, 15], &[o[1] as i32, 17], &[size, 18]]);
+                [&top[..top.len() - 1], &[30], real, &[18]].concat()
+            });
+            assert!(matches!(Cff::parse(&data), Err(Error::InvalidData)));
+        }
+
+        // A Subrs offset of 1E30 from the Private DICT.
+        let private = [private, vec![30, 0x1B, 0x30, 0xFF, 19]].concat();
+        let size = private.len() as i32;
+        let data = build(&[vec![0], charstrings, private], |o| ops(true, &[
+            &[o[0] as i32, 15],
+            &[o[1] as i32, 17],
+            &[size, o[2] as i32, 18],
+        ]));
+        assert!(matches!(Cff::parse(&data), Err(Error::InvalidData)));
+    }
+
+    #[test]
+    fn charset_past_last_glyph() {
+        // A range covering every glyph id, followed by one past the end.
+        let charset = Charset::Format2(&[0x03, 0xE8, 0xFF, 0xFE, 0x00, 0x0A, 0x00, 0x03]);
+        assert_eq!(charset.get_id(1), Some(1000));
+        assert_eq!(charset.get_id(64535), Some(65534));
+        assert_eq!(charset.get_id(65535), None);
+        assert_eq!(charset.get_glyph_id(65534), Some(64535));
+        assert_eq!(charset.get_glyph_id(11), None);
+
+        let charset = Charset::Format1(&[0x00, 0x05, 0x02, 0x00, 0x01, 0x00]);
+        assert_eq!(charset.get_id(3), Some(7));
+        assert_eq!(charset.get_id(4), Some(1));
+        assert_eq!(charset.get_glyph_id(1), Some(4));
+        assert_eq!(charset.get_glyph_id(8), None);
+    }
+}
@@ -0,0 +1,356 @@
+//! Contextual and chained contextual subtables, shared by GSUB and GPOS.
+
+use decode::{Error, Result, Table, ReadPrimitive};
+use buffer::GlyphInfo;
+use table::layout::{u16_at, ClassDef, Coverage, GlyphFilter};
+
+/// Splits `len` bytes off the front of a buffer.
+fn take<'tbl>(buffer: &mut &'tbl [u8], len: usize) -> Result<&'tbl [u8]> {
+    required_len!(buffer, len);
+    let (head, rest) = buffer.split_at(len);
+    *buffer = rest;
+    Ok(head)
+}
+
+/// A lookup to apply at one glyph of a matched input sequence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SequenceLookup {
+    pub sequence_index: u16,
+    pub lookup_index: u16,
+}
+
+/// How the values of a sequence identify glyphs.
+#[derive(Debug, Copy, Clone)]
+enum Matcher<'tbl> {
+    Glyphs,
+    /// A missing class definition puts every glyph in class 0.
+    Classes(Option<ClassDef<'tbl>>),
+    /// Offsets to coverage tables from the start of a subtable.
+    Coverages(&'tbl [u8]),
+}
+
+/// A sequence of glyph ids, classes or coverage tables to match glyphs
+/// against.
+#[derive(Debug, Copy, Clone)]
+pub struct Sequence<'tbl> {
+    values: &'tbl [u8],
+    matcher: Matcher<'tbl>,
+}
+
+impl<'tbl> Sequence<'tbl> {
+    /// Reads an array of coverage offsets preceded by its length.
+    pub fn read_coverages(subtable: &'tbl [u8], buffer: &mut &'tbl [u8]) -> Result<Sequence<'tbl>> {
+        let count = buffer.read::<u16>()? as usize;
+        Ok(Sequence { values: take(buffer, 2 * count)?, matcher: Matcher::Coverages(subtable) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether a glyph matches the value at an index.
+    pub fn matches(&self, index: usize, glyph_id: u16) -> bool {
+        let value = match u16_at(self.values, 2 * index) {
+            Some(value) => value,
+            None => return false,
+        };
+
+        match self.matcher {
+            Matcher::Glyphs => glyph_id == value,
+            Matcher::Classes(classes) => classes.map_or(0, |classes| classes.class(glyph_id)) == value,
+            Matcher::Coverages(subtable) => subtable.get(value as usize..)
+                .and_then(|coverage| Coverage::parse(coverage).ok())
+                .is_some_and(|coverage| coverage.contains(glyph_id)),
+        }
+    }
+}
+
+/// Whether the glyphs before `first` match a backtrack sequence, which is
+/// in reverse order, and the glyphs after `last` match a lookahead
+/// sequence.
+pub fn matches_context(glyphs: &[GlyphInfo], first: usize, last: usize, backtrack: &Sequence,
+                       lookahead: &Sequence, filter: &GlyphFilter) -> bool {
+    let mut index = first;
+    for i in 0..backtrack.len() {
+        match filter.prev(glyphs, index) {
+            Some(prev) if backtrack.matches(i, glyphs[prev].glyph_id) => index = prev,
+            _ => return false,
+        }
+    }
+
+    let mut index = last;
+    for i in 0..lookahead.len() {
+        match filter.next(glyphs, index) {
+            Some(next) if lookahead.matches(i, glyphs[next].glyph_id) => index = next,
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// A sequence of input glyphs, with the glyphs around it, and the lookups
+/// to apply when they all match.  Plain contexts have no backtrack or
+/// lookahead glyphs.
+#[derive(Debug, Copy, Clone)]
+pub struct Rule<'tbl> {
+    pub backtrack: Sequence<'tbl>,
+    /// The input glyphs after the first, which the subtable has already
+    /// matched.
+    pub input: Sequence<'tbl>,
+    pub lookahead: Sequence<'tbl>,
+    lookups: &'tbl [u8],
+}
+
+impl<'tbl> Rule<'tbl> {
+    /// Reads the input, the lookups and, when chained, the backtrack and
+    /// lookahead of a rule, with values read by `matchers`.
+    fn parse(mut buffer: &'tbl [u8], chained: bool, matchers: [Matcher<'tbl>; 3]) -> Result<Rule<'tbl>> {
+        let empty = Sequence { values: &[], matcher: Matcher::Glyphs };
+
+        if chained {
+            let count = buffer.read::<u16>()? as usize;
+            let backtrack = Sequence { values: take(&mut buffer, 2 * count)?, matcher: matchers[0] };
+            let count = buffer.read::<u16>()? as usize;
+            verify!(count > 0);
+            let input = Sequence { values: take(&mut buffer, 2 * (count - 1))?, matcher: matchers[1] };
+            let count = buffer.read::<u16>()? as usize;
+            let lookahead = Sequence { values: take(&mut buffer, 2 * count)?, matcher: matchers[2] };
+            let count = buffer.read::<u16>()? as usize;
+            let lookups = take(&mut buffer, 4 * count)?;
+
+            Ok(Rule { backtrack, input, lookahead, lookups })
+        } else {
+            required_len!(buffer, 4);
+            let input_count = buffer.read::<u16>()? as usize;
+            let lookup_count = buffer.read::<u16>()? as usize;
+            verify!(input_count > 0);
+            let input = Sequence { values: take(&mut buffer, 2 * (input_count - 1))?, matcher: matchers[1] };
+            let lookups = take(&mut buffer, 4 * lookup_count)?;
+
+            Ok(Rule { backtrack: empty, input, lookahead: empty, lookups })
+        }
+    }
+
+    /// The lookups to apply, in order.
+    pub fn lookups(&self) -> SequenceLookupIter<'tbl> {
+        SequenceLookupIter(self.lookups)
+    }
+
+    /// Matches the rule at a glyph the subtable covers, returning the
+    /// positions of the input glyphs.
+    pub fn match_at(&self, glyphs: &[GlyphInfo], index: usize, filter: &GlyphFilter) -> Option<Vec<usize>> {
+        let mut positions = vec![index];
+        for i in 0..self.input.len() {
+            match filter.next(glyphs, positions[i]) {
+                Some(next) if self.input.matches(i, glyphs[next].glyph_id) => positions.push(next),
+                _ => return None,
+            }
+        }
+
+        let last = positions[positions.len() - 1];
+        if matches_context(glyphs, index, last, &self.backtrack, &self.lookahead, filter) {
+            Some(positions)
+        } else {
+            None
+        }
+    }
+}
+
+pub struct SequenceLookupIter<'tbl>(&'tbl [u8]);
+
+impl<'tbl> Iterator for SequenceLookupIter<'tbl> {
+    type Item = SequenceLookup;
+
+    fn next(&mut self) -> Option<SequenceLookup> {
+        let lookup = SequenceLookup {
+            sequence_index: try_opt!(u16_at(self.0, 0)),
+            lookup_index: try_opt!(u16_at(self.0, 2)),
+        };
+        self.0 = &self.0[4..];
+        Some(lookup)
+    }
+}
+
+/// A contextual or chained contextual subtable, in any of its three
+/// formats: rules of glyph ids, of glyph classes, or of coverage tables.
+#[derive(Debug, Copy, Clone)]
+pub struct Context<'tbl> {
+    data: &'tbl [u8],
+    format: u16,
+    chained: bool,
+    /// The glyphs that start an input sequence.
+    pub coverage: Coverage<'tbl>,
+    /// The classes of the backtrack, input and lookahead glyphs.
+    classes: [Option<ClassDef<'tbl>>; 3],
+    /// The number of rule sets, and the offset of the first.
+    count: u16,
+    sets: usize,
+    /// The single rule of format 3.
+    rule: Option<Rule<'tbl>>,
+}
+
+impl<'tbl> Context<'tbl> {
+    pub fn parse(buffer: &'tbl [u8], chained: bool) -> Result<Context<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 4);
+
+        let class_def = |offset: u16| -> Result<Option<ClassDef<'tbl>>> {
+            match data.get(offset as usize..) {
+                Some(_) if offset == 0 => Ok(None),
+                Some(class_def) => ClassDef::parse(class_def).map(Some),
+                None => Err(Error::UnexpectedEof),
+            }
+        };
+        let coverage = |offset: u16| -> Result<Coverage<'tbl>> {
+            match data.get(offset as usize..) {
+                Some(coverage) if offset != 0 => Coverage::parse(coverage),
+                _ => Err(Error::InvalidData),
+            }
+        };
+
+        let format = buffer.read::<u16>()?;
+        let mut context = Context {
+            data,
+            format,
+            chained,
+            coverage: Coverage::Format1(&[]),
+            classes: [None; 3],
+            count: 0,
+            sets: 0,
+            rule: None,
+        };
+
+        match format {
+            1 | 2 => {
+                context.coverage = coverage(buffer.read::<u16>()?)?;
+                if format == 2 {
+                    if chained {
+                        required_len!(buffer, 6);
+                        for i in 0..3 {
+                            context.classes[i] = class_def(buffer.read::<u16>()?)?;
+                        }
+                    } else {
+                        context.classes[1] = class_def(buffer.read::<u16>()?)?;
+                    }
+                }
+                context.count = buffer.read::<u16>()?;
+                context.sets = data.len() - buffer.len();
+                required_len!(buffer, 2 * context.count as usize);
+            },
+            3 => {
+                let matcher = Matcher::Coverages(data);
+                let empty = Sequence { values: &[], matcher };
+
+                let (backtrack, mut input, lookahead, count) = if chained {
+                    let backtrack = Sequence::read_coverages(data, &mut buffer)?;
+                    let input = Sequence::read_coverages(data, &mut buffer)?;
+                    let lookahead = Sequence::read_coverages(data, &mut buffer)?;
+                    (backtrack, input, lookahead, buffer.read::<u16>()?)
+                } else {
+                    required_len!(buffer, 4);
+                    let input_count = buffer.read::<u16>()? as usize;
+                    let count = buffer.read::<u16>()?;
+                    let input = Sequence { values: take(&mut buffer, 2 * input_count)?, matcher };
+                    (empty, input, empty, count)
+                };
+                let lookups = take(&mut buffer, 4 * count as usize)?;
+
+                // The first input coverage selects the glyphs to try, and
+                // the rule matches the rest.
+                verify!(!input.is_empty());
+                context.coverage = coverage(u16_at(input.values, 0).unwrap_or(0))?;
+                input.values = &input.values[2..];
+                context.rule = Some(Rule { backtrack, input, lookahead, lookups });
+            },
+            _ => return Err(Error::InvalidData),
+        }
+
+        Ok(context)
+    }
+
+    pub fn format(&self) -> u16 {
+        self.format
+    }
+
+    /// The rules to try at a glyph, in order of preference.
+    pub fn rules(&self, glyph_id: u16) -> RuleIter<'tbl> {
+        let mut rules = RuleIter {
+            set: &[],
+            index: 0,
+            count: 0,
+            chained: self.chained,
+            matchers: [Matcher::Glyphs; 3],
+            rule: None,
+        };
+
+        let coverage_index = match self.coverage.coverage_index(glyph_id) {
+            Some(index) => index,
+            None => return rules,
+        };
+
+        let set_index = match self.format {
+            1 => coverage_index,
+            2 => self.classes[1].map_or(0, |classes| classes.class(glyph_id)),
+            _ => {
+                rules.rule = self.rule;
+                return rules
+            },
+        };
+
+        if self.format == 2 {
+            for i in 0..3 {
+                rules.matchers[i] = Matcher::Classes(self.classes[i]);
+            }
+        }
+
+        if set_index < self.count {
+            let offset = u16_at(self.data, self.sets + 2 * set_index as usize).unwrap_or(0) as usize;
+            if offset != 0 {
+                if let Some(set) = self.data.get(offset..) {
+                    rules.set = set;
+                    rules.count = u16_at(set, 0).unwrap_or(0);
+                }
+            }
+        }
+
+        rules
+    }
+}
+
+pub struct RuleIter<'tbl> {
+    set: &'tbl [u8],
+    index: u16,
+    count: u16,
+    chained: bool,
+    matchers: [Matcher<'tbl>; 3],
+    rule: Option<Rule<'tbl>>,
+}
+
+impl<'tbl> Iterator for RuleIter<'tbl> {
+    type Item = Rule<'tbl>;
+
+    fn next(&mut self) -> Option<Rule<'tbl>> {
+        if let Some(rule) = self.rule.take() {
+            return Some(rule)
+        }
+
+        while self.index < self.count {
+            self.index += 1;
+
+            let offset = try_opt!(u16_at(self.set, 2 * self.index as usize)) as usize;
+            let rule = self.set.get(offset..)
+                .and_then(|rule| Rule::parse(rule, self.chained, self.matchers).ok());
+            if rule.is_some() {
+                return rule
+            }
+        }
+
+        None
+    }
+}
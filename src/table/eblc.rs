@@ -0,0 +1,338 @@
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+
+// Embedded bitmap location tables.  The `EBLC` and `CBLC` tables share
+// the same layout, and locate bitmaps in `EBDT` and `CBDT` respectively.
+
+#[derive(Debug)]
+pub struct BitmapLocation<'tbl> {
+    buffer: &'tbl [u8],
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub num_sizes: u32,
+}
+
+impl<'tbl> Table<'tbl> for BitmapLocation<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<BitmapLocation<'tbl>> {
+        required_len!(buffer, 8);
+
+        let head = buffer;
+        let major_version = buffer.read::<u16>()?;
+        let minor_version = buffer.read::<u16>()?;
+        let num_sizes = buffer.read::<u32>()?;
+
+        verify!(major_version == 2 || major_version == 3);
+        required_len!(buffer, num_sizes as usize * BitmapSize::size());
+
+        Ok(BitmapLocation { buffer: head, major_version, minor_version, num_sizes })
+    }
+}
+
+impl<'tbl> BitmapLocation<'tbl> {
+    pub fn sizes(&self) -> BitmapSizeIter<'tbl> {
+        BitmapSizeIter {
+            buffer: &self.buffer[8..],
+            current: 0,
+            num_sizes: self.num_sizes,
+        }
+    }
+
+    /// Finds the metrics of a glyph in the largest strike containing it.
+    /// `bitmap_data` is the matching `EBDT` or `CBDT` table.
+    pub fn get_glyph_metrics(&self, glyph_id: u16, bitmap_data: &[u8])
+        -> Option<(BitmapSize, BigGlyphMetrics)>
+    {
+        let mut best: Option<(BitmapSize, BigGlyphMetrics)> = None;
+
+        for size in self.sizes() {
+            if glyph_id < size.start_glyph_index || glyph_id > size.end_glyph_index {
+                continue
+            }
+
+            if let Some((b, _)) = best.as_ref() {
+                if b.ppem_y >= size.ppem_y {
+                    continue
+                }
+            }
+
+            if let Some(metrics) = self.get_strike_metrics(&size, glyph_id, bitmap_data) {
+                best = Some((size, metrics));
+            }
+        }
+
+        best
+    }
+
+    fn get_strike_metrics(&self, size: &BitmapSize, glyph_id: u16, bitmap_data: &[u8])
+        -> Option<BigGlyphMetrics>
+    {
+        let array = try_opt!(self.buffer.get(size.index_sub_table_array_offset as usize..));
+
+        for i in 0..size.number_of_index_sub_tables as usize {
+            let mut rec = try_opt!(array.get(8 * i..));
+            let first = try_opt!(rec.read::<u16>().ok());
+            let last = try_opt!(rec.read::<u16>().ok());
+            let offset = try_opt!(rec.read::<u32>().ok()) as usize;
+
+            if glyph_id < first || glyph_id > last {
+                continue
+            }
+
+            let subtable = try_opt!(array.get(offset..));
+            return IndexSubTable::parse(subtable)
+                .ok()
+                .and_then(|sub| sub.get_glyph_metrics(glyph_id - first, glyph_id, bitmap_data))
+        }
+
+        None
+    }
+}
+
+pub struct BitmapSizeIter<'a> {
+    buffer: &'a [u8],
+    current: u32,
+    num_sizes: u32,
+}
+
+impl<'a> Iterator for BitmapSizeIter<'a> {
+    type Item = BitmapSize;
+
+    fn next(&mut self) -> Option<BitmapSize> {
+        if self.current >= self.num_sizes {
+            return None
+        }
+
+        // The length of the records is checked when the table is parsed.
+        self.current += 1;
+        let size = match BitmapSize::parse(self.buffer) {
+            Ok(size) => size,
+            Err(_) => unreachable!(),
+        };
+        self.buffer = &self.buffer[BitmapSize::size()..];
+        Some(size)
+    }
+}
+
+/// A strike of bitmaps at a single ppem.  The line metrics are omitted.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BitmapSize {
+    pub index_sub_table_array_offset: u32,
+    pub index_tables_size: u32,
+    pub number_of_index_sub_tables: u32,
+    pub start_glyph_index: u16,
+    pub end_glyph_index: u16,
+    pub ppem_x: u8,
+    pub ppem_y: u8,
+    pub bit_depth: u8,
+}
+
+impl SizedTable for BitmapSize {
+    fn size() -> usize {
+        48
+    }
+}
+
+impl<'tbl> Table<'tbl> for BitmapSize {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<BitmapSize> {
+        required_len!(buffer, Self::size());
+
+        let index_sub_table_array_offset = buffer.read::<u32>()?;
+        let index_tables_size = buffer.read::<u32>()?;
+        let number_of_index_sub_tables = buffer.read::<u32>()?;
+        let _ /* color_ref */ = buffer.read::<u32>()?;
+
+        // Horizontal and vertical line metrics.
+        let (_, mut buffer) = buffer.split_at(24);
+
+        let start_glyph_index = buffer.read::<u16>()?;
+        let end_glyph_index = buffer.read::<u16>()?;
+        let ppem_x = buffer.read::<u8>()?;
+        let ppem_y = buffer.read::<u8>()?;
+        let bit_depth = buffer.read::<u8>()?;
+
+        Ok(BitmapSize {
+            index_sub_table_array_offset,
+            index_tables_size,
+            number_of_index_sub_tables,
+            start_glyph_index,
+            end_glyph_index,
+            ppem_x,
+            ppem_y,
+            bit_depth,
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Table)]
+pub struct BigGlyphMetrics {
+    pub height: u8,
+    pub width: u8,
+    pub hori_bearing_x: i8,
+    pub hori_bearing_y: i8,
+    pub hori_advance: u8,
+    pub vert_bearing_x: i8,
+    pub vert_bearing_y: i8,
+    pub vert_advance: u8,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Table)]
+pub struct SmallGlyphMetrics {
+    pub height: u8,
+    pub width: u8,
+    pub bearing_x: i8,
+    pub bearing_y: i8,
+    pub advance: u8,
+}
+
+impl From<SmallGlyphMetrics> for BigGlyphMetrics {
+    fn from(m: SmallGlyphMetrics) -> BigGlyphMetrics {
+        BigGlyphMetrics {
+            height: m.height,
+            width: m.width,
+            hori_bearing_x: m.bearing_x,
+            hori_bearing_y: m.bearing_y,
+            hori_advance: m.advance,
+            vert_bearing_x: m.bearing_x,
+            vert_bearing_y: m.bearing_y,
+            vert_advance: m.advance,
+        }
+    }
+}
+
+struct IndexSubTable<'tbl> {
+    index_format: u16,
+    image_format: u16,
+    image_data_offset: u32,
+    body: &'tbl [u8],
+}
+
+impl<'tbl> Table<'tbl> for IndexSubTable<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<IndexSubTable<'tbl>> {
+        required_len!(buffer, 8);
+
+        let index_format = buffer.read::<u16>()?;
+        let image_format = buffer.read::<u16>()?;
+        let image_data_offset = buffer.read::<u32>()?;
+
+        Ok(IndexSubTable { index_format, image_format, image_data_offset, body: buffer })
+    }
+}
+
+impl<'tbl> IndexSubTable<'tbl> {
+    /// `index` is the position of the glyph relative to the subtable's first glyph.
+    fn get_glyph_metrics(&self, index: u16, glyph_id: u16, bitmap_data: &[u8])
+        -> Option<BigGlyphMetrics>
+    {
+        let index = index as usize;
+        let mut body = self.body;
+
+        let offset = match self.index_format {
+            1 => {
+                let mut buf = try_opt!(body.get(4 * index..));
+                let start = try_opt!(buf.read::<u32>().ok());
+                let end = try_opt!(buf.read::<u32>().ok());
+                if start == end { return None }
+                start as usize
+            },
+
+            2 => {
+                let _ /* image_size */ = try_opt!(body.read::<u32>().ok());
+                return body.read_table::<BigGlyphMetrics>().ok()
+            },
+
+            3 => {
+                let mut buf = try_opt!(body.get(2 * index..));
+                let start = try_opt!(buf.read::<u16>().ok());
+                let end = try_opt!(buf.read::<u16>().ok());
+                if start == end { return None }
+                start as usize
+            },
+
+            4 => {
+                let num_glyphs = try_opt!(body.read::<u32>().ok()) as usize;
+                let mut found = None;
+                for _ in 0..num_glyphs {
+                    let id = try_opt!(body.read::<u16>().ok());
+                    let offset = try_opt!(body.read::<u16>().ok());
+                    if id == glyph_id {
+                        found = Some(offset as usize);
+                        break
+                    }
+                }
+                try_opt!(found)
+            },
+
+            5 => {
+                let _ /* image_size */ = try_opt!(body.read::<u32>().ok());
+                let metrics = try_opt!(body.read_table::<BigGlyphMetrics>().ok());
+                let num_glyphs = try_opt!(body.read::<u32>().ok());
+                for _ in 0..num_glyphs {
+                    if try_opt!(body.read::<u16>().ok()) == glyph_id {
+                        return Some(metrics)
+                    }
+                }
+                return None
+            },
+
+            _ => return None,
+        };
+
+        let start = self.image_data_offset as usize + offset;
+        let mut data = try_opt!(bitmap_data.get(start..));
+
+        match self.image_format {
+            1 | 2 | 8 | 17 => data.read_table::<SmallGlyphMetrics>().ok().map(From::from),
+            6 | 7 | 9 | 18 => data.read_table::<BigGlyphMetrics>().ok(),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn bitmap_metrics() {
+    let eblc: &[u8] = &[
+        0x00, 0x02, 0x00, 0x00, // version 2.0
+        0x00, 0x00, 0x00, 0x01, // 1 size
+
+        // BitmapSize
+        0x00, 0x00, 0x00, 0x38, // index subtable array offset: 56
+        0x00, 0x00, 0x00, 0x14, // index tables size
+        0x00, 0x00, 0x00, 0x01, // 1 index subtable
+        0x00, 0x00, 0x00, 0x00, // color ref
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // hori line metrics
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // vert line metrics
+        0x00, 0x05, 0x00, 0x06, // glyphs 5 to 6
+        0x10, 0x10, 0x01, 0x01, // ppem 16, 1 bit
+
+        // IndexSubTableArray
+        0x00, 0x05, 0x00, 0x06, // glyphs 5 to 6
+        0x00, 0x00, 0x00, 0x08, // subtable offset
+
+        // IndexSubTable format 3, image format 1
+        0x00, 0x03, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x04, // image data offset
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x07,
+    ];
+
+    let ebdt: &[u8] = &[
+        0x00, 0x02, 0x00, 0x00, // version 2.0
+        0x0C, 0x08, 0x01, 0x0A, 0x0A, 0x00, 0x00,
+    ];
+
+    let loc = BitmapLocation::parse(eblc).expect("unable to parse EBLC table");
+    let sizes = loc.sizes().collect::<Vec<_>>();
+    assert_eq!(sizes.len(), 1);
+    assert_eq!(sizes[0].ppem_y, 16);
+
+    assert!(loc.get_glyph_metrics(5, ebdt).is_none());
+    assert!(loc.get_glyph_metrics(7, ebdt).is_none());
+
+    let (size, metrics) = loc.get_glyph_metrics(6, ebdt)
+        .expect("unable to find bitmap metrics");
+
+    assert_eq!(size.ppem_x, 16);
+    assert_eq!(metrics.height, 12);
+    assert_eq!(metrics.width, 8);
+    assert_eq!(metrics.hori_bearing_x, 1);
+    assert_eq!(metrics.hori_bearing_y, 10);
+    assert_eq!(metrics.hori_advance, 10);
+}
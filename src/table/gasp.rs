@@ -0,0 +1,110 @@
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+
+/// Grid-fitting and scan-conversion procedure table.  Gives the
+/// rendering behaviour the font was designed for, by ranges of ppem.
+#[derive(Debug)]
+pub struct Gasp<'tbl> {
+    ranges: &'tbl [u8],
+    pub version: u16,
+    pub num_ranges: u16,
+}
+
+impl<'tbl> Table<'tbl> for Gasp<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Gasp<'tbl>> {
+        required_len!(buffer, 4);
+
+        let version = buffer.read::<u16>()?;
+        let num_ranges = buffer.read::<u16>()?;
+
+        verify!(version <= 1);
+        required_len!(buffer, num_ranges as usize * GaspRange::size());
+
+        Ok(Gasp {
+            ranges: &buffer[..num_ranges as usize * GaspRange::size()],
+            version,
+            num_ranges,
+        })
+    }
+}
+
+impl<'tbl> Gasp<'tbl> {
+    pub fn ranges(&self) -> GaspRangeIter<'tbl> {
+        GaspRangeIter { buffer: self.ranges }
+    }
+
+    /// The behaviour for the first range whose maximum is at least `ppem`.
+    /// Version 0 tables only define the grid-fitting and grayscale bits.
+    pub fn get_behavior(&self, ppem: u16) -> Option<GaspBehavior> {
+        let range = try_opt!(self.ranges().find(|r| ppem <= r.range_max_ppem));
+        let behavior = range.get_behavior();
+
+        if self.version == 0 {
+            Some(behavior & (GRIDFIT | DOGRAY))
+        } else {
+            Some(behavior)
+        }
+    }
+}
+
+pub struct GaspRangeIter<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> Iterator for GaspRangeIter<'a> {
+    type Item = GaspRange;
+
+    fn next(&mut self) -> Option<GaspRange> {
+        // The length of the ranges is checked when the table is parsed.
+        self.buffer.read_table::<GaspRange>().ok()
+    }
+}
+
+#[derive(Debug, Table, PartialEq)]
+pub struct GaspRange {
+    pub range_max_ppem: u16,
+    pub range_gasp_behavior: u16,
+}
+
+impl GaspRange {
+    pub fn get_behavior(&self) -> GaspBehavior {
+        GaspBehavior::from_bits_truncate(self.range_gasp_behavior)
+    }
+}
+
+bitflags! {
+    pub struct GaspBehavior: u16 {
+        const GRIDFIT             = 1 << 0;
+        const DOGRAY              = 1 << 1;
+        const SYMMETRIC_GRIDFIT   = 1 << 2;
+        const SYMMETRIC_SMOOTHING = 1 << 3;
+    }
+}
+
+#[test]
+fn gasp() {
+    use font::Font;
+
+    let buf = open_font!("data/OpenSans-Regular.ttf");
+    let font = Font::from_buffer(&buf).expect("Unable to parse font");
+    let gasp = font.get_table::<Gasp>().expect("unable to read gasp table");
+
+    assert_eq!(gasp.version, 1);
+    assert_eq!(gasp.ranges().collect::<Vec<_>>(), vec![
+        GaspRange { range_max_ppem: 8, range_gasp_behavior: 10 },
+        GaspRange { range_max_ppem: 13, range_gasp_behavior: 7 },
+        GaspRange { range_max_ppem: 65535, range_gasp_behavior: 15 },
+    ]);
+
+    assert_eq!(gasp.get_behavior(8), Some(DOGRAY | SYMMETRIC_SMOOTHING));
+    assert_eq!(gasp.get_behavior(9), Some(GRIDFIT | DOGRAY | SYMMETRIC_GRIDFIT));
+    assert_eq!(gasp.get_behavior(100), Some(GaspBehavior::all()));
+
+    // Version 0 ignores the symmetric bits, and sizes may be left uncovered.
+    let buf: &[u8] = &[
+        0x00, 0x00, 0x00, 0x01,
+        0x00, 0x10, 0x00, 0x0F,
+    ];
+    let gasp = Gasp::parse(buf).expect("unable to parse gasp table");
+    assert_eq!(gasp.get_behavior(16), Some(GRIDFIT | DOGRAY));
+    assert_eq!(gasp.get_behavior(17), None);
+}
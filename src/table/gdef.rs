@@ -1,12 +1,13 @@
 use decode::{Error, Result, SizedTable, Table, TableInherited, Primitive, ReadPrimitive, ReadTable};
 use decode::primitives::Ignored;
+use table::layout::{ClassDef, Coverage};
 
 #[derive(Debug)]
 pub struct Header<'tbl> {
-    pub glyph_class_def: Option<&'tbl [u8]>,
+    pub glyph_class_def: Option<ClassDef<'tbl>>,
     pub attach_list: Option<&'tbl [u8]>,
     pub lig_caret_list: Option<&'tbl [u8]>,
-    pub mark_attach_class_def: Option<&'tbl [u8]>,
+    pub mark_attach_class_def: Option<ClassDef<'tbl>>,
     pub mark_glyph_sets_def: Option<&'tbl [u8]>,
     pub item_var_store: Option<&'tbl [u8]>,
 }
@@ -22,10 +23,16 @@ impl<'tbl> Table<'tbl> for Header<'tbl> {
         let minor = buffer.read::<u16>()?;
 
         // Version >= 1.0
-        let glyph_class_def = offset_maybe_null!(head, buffer);
+        let glyph_class_def = match offset_maybe_null!(head, buffer) {
+            Some(data) => Some(ClassDef::parse(data)?),
+            None => None,
+        };
         let attach_list = offset_maybe_null!(head, buffer);
         let lig_caret_list = offset_maybe_null!(head, buffer);
-        let mark_attach_class_def = offset_maybe_null!(head, buffer);
+        let mark_attach_class_def = match offset_maybe_null!(head, buffer) {
+            Some(data) => Some(ClassDef::parse(data)?),
+            None => None,
+        };
 
         // Version >= 1.2
         let mark_glyph_sets_def = if minor >= 2 {
@@ -54,8 +61,28 @@ impl<'tbl> Table<'tbl> for Header<'tbl> {
     }
 }
 
+impl<'tbl> Header<'tbl> {
+    /// The class of a glyph: 1 for base glyphs, 2 for ligatures, 3 for
+    /// marks and 4 for components, or 0 when it has none.
+    pub fn glyph_class(&self, glyph_id: u16) -> u16 {
+        self.glyph_class_def.as_ref().map_or(0, |classes| classes.class(glyph_id))
+    }
+
+    pub fn mark_attach_class(&self, glyph_id: u16) -> u16 {
+        self.mark_attach_class_def.as_ref().map_or(0, |classes| classes.class(glyph_id))
+    }
+
+    /// Whether a glyph is in one of the mark glyph sets.
+    pub fn is_in_mark_set(&self, set: u16, glyph_id: u16) -> bool {
+        self.mark_glyph_sets_def
+            .and_then(|data| MarkGlyphSets::parse(data).ok())
+            .and_then(|sets| sets.coverage(set))
+            .is_some_and(|coverage| coverage.contains(glyph_id))
+    }
+}
+
 pub struct AttachList<'tbl> {
-    coverage: &'tbl [u8],
+    pub coverage: Coverage<'tbl>,
     attach_point: &'tbl [u8],
 }
 
@@ -73,7 +100,7 @@ impl<'tbl> Table<'tbl> for AttachList<'tbl> {
             return Err(Error::UnexpectedEof)
         }
 
-        let (_, coverage) = head.split_at(coverage_offset as usize);
+        let coverage = Coverage::parse(&head[coverage_offset as usize..])?;
 
         if buffer.len() < glyph_count_size {
             return Err(Error::UnexpectedEof)
@@ -104,7 +131,7 @@ impl<'tbl> Table<'tbl> for AttachPoint<'tbl> {
 }
 
 pub struct LigCaretList<'tbl> {
-    coverage: &'tbl [u8],
+    pub coverage: Coverage<'tbl>,
     lig_glyph: &'tbl [u8],
 }
 
@@ -122,7 +149,7 @@ impl<'tbl> Table<'tbl> for LigCaretList<'tbl> {
             return Err(Error::UnexpectedEof)
         }
 
-        let (_, coverage) = head.split_at(coverage_offset as usize);
+        let coverage = Coverage::parse(&head[coverage_offset as usize..])?;
 
         if buffer.len() < glyph_count_size {
             return Err(Error::UnexpectedEof)
@@ -164,21 +191,86 @@ impl<'tbl> Table<'tbl> for CaretValue<'tbl> {
     }
 }
 
+/// Sets of marks, used by lookups to skip marks outside a set.
 pub struct MarkGlyphSets<'tbl> {
-    coverage: &'tbl [u8],
+    data: &'tbl [u8],
+    count: u16,
 }
 
 impl<'tbl> Table<'tbl> for MarkGlyphSets<'tbl> {
-    fn parse(mut buffer: &[u8]) -> Result<MarkGlyphSets> {
+    fn parse(buffer: &'tbl [u8]) -> Result<MarkGlyphSets<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
         required_len!(buffer, 4);
         let format = buffer.read::<u16>()?;
-        let coverage_size = 4 * buffer.read::<u16>()? as usize;
+        let count = buffer.read::<u16>()?;
         verify!(format == 1);
-        required_len!(buffer, coverage_size);
-        let (coverage, _) = buffer.split_at(coverage_size);
+        required_len!(buffer, 4 * count as usize);
+
+        Ok(MarkGlyphSets { data, count })
+    }
+}
+
+impl<'tbl> MarkGlyphSets<'tbl> {
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
 
-        Ok(MarkGlyphSets { coverage })
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn coverage(&self, set: u16) -> Option<Coverage<'tbl>> {
+        if set >= self.count {
+            return None
+        }
+
+        let mut buffer = &self.data[4 + 4 * set as usize..];
+        let offset = try_opt!(buffer.read::<u32>().ok()) as usize;
+        Coverage::parse(try_opt!(self.data.get(offset..))).ok()
     }
 }
 
-// TODO: Item Variation Store Table, ref: https://www.microsoft.com/typography/otspec/otvaroverview.htm
\ No newline at end of file
+// TODO: Item Variation Store Table, ref: https://www.microsoft.com/typography/otspec/otvaroverview.htm
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use decode::Table;
+    use table::gdef::{AttachList, Header};
+
+    #[test]
+    fn glyph_classes() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gdef = font.get_table::<Header>().expect("Unable to read GDEF table");
+        let classes = gdef.glyph_class_def.expect("Missing glyph classes");
+        assert_eq!(classes.format(), 2);
+        assert_eq!(classes.class(36), 1);
+        assert_eq!(classes.iter().count(), 938);
+        assert!(gdef.mark_attach_class_def.is_none());
+
+        let buf = open_font!("data/NotoSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gdef = font.get_table::<Header>().expect("Unable to read GDEF table");
+        let classes = gdef.glyph_class_def.expect("Missing glyph classes");
+        assert_eq!(classes.class(0), 1);
+        assert_eq!(classes.class(608), 3);  // mark
+        assert_eq!(classes.class(3051), 2); // ligature
+        assert_eq!(classes.class(3052), 0);
+        assert_eq!(classes.class(5000), 0);
+        assert_eq!(classes.iter().filter(|&(_, class)| class == 3).count(), 333);
+
+        let marks = gdef.mark_attach_class_def.expect("Missing mark attachment classes");
+        assert_eq!((marks.class(608), marks.class(612), marks.class(36)), (2, 3, 0));
+        assert_eq!(marks.iter().last(), Some((2190, 1)));
+
+        let attach = AttachList::parse(gdef.attach_list.unwrap()).expect("Unable to read attach list");
+        assert_eq!(attach.coverage.format(), 2);
+        assert_eq!(attach.coverage.len(), 192);
+        assert_eq!(attach.coverage.coverage_index(2414), Some(0));
+        assert_eq!(attach.coverage.coverage_index(2422), Some(2));
+        assert_eq!(attach.coverage.coverage_index(3043), Some(191));
+        assert_eq!(attach.coverage.coverage_index(2416), None);
+        assert_eq!(attach.coverage.iter().take(4).collect::<Vec<_>>(), vec![2414, 2415, 2422, 2423]);
+    }
+}
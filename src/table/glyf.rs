@@ -1,25 +1,655 @@
 use decode::{Error, Result, SizedTable, Table, TableInherited, Primitive, ReadPrimitive, ReadTable};
 use decode::primitives::Ignored;
+use outline::{OutlineBuilder, ContourBuilder, Transform};
 
-// TODO: It's unclear if this is needed.
-// we might need it for SVG font renderering, but that's probably
-// better off in a separate crate.
+/// A glyph description from the `glyf` table.
+#[derive(Debug)]
+pub enum Glyph<'tbl> {
+    /// Glyphs without an outline, such as the space.
+    Empty,
+    Simple(SimpleGlyph<'tbl>),
+    Composite(CompositeGlyph<'tbl>),
+}
+
+impl<'tbl> Table<'tbl> for Glyph<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<Glyph<'tbl>> {
+        if buffer.is_empty() {
+            return Ok(Glyph::Empty)
+        }
+
+        let header = GlyphHeader::parse(buffer)?;
+        let (_, body) = buffer.split_at(GlyphHeader::size());
+
+        if header.num_of_contours >= 0 {
+            let description = GlyphDescription::parse(body, header.num_of_contours as u16)?;
+            Ok(Glyph::Simple(SimpleGlyph { header, description }))
+        } else {
+            Ok(Glyph::Composite(CompositeGlyph { header, components: body }))
+        }
+    }
+}
+
+impl<'tbl> Glyph<'tbl> {
+    pub fn header(&self) -> Option<&GlyphHeader> {
+        match *self {
+            Glyph::Empty => None,
+            Glyph::Simple(ref g) => Some(&g.header),
+            Glyph::Composite(ref g) => Some(&g.header),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SimpleGlyph<'tbl> {
+    pub header: GlyphHeader,
+    pub description: GlyphDescription<'tbl>,
+}
+
+#[derive(Debug)]
+pub struct CompositeGlyph<'tbl> {
+    pub header: GlyphHeader,
+    components: &'tbl [u8],
+}
+
+impl<'tbl> CompositeGlyph<'tbl> {
+    pub fn components(&self) -> ComponentIter<'tbl> {
+        ComponentIter { buffer: self.components, done: false }
+    }
+
+    /// The instructions following the last component, if any.
+    pub fn instructions(&self) -> &'tbl [u8] {
+        let mut iter = self.components();
+        let mut has_instructions = false;
+
+        for component in iter.by_ref() {
+            match component {
+                Ok(c) => has_instructions = c.flags.contains(WE_HAVE_INSTRUCTIONS),
+                Err(_) => return &[],
+            }
+        }
+
+        let mut buffer = iter.buffer;
+        if !has_instructions {
+            return &[]
+        }
+
+        match buffer.read::<u16>() {
+            Ok(size) if buffer.len() >= size as usize => &buffer[..size as usize],
+            _ => &[],
+        }
+    }
+}
+
+pub struct ComponentIter<'a> {
+    buffer: &'a [u8],
+    done: bool,
+}
+
+impl<'a> Iterator for ComponentIter<'a> {
+    type Item = Result<Component>;
+
+    fn next(&mut self) -> Option<Result<Component>> {
+        if self.done {
+            return None
+        }
+
+        let component = Component::parse(&mut self.buffer);
+        match component {
+            Ok(ref c) => self.done = !c.flags.contains(MORE_COMPONENTS),
+            Err(_) => self.done = true,
+        }
+
+        Some(component)
+    }
+}
+
+/// A reference to another glyph from a composite glyph.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Component {
+    pub flags: CompositeFlags,
+    pub glyph_id: u16,
+    /// Either an x offset, or the index of a point in the composite
+    /// glyph if `ARGS_ARE_XY_VALUES` is unset.
+    pub arg1: i32,
+    /// Either a y offset, or the index of a point in the component.
+    pub arg2: i32,
+    /// The 2x2 transform `[xx, xy, yx, yy]`, applied as
+    /// `x' = xx * x + yx * y` and `y' = xy * x + yy * y`.
+    pub transform: [f32; 4],
+}
+
+impl Component {
+    fn parse(buffer: &mut &[u8]) -> Result<Component> {
+        let flags = CompositeFlags::from_bits_truncate(buffer.read::<u16>()?);
+        let glyph_id = buffer.read::<u16>()?;
+
+        let (arg1, arg2) = match (flags.contains(ARG_1_AND_2_ARE_WORDS),
+                                  flags.contains(ARGS_ARE_XY_VALUES)) {
+            (true, true) => (buffer.read::<i16>()? as i32, buffer.read::<i16>()? as i32),
+            (true, false) => (buffer.read::<u16>()? as i32, buffer.read::<u16>()? as i32),
+            (false, true) => (buffer.read::<i8>()? as i32, buffer.read::<i8>()? as i32),
+            (false, false) => (buffer.read::<u8>()? as i32, buffer.read::<u8>()? as i32),
+        };
+
+        let f2dot14 = |buffer: &mut &[u8]| -> Result<f32> {
+            Ok(buffer.read::<i16>()? as f32 / 16384.0)
+        };
+
+        let transform = if flags.contains(WE_HAVE_A_SCALE) {
+            let scale = f2dot14(buffer)?;
+            [scale, 0.0, 0.0, scale]
+        } else if flags.contains(WE_HAVE_AN_X_AND_Y_SCALE) {
+            let x_scale = f2dot14(buffer)?;
+            let y_scale = f2dot14(buffer)?;
+            [x_scale, 0.0, 0.0, y_scale]
+        } else if flags.contains(WE_HAVE_A_TWO_BY_TWO) {
+            [f2dot14(buffer)?, f2dot14(buffer)?, f2dot14(buffer)?, f2dot14(buffer)?]
+        } else {
+            [1.0, 0.0, 0.0, 1.0]
+        };
+
+        Ok(Component { flags, glyph_id, arg1, arg2, transform })
+    }
+
+    pub fn transform_point(&self, p: &Point) -> Point {
+        let t = &self.transform;
+        Point {
+            x: t[0] * p.x + t[2] * p.y,
+            y: t[1] * p.x + t[3] * p.y,
+            on_curve: p.on_curve,
+        }
+    }
+
+    fn is_identity(&self) -> bool {
+        self.transform == [1.0, 0.0, 0.0, 1.0]
+    }
+
+    /// The offset of a component positioned by `ARGS_ARE_XY_VALUES`.
+    /// Offsets are unscaled unless explicitly requested.
+    pub fn xy_offset(&self) -> (f32, f32) {
+        let mut dx = self.arg1 as f32;
+        let mut dy = self.arg2 as f32;
+
+        if self.flags.contains(SCALED_COMPONENT_OFFSET)
+            && !self.flags.contains(UNSCALED_COMPONENT_OFFSET)
+        {
+            let p = self.transform_point(&Point { x: dx, y: dy, on_curve: true });
+            dx = p.x;
+            dy = p.y;
+        }
+
+        if self.flags.contains(ROUND_XY_TO_GRID) {
+            dx = dx.round();
+            dy = dy.round();
+        }
+
+        (dx, dy)
+    }
+}
+
+/// The default bound on composite nesting, when `maxp` does not provide one.
+pub const MAX_COMPONENT_DEPTH: u16 = 16;
 
-#[derive(Table, Debug)]
-struct GlyphHeader {
-	num_of_contours: i16,
-	x_min: i16,
-	y_min: i16,
-	x_max: i16,
-	y_max: i16,
+/// A glyph outline with every component of a composite glyph resolved
+/// into simple contours, in font units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outline {
+    pub contours: Vec<Contour>,
+    /// The component whose metrics replace those of the composite glyph.
+    pub metrics_glyph: Option<u16>,
 }
 
-struct GlyphDescription<'tbl> {
+impl Outline {
+    pub fn draw<B: OutlineBuilder>(&self, transform: &Transform, builder: &mut B) {
+        for contour in &self.contours {
+            contour.draw(transform, builder);
+        }
+    }
+
+    pub fn num_points(&self) -> usize {
+        self.contours.iter().map(|c| c.points.len()).sum()
+    }
+
+    fn point(&self, mut index: usize) -> Option<Point> {
+        for contour in &self.contours {
+            if index < contour.points.len() {
+                return Some(contour.points[index])
+            }
+            index -= contour.points.len();
+        }
+
+        None
+    }
+}
+
+/// Resolves a glyph into its outline, recursively flattening components.
+/// `load` fetches the description of a glyph, and composite glyphs may
+/// nest at most `max_depth` levels.  Cyclic references are an error.
+pub fn resolve_outline<'a, F>(glyph_id: u16, max_depth: u16, load: &F) -> Result<Outline>
+    where F: Fn(u16) -> Option<Glyph<'a>>
+{
+    let mut stack = Vec::new();
+    resolve_glyph(glyph_id, max_depth, load, &mut stack)
+}
+
+/// Streams a glyph outline to the builder, applying components'
+/// transforms on the fly.  Composite glyphs positioned by matching
+/// points are resolved into an `Outline` first.
+pub fn draw_glyph<'a, F, B>(glyph_id: u16, max_depth: u16, load: &F,
+                            transform: &Transform, builder: &mut B) -> Result<()>
+    where F: Fn(u16) -> Option<Glyph<'a>>, B: OutlineBuilder
+{
+    let mut stack = Vec::new();
+    draw_glyph_recursive(glyph_id, max_depth, load, transform, builder, &mut stack)
+}
+
+fn draw_glyph_recursive<'a, F, B>(glyph_id: u16, max_depth: u16, load: &F, transform: &Transform,
+                                  builder: &mut B, stack: &mut Vec<u16>) -> Result<()>
+    where F: Fn(u16) -> Option<Glyph<'a>>, B: OutlineBuilder
+{
+    let glyph = match load(glyph_id) {
+        Some(glyph) => glyph,
+        None => return Err(Error::InvalidData),
+    };
+
+    let composite = match glyph {
+        Glyph::Empty => return Ok(()),
+        Glyph::Simple(ref g) => return g.description.draw(transform, builder),
+        Glyph::Composite(composite) => composite,
+    };
+
+    verify!(stack.len() < max_depth as usize);
+    verify!(!stack.contains(&glyph_id));
+
+    let point_matched = composite.components()
+        .any(|c| c.map(|c| !c.flags.contains(ARGS_ARE_XY_VALUES)).unwrap_or(false));
+
+    if point_matched {
+        let outline = resolve_glyph(glyph_id, max_depth, load, stack)?;
+        outline.draw(transform, builder);
+        return Ok(())
+    }
+
+    stack.push(glyph_id);
+
+    for component in composite.components() {
+        let component = component?;
+        let (dx, dy) = component.xy_offset();
+        let t = &component.transform;
+        let local = Transform { xx: t[0], xy: t[1], yx: t[2], yy: t[3], dx, dy };
+
+        draw_glyph_recursive(component.glyph_id, max_depth, load,
+                             &local.then(transform), builder, stack)?;
+    }
+
+    stack.pop();
+    Ok(())
+}
+
+fn resolve_glyph<'a, F>(glyph_id: u16, max_depth: u16, load: &F, stack: &mut Vec<u16>) -> Result<Outline>
+    where F: Fn(u16) -> Option<Glyph<'a>>
+{
+    let glyph = match load(glyph_id) {
+        Some(glyph) => glyph,
+        None => return Err(Error::InvalidData),
+    };
+
+    let composite = match glyph {
+        Glyph::Empty => return Ok(Outline { contours: Vec::new(), metrics_glyph: None }),
+        Glyph::Simple(ref g) => return Ok(Outline {
+            contours: g.description.contours()?,
+            metrics_glyph: None,
+        }),
+        Glyph::Composite(composite) => composite,
+    };
+
+    verify!(stack.len() < max_depth as usize);
+    verify!(!stack.contains(&glyph_id));
+    stack.push(glyph_id);
+
+    let mut outline = Outline { contours: Vec::new(), metrics_glyph: None };
+
+    for component in composite.components() {
+        let component = component?;
+        let mut child = resolve_glyph(component.glyph_id, max_depth, load, stack)?;
+
+        if component.flags.contains(USE_MY_METRICS) {
+            outline.metrics_glyph = Some(child.metrics_glyph.unwrap_or(component.glyph_id));
+        }
+
+        if !component.is_identity() {
+            for contour in &mut child.contours {
+                for p in &mut contour.points {
+                    *p = component.transform_point(p);
+                }
+            }
+        }
+
+        let (dx, dy) = if component.flags.contains(ARGS_ARE_XY_VALUES) {
+            component.xy_offset()
+        } else {
+            // Align a point of the component with a point of the
+            // contours placed so far.
+            let parent = outline.point(component.arg1 as usize);
+            let child_point = child.point(component.arg2 as usize);
+
+            match (parent, child_point) {
+                (Some(p), Some(c)) => (p.x - c.x, p.y - c.y),
+                _ => return Err(Error::InvalidData),
+            }
+        };
+
+        for contour in &mut child.contours {
+            for p in &mut contour.points {
+                p.x += dx;
+                p.y += dy;
+            }
+        }
+
+        outline.contours.extend(child.contours);
+    }
+
+    stack.pop();
+    Ok(outline)
+}
+
+#[derive(Table, Debug, Copy, Clone, PartialEq)]
+pub struct GlyphHeader {
+	pub num_of_contours: i16,
+	pub x_min: i16,
+	pub y_min: i16,
+	pub x_max: i16,
+	pub y_max: i16,
+}
+
+#[derive(Debug)]
+pub struct GlyphDescription<'tbl> {
 	end_pts_of_contours: &'tbl [u8],
-	instructions: &'tbl [u8],
+	pub instructions: &'tbl [u8],
 	flags: &'tbl [u8],
 	x_coordinates: &'tbl [u8],
 	y_coordinates: &'tbl [u8],
+	num_points: usize,
+}
+
+impl<'tbl> GlyphDescription<'tbl> {
+    /// Splits the description of a simple glyph, following the glyph header.
+    pub fn parse(buffer: &'tbl [u8], num_contours: u16) -> Result<GlyphDescription<'tbl>> {
+        let end_size = 2 * num_contours as usize;
+        required_len!(buffer, end_size + 2);
+
+        let (end_pts_of_contours, mut buffer) = buffer.split_at(end_size);
+        let num_points = match num_contours {
+            0 => 0,
+            _ => {
+                let mut last = &end_pts_of_contours[end_size - 2..];
+                last.read::<u16>()? as usize + 1
+            },
+        };
+
+        let instruction_size = buffer.read::<u16>()? as usize;
+        required_len!(buffer, instruction_size);
+        let (instructions, buffer) = buffer.split_at(instruction_size);
+
+        // The size of the flags, and of each coordinate array,
+        // can only be found by walking the flags.
+        let mut flags_size = 0;
+        let mut x_size = 0;
+        let mut y_size = 0;
+        let mut count = 0;
+
+        while count < num_points {
+            let flag = match buffer.get(flags_size) {
+                Some(&flag) => GlyphFlag::from_bits_truncate(flag),
+                None => return Err(Error::UnexpectedEof),
+            };
+
+            let repeat = if flag.contains(REPEAT) {
+                match buffer.get(flags_size + 1) {
+                    Some(&repeat) => repeat as usize + 1,
+                    None => return Err(Error::UnexpectedEof),
+                }
+            } else { 1 };
+
+            flags_size += if flag.contains(REPEAT) { 2 } else { 1 };
+            count += repeat;
+
+            x_size += repeat * if flag.contains(X_SHORT_VECTOR) { 1 }
+                else if flag.contains(X_IS_SAME) { 0 }
+                else { 2 };
+
+            y_size += repeat * if flag.contains(Y_SHORT_VECTOR) { 1 }
+                else if flag.contains(Y_IS_SAME) { 0 }
+                else { 2 };
+        }
+
+        verify!(count == num_points);
+        required_len!(buffer, flags_size + x_size + y_size);
+
+        let (flags, buffer) = buffer.split_at(flags_size);
+        let (x_coordinates, buffer) = buffer.split_at(x_size);
+        let (y_coordinates, _) = buffer.split_at(y_size);
+
+        Ok(GlyphDescription {
+            end_pts_of_contours,
+            instructions,
+            flags,
+            x_coordinates,
+            y_coordinates,
+            num_points,
+        })
+    }
+
+    pub fn num_contours(&self) -> usize {
+        self.end_pts_of_contours.len() / 2
+    }
+
+    pub fn num_points(&self) -> usize {
+        self.num_points
+    }
+
+    /// The index of the last point in each contour.
+    pub fn end_points(&self) -> EndPointIter<'tbl> {
+        EndPointIter { buffer: self.end_pts_of_contours }
+    }
+
+    /// Decodes the points of every contour, in order.
+    pub fn points(&self) -> PointIter<'tbl> {
+        PointIter {
+            flags: self.flags,
+            x_coordinates: self.x_coordinates,
+            y_coordinates: self.y_coordinates,
+            flag: GlyphFlag::empty(),
+            repeat: 0,
+            remaining: self.num_points,
+            x: 0,
+            y: 0,
+        }
+    }
+
+    /// Decodes the points, split into contours.
+    pub fn contours(&self) -> Result<Vec<Contour>> {
+        let mut points = self.points();
+        let mut contours = Vec::with_capacity(self.num_contours());
+        let mut start = 0;
+
+        for end in self.end_points() {
+            let end = end as usize + 1;
+            verify!(end >= start && end <= self.num_points);
+
+            contours.push(Contour {
+                points: points.by_ref().take(end - start).collect(),
+            });
+            start = end;
+        }
+
+        Ok(contours)
+    }
+
+    /// Streams the contours to the builder, without allocating.
+    pub fn draw<B: OutlineBuilder>(&self, transform: &Transform, builder: &mut B) -> Result<()> {
+        let mut contour = ContourBuilder::new(builder, *transform);
+        let mut points = self.points();
+        let mut start = 0;
+
+        for end in self.end_points() {
+            let end = end as usize + 1;
+            verify!(end >= start && end <= self.num_points);
+
+            for p in points.by_ref().take(end - start) {
+                contour.push(p);
+            }
+            contour.finish();
+            start = end;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct EndPointIter<'a> {
+    buffer: &'a [u8],
+}
+
+impl<'a> Iterator for EndPointIter<'a> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        self.buffer.read::<u16>().ok()
+    }
+}
+
+/// Expands the flags and delta encoded coordinates of a simple glyph.
+pub struct PointIter<'a> {
+    flags: &'a [u8],
+    x_coordinates: &'a [u8],
+    y_coordinates: &'a [u8],
+    flag: GlyphFlag,
+    repeat: u8,
+    remaining: usize,
+    x: i32,
+    y: i32,
+}
+
+impl<'a> PointIter<'a> {
+    fn delta(buffer: &mut &[u8], short: bool, same_or_positive: bool) -> i32 {
+        // Sizes are checked when the glyph description is parsed.
+        match (short, same_or_positive) {
+            (true, true) => buffer.read::<u8>().unwrap_or(0) as i32,
+            (true, false) => -(buffer.read::<u8>().unwrap_or(0) as i32),
+            (false, true) => 0,
+            (false, false) => buffer.read::<i16>().unwrap_or(0) as i32,
+        }
+    }
+}
+
+impl<'a> Iterator for PointIter<'a> {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        if self.remaining == 0 {
+            return None
+        }
+
+        self.remaining -= 1;
+        if self.repeat > 0 {
+            self.repeat -= 1;
+        } else {
+            self.flag = GlyphFlag::from_bits_truncate(try_opt!(self.flags.read::<u8>().ok()));
+            if self.flag.contains(REPEAT) {
+                self.repeat = try_opt!(self.flags.read::<u8>().ok());
+            }
+        }
+
+        let flag = self.flag;
+        self.x += Self::delta(&mut self.x_coordinates,
+            flag.contains(X_SHORT_VECTOR), flag.contains(POSITIVE_X));
+        self.y += Self::delta(&mut self.y_coordinates,
+            flag.contains(Y_SHORT_VECTOR), flag.contains(POSITIVE_Y));
+
+        Some(Point {
+            x: self.x as f32,
+            y: self.y as f32,
+            on_curve: flag.contains(ON_CURVE),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+/// A point of a glyph outline in font units.  Consecutive off curve
+/// points are quadratic control points with an implied on curve point
+/// halfway between them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+    pub on_curve: bool,
+}
+
+impl Point {
+    pub fn midpoint(&self, other: &Point) -> Point {
+        Point {
+            x: (self.x + other.x) / 2.0,
+            y: (self.y + other.y) / 2.0,
+            on_curve: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    pub points: Vec<Point>,
+}
+
+impl Contour {
+    /// Returns the points of the contour with the implied on curve points
+    /// inserted, starting from an on curve point.  The result alternates
+    /// between lines and quadratic curves, and is implicitly closed.
+    pub fn with_implied_points(&self) -> Vec<Point> {
+        let n = self.points.len();
+        if n == 0 {
+            return Vec::new()
+        }
+
+        // Start from the first on curve point, or the implied
+        // point before the first point if all are off curve.
+        let (start, first) = match self.points.iter().position(|p| p.on_curve) {
+            Some(idx) => (idx, self.points[idx]),
+            None => (0, self.points[n - 1].midpoint(&self.points[0])),
+        };
+
+        let mut result = Vec::with_capacity(2 * n);
+        result.push(first);
+
+        let skip = if first.on_curve && self.points[start] == first { 1 } else { 0 };
+        let mut prev = first;
+        for i in skip..n {
+            let p = self.points[(start + i) % n];
+            if !p.on_curve && !prev.on_curve {
+                result.push(prev.midpoint(&p));
+            }
+            result.push(p);
+            prev = p;
+        }
+
+        // Close the final curve back to the start.
+        if !prev.on_curve && !first.on_curve {
+            result.push(prev.midpoint(&first));
+        }
+
+        result
+    }
+
+    pub fn draw<B: OutlineBuilder>(&self, transform: &Transform, builder: &mut B) {
+        let mut contour = ContourBuilder::new(builder, *transform);
+        for p in &self.points {
+            contour.push(*p);
+        }
+        contour.finish();
+    }
 }
 
 bitflags! {
@@ -32,6 +662,7 @@ bitflags! {
 		const POSITIVE_X 		= 1 << 4;
 		const Y_IS_SAME			= 1 << 5;
 		const POSITIVE_Y		= 1 << 5;
+		const OVERLAP_SIMPLE	= 1 << 6;
 	}
 }
 
@@ -53,4 +684,139 @@ bitflags! {
 		const SCALED_COMPONENT_OFFSET	= 1 << 11;
 		const UNSCALED_COMPONENT_OFFSET	= 1 << 12;
 	}
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use decode::Table;
+    use table::glyf::{Glyph, Point};
+
+    macro_rules! points {
+        ($(($x:expr, $y:expr, $on:expr)),* $(,)*) => (
+            vec![$(Point { x: $x as f32, y: $y as f32, on_curve: $on == 1 }),*]
+        )
+    }
+
+    #[test]
+    fn simple_glyph_contours() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // 'A'
+        let glyph = match font.get_glyph(36).expect("unable to read glyph 'A'") {
+            Glyph::Simple(g) => g,
+            _ => panic!("'A' should be a simple glyph"),
+        };
+
+        assert_eq!(glyph.header.num_of_contours, 2);
+        assert_eq!(glyph.description.num_points(), 15);
+        assert_eq!(glyph.description.end_points().collect::<Vec<_>>(), [7, 14]);
+
+        let contours = glyph.description.contours().expect("unable to decode 'A'");
+        assert_eq!(contours[0].points, points![
+            (1120, 0, 1), (938, 465, 1), (352, 465, 1), (172, 0, 1),
+            (0, 0, 1), (578, 1468, 1), (721, 1468, 1), (1296, 0, 1),
+        ]);
+        assert_eq!(contours[1].points, points![
+            (885, 618, 1), (715, 1071, 1), (682, 1157, 0), (647, 1282, 1),
+            (625, 1186, 0), (584, 1071, 1), (412, 618, 1),
+        ]);
+
+        // 'o'
+        let glyph = match font.get_glyph(82).expect("unable to read glyph 'o'") {
+            Glyph::Simple(g) => g,
+            _ => panic!("'o' should be a simple glyph"),
+        };
+
+        let contours = glyph.description.contours().expect("unable to decode 'o'");
+        assert_eq!(contours[1].points, points![
+            (287, 549, 1), (287, 339, 0), (455, 119, 0), (618, 119, 1),
+            (781, 119, 0), (950, 338, 0), (950, 549, 1), (950, 758, 0),
+            (781, 975, 0), (616, 975, 1), (453, 975, 0), (287, 761, 0),
+        ]);
+
+        assert_eq!(contours[1].with_implied_points(), points![
+            (287, 549, 1), (287, 339, 0), (371, 229, 1), (455, 119, 0), (618, 119, 1),
+            (781, 119, 0), (865.5, 228.5, 1), (950, 338, 0), (950, 549, 1), (950, 758, 0),
+            (865.5, 866.5, 1), (781, 975, 0), (616, 975, 1), (453, 975, 0),
+            (370, 868, 1), (287, 761, 0),
+        ]);
+
+        // ' '
+        assert!(matches!(font.get_glyph(3), Some(Glyph::Empty)));
+    }
+
+    #[test]
+    fn composite_glyphs() {
+        use table::glyf::{ARGS_ARE_XY_VALUES, USE_MY_METRICS, WE_HAVE_INSTRUCTIONS};
+
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        // 'Á' is built from 'A' and 'acute'
+        let glyph = match font.get_glyph(131).expect("unable to read glyph 'Á'") {
+            Glyph::Composite(g) => g,
+            _ => panic!("'Á' should be a composite glyph"),
+        };
+
+        let components = glyph.components()
+            .collect::<Result<Vec<_>, _>>()
+            .expect("unable to read components");
+
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0].glyph_id, 36);
+        assert!(components[0].flags.contains(USE_MY_METRICS | ARGS_ARE_XY_VALUES));
+        assert_eq!(components[1].glyph_id, 118);
+        assert_eq!((components[1].arg1, components[1].arg2), (133, 338));
+        assert!(components[1].flags.contains(WE_HAVE_INSTRUCTIONS));
+        assert!(!glyph.instructions().is_empty());
+
+        let outline = font.get_glyph_outline(131).expect("unable to resolve 'Á'");
+        assert_eq!(outline.metrics_glyph, Some(36));
+        assert_eq!(outline.contours.len(), 3);
+        assert_eq!(outline.num_points(), 25);
+        assert_eq!(outline.contours[2].points[0], Point { x: 393.0 + 133.0, y: 1266.0 + 338.0, on_curve: true });
+
+        // Scaled components in Roboto do not scale their offsets.
+        let buf = open_font!("data/Roboto-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        let outline = font.get_glyph_outline(385).expect("unable to resolve glyph 385");
+        let x_min = outline.contours[0].points.iter().map(|p| p.x).fold(f32::MAX, f32::min);
+        let x_max = outline.contours[0].points.iter().map(|p| p.x).fold(f32::MIN, f32::max);
+        assert_eq!(x_min.round(), 163.0);
+        assert_eq!(x_max.round(), 1165.0);
+    }
+
+    #[test]
+    fn composite_guards() {
+        use table::glyf::resolve_outline;
+
+        // A composite glyph referencing itself, with one component at (0, 0).
+        let cyclic: &[u8] = &[
+            0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x0A,
+            0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+        ];
+
+        let load = |_| Glyph::parse(cyclic).ok();
+        assert!(resolve_outline(0, 16, &load).is_err());
+        assert!(resolve_outline(0, 0, &load).is_err());
+    }
+
+    #[test]
+    fn implied_points() {
+        use table::glyf::Contour;
+
+        // A contour of only off curve points starts on an implied point.
+        let contour = Contour { points: points![(0, 0, 0), (10, 0, 0), (10, 10, 0)] };
+        assert_eq!(contour.with_implied_points(), points![
+            (5, 5, 1), (0, 0, 0), (5, 0, 1), (10, 0, 0), (10, 5, 1), (10, 10, 0),
+        ]);
+
+        // Leading off curve points are moved after the first on curve point.
+        let contour = Contour { points: points![(0, 0, 0), (10, 0, 1), (10, 10, 0), (0, 10, 0)] };
+        assert_eq!(contour.with_implied_points(), points![
+            (10, 0, 1), (10, 10, 0), (5, 10, 1), (0, 10, 0), (0, 5, 1), (0, 0, 0),
+        ]);
+    }
+}
@@ -1,50 +1,214 @@
-// use decode::primitives::{Tag, FWord, UFWord};
-use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+use std::ops::Deref;
+use decode::{Error, Result, Table, ReadPrimitive};
+use decode::primitives::Tag;
+use buffer::{GlyphBuffer, GlyphPosition};
+use table::gdef;
+use table::layout::{coverage_at, u16_at, ClassDef, Coverage, GlyphFilter, LayoutTable, IGNORE_MARKS};
 
-pub struct Header<'tbl> {
-    scripts: &'tbl [u8],
-    features: &'tbl [u8],
-    lookups: &'tbl [u8],
-    variations: Option<&'tbl [u8]>,
-}
+/// The glyph positioning table.
+pub struct Header<'tbl>(LayoutTable<'tbl>);
 
 impl<'tbl> Table<'tbl> for Header<'tbl> {
-    fn parse(mut buffer: &[u8]) -> Result<Header> {
-        required_len!(buffer, 10);
+    fn parse(buffer: &'tbl [u8]) -> Result<Header<'tbl>> {
+        LayoutTable::parse(buffer).map(Header)
+    }
+}
 
-        let head = buffer;
-        let major = buffer.read::<u16>()?;
-        let minor = buffer.read::<u16>()?;
+impl<'tbl> Deref for Header<'tbl> {
+    type Target = LayoutTable<'tbl>;
 
-        verify!(major == 1);
+    fn deref(&self) -> &LayoutTable<'tbl> {
+        &self.0
+    }
+}
 
-        // minor >= 0
-        let scripts_offset = buffer.read::<u16>()? as usize;
-        let feature_offset = buffer.read::<u16>()? as usize;
-        let lookup_offset = buffer.read::<u16>()? as usize;
+impl<'tbl> Header<'tbl> {
+    /// Applies the lookups of some features for a script and language to
+    /// the positions of a glyph run, in lookup order, then resolves mark
+    /// attachments.  Returns whether any glyph was positioned.
+    pub fn apply(&self, buffer: &mut GlyphBuffer, gdef: Option<&gdef::Header>,
+                 script: Tag, language: Option<Tag>, features: &[Tag]) -> bool {
+        let mut applied = false;
+        for index in self.lookup_indices(script, language, features) {
+            applied |= self.apply_lookup(index, buffer, gdef);
+        }
+        buffer.resolve_attachments();
+        applied
+    }
 
-        required_len!(head,
-            max!(scripts_offset, feature_offset, lookup_offset));
+    /// Applies a lookup at each glyph of a run in turn, skipping the glyphs
+    /// the lookup flags ignore.  The offsets of marks stay relative to the
+    /// glyphs they are attached to until the attachments are resolved.
+    pub fn apply_lookup(&self, index: u16, buffer: &mut GlyphBuffer, gdef: Option<&gdef::Header>) -> bool {
+        let lookup = match self.lookups.get(index) {
+            Some(lookup) => lookup,
+            None => return false,
+        };
+        let filter = GlyphFilter::new(&lookup, gdef);
+        let subtables: Vec<_> = lookup.subtables()
+            .filter_map(|data| Subtable::parse(lookup.lookup_type, data).ok())
+            .collect();
 
-        let (_, scripts) = head.split_at(scripts_offset);
-        let (_, features) = head.split_at(feature_offset);
-        let (_, lookups) = head.split_at(lookup_offset);
+        let mut applied = false;
+        let mut index = 0;
+        while index < buffer.len() {
+            let next = if filter.skips(buffer.glyphs[index].glyph_id) {
+                None
+            } else {
+                subtables.iter()
+                    .filter_map(|subtable| subtable.apply(buffer, index, &filter))
+                    .next()
+            };
 
-        // minor == 1
-        let variations = match minor {
-            0 => None,
-            1 => {
-                let offset = buffer.read::<u32>()? as usize;
-                required_len!(head, offset);
-                Some(head.split_at(offset).1)
+            match next {
+                Some(next) => {
+                    applied = true;
+                    index = next;
+                },
+                None => index += 1,
             }
+        }
+        applied
+    }
+}
+
+/// A subtable of a GPOS lookup.
+pub enum Subtable<'tbl> {
+    SingleFormat1(SinglePosFormat1<'tbl>),
+    SingleFormat2(SinglePosFormat2<'tbl>),
+    PairFormat1(PairPosFormat1<'tbl>),
+    PairFormat2(PairPosFormat2<'tbl>),
+    MarkToBase(MarkBasePos<'tbl>),
+    MarkToLigature(MarkLigPos<'tbl>),
+    MarkToMark(MarkBasePos<'tbl>),
+}
+
+impl<'tbl> Subtable<'tbl> {
+    /// Reads a subtable, following extension subtables to the subtable
+    /// they hold.
+    pub fn parse(lookup_type: u16, buffer: &'tbl [u8]) -> Result<Subtable<'tbl>> {
+        let format = (&buffer[..]).read::<u16>()?;
+
+        Ok(match (lookup_type, format) {
+            (1, 1) => Subtable::SingleFormat1(SinglePosFormat1::parse(buffer)?),
+            (1, 2) => Subtable::SingleFormat2(SinglePosFormat2::parse(buffer)?),
+            (2, 1) => Subtable::PairFormat1(PairPosFormat1::parse(buffer)?),
+            (2, 2) => Subtable::PairFormat2(PairPosFormat2::parse(buffer)?),
+            (4, 1) => Subtable::MarkToBase(MarkBasePos::parse(buffer)?),
+            (5, 1) => Subtable::MarkToLigature(MarkLigPos::parse(buffer)?),
+            (6, 1) => Subtable::MarkToMark(MarkBasePos::parse(buffer)?),
+            (9, 1) => {
+                let mut header = buffer;
+                required_len!(header, 8);
+
+                let _ /* format */ = header.read::<u16>()?;
+                let lookup_type = header.read::<u16>()?;
+                let offset = header.read::<u32>()? as usize;
+                verify!(lookup_type != 9);
+
+                return Subtable::parse(lookup_type, buffer.get(offset..).ok_or(Error::UnexpectedEof)?)
+            },
             _ => return Err(Error::InvalidData),
+        })
+    }
+
+    /// Applies the subtable at a glyph, returning the index of the glyph
+    /// to continue from.
+    fn apply(&self, buffer: &mut GlyphBuffer, index: usize, filter: &GlyphFilter) -> Option<usize> {
+        let glyph_id = buffer.glyphs[index].glyph_id;
+
+        let value = match *self {
+            Subtable::SingleFormat1(ref single) => single.value(glyph_id),
+            Subtable::SingleFormat2(ref single) => single.value(glyph_id),
+            _ => None,
+        };
+        if let Some(value) = value {
+            value.apply(&mut buffer.positions[index]);
+            return Some(index + 1)
+        }
+
+        match *self {
+            Subtable::MarkToBase(ref mark_base) => {
+                // Marks attach to the glyph before them that is not a mark,
+                // whatever the lookup flags.
+                let base = try_opt!(filter.with_flags_replaced(IGNORE_MARKS).prev(&buffer.glyphs, index));
+                let (mark_class, mark_anchor) = try_opt!(mark_base.mark(glyph_id));
+                let base_anchor = try_opt!(mark_base.base_anchor(buffer.glyphs[base].glyph_id, mark_class));
+                return Some(attach(buffer, index, base, mark_anchor, base_anchor))
+            },
+            Subtable::MarkToLigature(ref mark_lig) => {
+                let ligature = try_opt!(filter.with_flags_replaced(IGNORE_MARKS).prev(&buffer.glyphs, index));
+                let (mark_class, mark_anchor) = try_opt!(mark_lig.mark(glyph_id));
+                let (mark, lig) = (buffer.glyphs[index], buffer.glyphs[ligature]);
+                let components = try_opt!(mark_lig.components(lig.glyph_id));
+
+                // Marks skipped over when the ligature formed belong to the
+                // component before them, and others to the last component.
+                let component = if mark.lig_id != 0 && mark.lig_id == lig.lig_id && mark.lig_component > 0 {
+                    min!(mark.lig_component as u16, components) - 1
+                } else {
+                    try_opt!(components.checked_sub(1))
+                };
+
+                let lig_anchor = try_opt!(mark_lig.ligature_anchor(lig.glyph_id, component, mark_class));
+                return Some(attach(buffer, index, ligature, mark_anchor, lig_anchor))
+            },
+            Subtable::MarkToMark(ref mark_mark) => {
+                let prev = try_opt!(filter.prev(&buffer.glyphs, index));
+                let (mark, prev_mark) = (buffer.glyphs[index], buffer.glyphs[prev]);
+                if filter.glyph_class(prev_mark.glyph_id) != 3 {
+                    return None
+                }
+
+                // Both marks must belong to the same ligature component,
+                // unless one of them is itself a ligature of marks.
+                let same_component = if mark.lig_id == prev_mark.lig_id {
+                    mark.lig_id == 0 || mark.lig_component == prev_mark.lig_component
+                } else {
+                    (mark.lig_id > 0 && mark.lig_component == 0)
+                        || (prev_mark.lig_id > 0 && prev_mark.lig_component == 0)
+                };
+                if !same_component {
+                    return None
+                }
+
+                let (mark_class, mark_anchor) = try_opt!(mark_mark.mark(glyph_id));
+                let base_anchor = try_opt!(mark_mark.base_anchor(prev_mark.glyph_id, mark_class));
+                return Some(attach(buffer, index, prev, mark_anchor, base_anchor))
+            },
+            _ => {},
+        }
+
+        let next = try_opt!(filter.next(&buffer.glyphs, index));
+        let second = buffer.glyphs[next].glyph_id;
+        let ((first_value, second_value), format) = match *self {
+            Subtable::PairFormat1(ref pair) => (try_opt!(pair.values(glyph_id, second)), pair.formats.1),
+            Subtable::PairFormat2(ref pair) => (try_opt!(pair.values(glyph_id, second)), pair.formats.1),
+            _ => return None,
         };
 
-        Ok(Header { scripts, features, lookups, variations })
+        first_value.apply(&mut buffer.positions[index]);
+        second_value.apply(&mut buffer.positions[next]);
+
+        // The second glyph is only skipped over when it was adjusted.
+        if format.is_empty() {
+            Some(next)
+        } else {
+            Some(next + 1)
+        }
     }
 }
 
+/// Places a mark so that its anchor is on the anchor of the glyph it is
+/// attached to, returning the index of the glyph after the mark.
+fn attach(buffer: &mut GlyphBuffer, mark: usize, base: usize, mark_anchor: Anchor, base_anchor: Anchor) -> usize {
+    let position = &mut buffer.positions[mark];
+    position.x_offset = base_anchor.x as i32 - mark_anchor.x as i32;
+    position.y_offset = base_anchor.y as i32 - mark_anchor.y as i32;
+    position.attached_to = Some(base);
+    mark + 1
+}
+
 bitflags! {
     pub struct ValueFormatFlags: u16 {
         const X_PLACEMENT        = 1 << 0;
@@ -58,61 +222,616 @@ bitflags! {
     }
 }
 
+impl ValueFormatFlags {
+    /// The size of a value record of this format.
+    pub fn size(&self) -> usize {
+        2 * self.bits().count_ones() as usize
+    }
+}
+
+/// An adjustment to the position of a glyph, in font units.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
 pub struct ValueRecord {
-    x_placement: Option<i16>,
-    y_placement: Option<i16>,
-    x_advance: Option<i16>,
-    y_advance: Option<i16>,
-    // TODO: implement device tables.
-    // x_placement_device: Option<&'tbl [u8]>,
-    // y_placement_device: Option<&'tbl [u8]>,
-    // x_advance_device: Option<&'tbl [u8]>,
-    // y_advance_device: Option<&'tbl [u8]>,
+    pub x_placement: Option<i16>,
+    pub y_placement: Option<i16>,
+    pub x_advance: Option<i16>,
+    pub y_advance: Option<i16>,
+    // TODO: implement device tables, whose offsets are read over.
 }
 
 impl ValueRecord {
-    fn parse(mut buffer: &[u8], format: ValueFormatFlags) -> Result<ValueRecord> {
-        // TODO: This is innacurate if the ValueRecord is a the tail end of a font
-        // and is only partially constructed.  I just don't know of a better way
-        // at the moment.
+    /// Reads a record holding only the fields of `format`.
+    pub fn parse(mut buffer: &[u8], format: ValueFormatFlags) -> Result<ValueRecord> {
+        required_len!(buffer, format.size());
+
+        let mut read = |flag: ValueFormatFlags| -> Result<Option<i16>> {
+            if format.contains(flag) {
+                Ok(Some(buffer.read::<i16>()?))
+            } else {
+                Ok(None)
+            }
+        };
+
+        Ok(ValueRecord {
+            x_placement: read(X_PLACEMENT)?,
+            y_placement: read(Y_PLACEMENT)?,
+            x_advance: read(X_ADVANCE)?,
+            y_advance: read(Y_ADVANCE)?,
+        })
+    }
+
+    pub fn apply(&self, position: &mut GlyphPosition) {
+        position.x_offset += self.x_placement.unwrap_or(0) as i32;
+        position.y_offset += self.y_placement.unwrap_or(0) as i32;
+        position.x_advance += self.x_advance.unwrap_or(0) as i32;
+        position.y_advance += self.y_advance.unwrap_or(0) as i32;
+    }
+}
+
+/// Adjusts every covered glyph by the same value.
+pub struct SinglePosFormat1<'tbl> {
+    pub coverage: Coverage<'tbl>,
+    pub value: ValueRecord,
+}
+
+impl<'tbl> Table<'tbl> for SinglePosFormat1<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<SinglePosFormat1<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 6);
+
+        verify!(buffer.read::<u16>()? == 1);
+        let coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let format = ValueFormatFlags::from_bits_truncate(buffer.read::<u16>()?);
+        let value = ValueRecord::parse(buffer, format)?;
+
+        Ok(SinglePosFormat1 { coverage, value })
+    }
+}
+
+impl<'tbl> SinglePosFormat1<'tbl> {
+    pub fn value(&self, glyph_id: u16) -> Option<ValueRecord> {
+        self.coverage.coverage_index(glyph_id).map(|_| self.value)
+    }
+}
+
+/// Adjusts each covered glyph by its own value.
+pub struct SinglePosFormat2<'tbl> {
+    pub coverage: Coverage<'tbl>,
+    format: ValueFormatFlags,
+    count: u16,
+    values: &'tbl [u8],
+}
+
+impl<'tbl> Table<'tbl> for SinglePosFormat2<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<SinglePosFormat2<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
         required_len!(buffer, 8);
 
-        let temp_x_pla = buffer.read::<i16>()?;
-        let temp_y_pla = buffer.read::<i16>()?;
-        let temp_x_adv = buffer.read::<i16>()?;
-        let temp_y_adv = buffer.read::<i16>()?;
+        verify!(buffer.read::<u16>()? == 2);
+        let coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let format = ValueFormatFlags::from_bits_truncate(buffer.read::<u16>()?);
+        let count = buffer.read::<u16>()?;
+        required_len!(buffer, count as usize * format.size());
 
-        let x_placement = match format.contains(X_PLACEMENT) {
-            false => None,
-            true => Some(temp_x_pla),
-        };
+        Ok(SinglePosFormat2 { coverage, format, count, values: buffer })
+    }
+}
 
-        let y_placement = match format.contains(Y_PLACEMENT) {
-            false => None,
-            true => Some(temp_y_pla),
-        };
+impl<'tbl> SinglePosFormat2<'tbl> {
+    pub fn value(&self, glyph_id: u16) -> Option<ValueRecord> {
+        let index = try_opt!(self.coverage.coverage_index(glyph_id));
+        if index >= self.count {
+            return None
+        }
 
-        let x_advance = match format.contains(X_ADVANCE) {
-            false => None,
-            true => Some(temp_x_adv),
-        };
+        let values = try_opt!(self.values.get(index as usize * self.format.size()..));
+        ValueRecord::parse(values, self.format).ok()
+    }
+}
+
+/// Adjusts pairs of glyphs, listing the second glyphs that follow each
+/// covered first glyph.
+pub struct PairPosFormat1<'tbl> {
+    data: &'tbl [u8],
+    pub coverage: Coverage<'tbl>,
+    /// The formats of the values of the first and second glyphs.
+    pub formats: (ValueFormatFlags, ValueFormatFlags),
+    count: u16,
+}
+
+impl<'tbl> Table<'tbl> for PairPosFormat1<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<PairPosFormat1<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 10);
+
+        verify!(buffer.read::<u16>()? == 1);
+        let coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let formats = (ValueFormatFlags::from_bits_truncate(buffer.read::<u16>()?),
+                       ValueFormatFlags::from_bits_truncate(buffer.read::<u16>()?));
+        let count = buffer.read::<u16>()?;
+        required_len!(buffer, 2 * count as usize);
+
+        Ok(PairPosFormat1 { data, coverage, formats, count })
+    }
+}
+
+impl<'tbl> PairPosFormat1<'tbl> {
+    /// The values of a pair, found by binary search of the pair set of the
+    /// first glyph.
+    pub fn values(&self, first: u16, second: u16) -> Option<(ValueRecord, ValueRecord)> {
+        let index = try_opt!(self.coverage.coverage_index(first));
+        if index >= self.count {
+            return None
+        }
+
+        let offset = try_opt!(u16_at(self.data, 10 + 2 * index as usize)) as usize;
+        let set = try_opt!(self.data.get(offset..));
+        let count = try_opt!(u16_at(set, 0)) as usize;
+        let sizes = (self.formats.0.size(), self.formats.1.size());
+        let record_size = 2 + sizes.0 + sizes.1;
+
+        let (mut lo, mut hi) = (0, count);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let record = try_opt!(set.get(2 + mid * record_size..2 + (mid + 1) * record_size));
+            let glyph_id = try_opt!(u16_at(record, 0));
+
+            if glyph_id < second {
+                lo = mid + 1;
+            } else if glyph_id > second {
+                hi = mid;
+            } else {
+                let first_value = try_opt!(ValueRecord::parse(&record[2..], self.formats.0).ok());
+                let second_value = try_opt!(ValueRecord::parse(&record[2 + sizes.0..], self.formats.1).ok());
+                return Some((first_value, second_value))
+            }
+        }
+
+        None
+    }
+}
+
+/// Adjusts pairs of glyphs by their classes.
+pub struct PairPosFormat2<'tbl> {
+    pub coverage: Coverage<'tbl>,
+    pub formats: (ValueFormatFlags, ValueFormatFlags),
+    pub first_classes: ClassDef<'tbl>,
+    pub second_classes: ClassDef<'tbl>,
+    counts: (u16, u16),
+    records: &'tbl [u8],
+}
+
+impl<'tbl> Table<'tbl> for PairPosFormat2<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<PairPosFormat2<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 16);
 
-        let y_advance = match format.contains(Y_ADVANCE) {
-            false => None,
-            true => Some(temp_y_adv)
+        verify!(buffer.read::<u16>()? == 2);
+        let coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let formats = (ValueFormatFlags::from_bits_truncate(buffer.read::<u16>()?),
+                       ValueFormatFlags::from_bits_truncate(buffer.read::<u16>()?));
+        let first_classes = ClassDef::parse(offset_maybe_null!(data, buffer).ok_or(Error::InvalidData)?)?;
+        let second_classes = ClassDef::parse(offset_maybe_null!(data, buffer).ok_or(Error::InvalidData)?)?;
+        let counts = (buffer.read::<u16>()?, buffer.read::<u16>()?);
+        required_len!(buffer, counts.0 as usize * counts.1 as usize * (formats.0.size() + formats.1.size()));
+
+        Ok(PairPosFormat2 { coverage, formats, first_classes, second_classes, counts, records: buffer })
+    }
+}
+
+impl<'tbl> PairPosFormat2<'tbl> {
+    pub fn values(&self, first: u16, second: u16) -> Option<(ValueRecord, ValueRecord)> {
+        try_opt!(self.coverage.coverage_index(first));
+
+        let first_class = self.first_classes.class(first);
+        let second_class = self.second_classes.class(second);
+        if first_class >= self.counts.0 || second_class >= self.counts.1 {
+            return None
+        }
+
+        let sizes = (self.formats.0.size(), self.formats.1.size());
+        let index = first_class as usize * self.counts.1 as usize + second_class as usize;
+        let record = try_opt!(self.records.get(index * (sizes.0 + sizes.1)..));
+
+        let first_value = try_opt!(ValueRecord::parse(record, self.formats.0).ok());
+        let second_value = try_opt!(ValueRecord::parse(&record[sizes.0..], self.formats.1).ok());
+        Some((first_value, second_value))
+    }
+}
+
+/// A point on a glyph that marks attach to, in font units.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Anchor {
+    pub x: i16,
+    pub y: i16,
+    /// The outline point a hinted anchor moves with, for format 2.
+    pub point: Option<u16>,
+    // TODO: implement the device tables of format 3.
+}
+
+impl<'tbl> Table<'tbl> for Anchor {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Anchor> {
+        required_len!(buffer, 6);
+
+        let format = buffer.read::<u16>()?;
+        let x = buffer.read::<i16>()?;
+        let y = buffer.read::<i16>()?;
+
+        let point = match format {
+            1 | 3 => None,
+            2 => Some(buffer.read::<u16>()?),
+            _ => return Err(Error::InvalidData),
         };
 
-        Ok(ValueRecord { x_placement, y_placement, x_advance, y_advance })
+        Ok(Anchor { x, y, point })
     }
 }
 
-pub struct SinglePosFormat1<'tbl> {
-    coverage: &'tbl [u8],
-    value: ValueRecord,
+/// Reads the anchor at an offset from `data`, with 0 for no anchor.
+fn anchor_at(data: &[u8], offset: usize) -> Option<Anchor> {
+    let offset = try_opt!(u16_at(data, offset)) as usize;
+    if offset == 0 {
+        return None
+    }
+    Anchor::parse(try_opt!(data.get(offset..))).ok()
 }
 
-impl<'tbl> Table<'tbl> for SinglePosFormat1<'tbl> {
-    fn parse(buffer: &[u8]) -> Result<SinglePosFormat1> {
-        unimplemented!()
+/// The class and anchor of each mark, by coverage index.
+#[derive(Debug, Copy, Clone)]
+pub struct MarkArray<'tbl> {
+    data: &'tbl [u8],
+    count: u16,
+}
+
+impl<'tbl> Table<'tbl> for MarkArray<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<MarkArray<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 2);
+
+        let count = buffer.read::<u16>()?;
+        required_len!(buffer, 4 * count as usize);
+
+        Ok(MarkArray { data, count })
+    }
+}
+
+impl<'tbl> MarkArray<'tbl> {
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn get(&self, index: u16) -> Option<(u16, Anchor)> {
+        if index >= self.count {
+            return None
+        }
+
+        let class = try_opt!(u16_at(self.data, 2 + 4 * index as usize));
+        Some((class, try_opt!(anchor_at(self.data, 4 + 4 * index as usize))))
+    }
+}
+
+/// Rows of anchors, one for each mark class, as used for bases, mark
+/// bases and ligature components.
+#[derive(Debug, Copy, Clone)]
+struct AnchorMatrix<'tbl> {
+    data: &'tbl [u8],
+    rows: u16,
+    classes: u16,
+}
+
+impl<'tbl> AnchorMatrix<'tbl> {
+    fn parse(buffer: &'tbl [u8], classes: u16) -> Result<AnchorMatrix<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 2);
+
+        let rows = buffer.read::<u16>()?;
+        required_len!(buffer, 2 * rows as usize * classes as usize);
+
+        Ok(AnchorMatrix { data, rows, classes })
+    }
+
+    fn get(&self, row: u16, class: u16) -> Option<Anchor> {
+        if row >= self.rows || class >= self.classes {
+            return None
+        }
+        anchor_at(self.data, 2 + 2 * (row as usize * self.classes as usize + class as usize))
+    }
+}
+
+/// Attaches marks to bases, or for mark to mark attachment, to other
+/// marks.
+pub struct MarkBasePos<'tbl> {
+    pub mark_coverage: Coverage<'tbl>,
+    pub base_coverage: Coverage<'tbl>,
+    pub marks: MarkArray<'tbl>,
+    bases: AnchorMatrix<'tbl>,
+}
+
+impl<'tbl> Table<'tbl> for MarkBasePos<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<MarkBasePos<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 12);
+
+        verify!(buffer.read::<u16>()? == 1);
+        let mark_coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let base_coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let classes = buffer.read::<u16>()?;
+        let marks = MarkArray::parse(offset_maybe_null!(data, buffer).ok_or(Error::InvalidData)?)?;
+        let bases = AnchorMatrix::parse(offset_maybe_null!(data, buffer).ok_or(Error::InvalidData)?, classes)?;
+
+        Ok(MarkBasePos { mark_coverage, base_coverage, marks, bases })
     }
-}
\ No newline at end of file
+}
+
+impl<'tbl> MarkBasePos<'tbl> {
+    /// The class and anchor of a mark.
+    pub fn mark(&self, glyph_id: u16) -> Option<(u16, Anchor)> {
+        self.marks.get(try_opt!(self.mark_coverage.coverage_index(glyph_id)))
+    }
+
+    /// The anchor of a base for a class of marks.
+    pub fn base_anchor(&self, glyph_id: u16, class: u16) -> Option<Anchor> {
+        self.bases.get(try_opt!(self.base_coverage.coverage_index(glyph_id)), class)
+    }
+}
+
+/// Attaches marks to the components of ligatures.
+pub struct MarkLigPos<'tbl> {
+    pub mark_coverage: Coverage<'tbl>,
+    pub ligature_coverage: Coverage<'tbl>,
+    pub marks: MarkArray<'tbl>,
+    classes: u16,
+    ligatures: &'tbl [u8],
+}
+
+impl<'tbl> Table<'tbl> for MarkLigPos<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<MarkLigPos<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 12);
+
+        verify!(buffer.read::<u16>()? == 1);
+        let mark_coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let ligature_coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let classes = buffer.read::<u16>()?;
+        let marks = MarkArray::parse(offset_maybe_null!(data, buffer).ok_or(Error::InvalidData)?)?;
+        let ligatures = offset_maybe_null!(data, buffer).ok_or(Error::InvalidData)?;
+        required_len!(ligatures, 2);
+
+        Ok(MarkLigPos { mark_coverage, ligature_coverage, marks, classes, ligatures })
+    }
+}
+
+impl<'tbl> MarkLigPos<'tbl> {
+    pub fn mark(&self, glyph_id: u16) -> Option<(u16, Anchor)> {
+        self.marks.get(try_opt!(self.mark_coverage.coverage_index(glyph_id)))
+    }
+
+    /// The anchors of each component of a ligature.
+    fn attach(&self, glyph_id: u16) -> Option<AnchorMatrix<'tbl>> {
+        let index = try_opt!(self.ligature_coverage.coverage_index(glyph_id));
+        if index >= try_opt!(u16_at(self.ligatures, 0)) {
+            return None
+        }
+
+        let offset = try_opt!(u16_at(self.ligatures, 2 + 2 * index as usize)) as usize;
+        AnchorMatrix::parse(try_opt!(self.ligatures.get(offset..)), self.classes).ok()
+    }
+
+    /// The number of components of a ligature.
+    pub fn components(&self, glyph_id: u16) -> Option<u16> {
+        self.attach(glyph_id).map(|attach| attach.rows)
+    }
+
+    /// The anchor of a ligature component for a class of marks.
+    pub fn ligature_anchor(&self, glyph_id: u16, component: u16, class: u16) -> Option<Anchor> {
+        try_opt!(self.attach(glyph_id)).get(component, class)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use decode::{Table, ReadPrimitive};
+    use decode::primitives::Tag;
+    use buffer::GlyphBuffer;
+    use table::gdef;
+    use table::gpos::{Anchor, Header, SinglePosFormat2, ValueFormatFlags, ValueRecord};
+    use table::gpos::{X_ADVANCE, X_ADVANCE_DEVICE, Y_PLACEMENT};
+    use table::layout::Coverage;
+
+    /// The coverage of a subtable, found by following the offsets.
+    fn coverage<'a>(gpos: &Header<'a>, lookup: u16, subtable: u16) -> Coverage<'a> {
+        let subtable = gpos.lookups.get(lookup).and_then(|l| l.subtable(subtable)).unwrap();
+        let offset = (&subtable[2..]).read::<u16>().unwrap() as usize;
+        Coverage::parse(&subtable[offset..]).expect("Unable to parse coverage")
+    }
+
+    #[test]
+    fn coverage_notosans() {
+        let buf = open_font!("data/NotoSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gpos = font.get_table::<Header>().expect("Unable to read GPOS table");
+
+        // Mark to base attachment.
+        let marks = coverage(&gpos, 0, 0);
+        assert_eq!(marks.format(), 2);
+        assert_eq!(marks.len(), 108);
+        assert_eq!(marks.coverage_index(612), Some(3));
+        assert_eq!(marks.coverage_index(1620), Some(107));
+        assert_eq!(marks.coverage_index(611), None);
+
+        // Pair adjustment.
+        let pairs = coverage(&gpos, 11, 0);
+        assert_eq!(pairs.format(), 1);
+        assert_eq!(pairs.iter().collect::<Vec<_>>(),
+                   vec![41, 209, 240, 256, 352, 356, 367, 371, 387, 404, 666, 684, 713]);
+        assert_eq!(pairs.coverage_index(713), Some(12));
+        assert!(!pairs.contains(42));
+    }
+
+    #[test]
+    fn value_records() {
+        let format = Y_PLACEMENT | X_ADVANCE | X_ADVANCE_DEVICE;
+        assert_eq!(format.size(), 6);
+        assert_eq!(ValueRecord::parse(&[0xFF, 0xF6, 0x00, 0x64, 0x00, 0x00], format).unwrap(), ValueRecord {
+            x_placement: None,
+            y_placement: Some(-10),
+            x_advance: Some(100),
+            y_advance: None,
+        });
+        assert!(ValueRecord::parse(&[0x00, 0x01, 0x00, 0x02], format).is_err());
+        assert!(ValueRecord::parse(&[], ValueFormatFlags::empty()).is_ok());
+
+        let buf: &[u8] = &[
+            0x00, 0x02, 0x00, 0x10, 0x00, 0x05, 0x00, 0x02, // x placement and advance
+            0x00, 0x01, 0x00, 0x02,
+            0xFF, 0xFF, 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x02, 0x00, 0x07, 0x00, 0x09,
+        ];
+        let single = SinglePosFormat2::parse(buf).expect("Unable to parse single positioning");
+        assert_eq!(single.value(7).map(|v| (v.x_placement, v.x_advance)), Some((Some(1), Some(2))));
+        assert_eq!(single.value(9).map(|v| (v.x_placement, v.x_advance)), Some((Some(-1), Some(0))));
+        assert_eq!(single.value(8), None);
+    }
+
+    #[test]
+    fn pairs_roboto() {
+        let buf = open_font!("data/Roboto-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        let gpos = font.get_table::<Header>().expect("Unable to read GPOS table");
+
+        let mut run = GlyphBuffer::new();
+        for &glyph_id in &[37, 58, 37] {
+            run.push(glyph_id, 0);
+        }
+
+        // The pair A V is in the class based subtable, after the pair sets.
+        assert!(gpos.apply(&mut run, None, Tag(*b"latn"), None, &[Tag(*b"kern")]));
+        assert_eq!(run.positions.iter().map(|p| p.x_advance).collect::<Vec<_>>(), vec![-87, -75, 0]);
+
+        // Capital spacing moves and widens each glyph.
+        let mut run = GlyphBuffer::new();
+        run.push(37, 0);
+        assert!(gpos.apply(&mut run, None, Tag(*b"latn"), None, &[Tag(*b"cpsp")]));
+        assert_eq!((run.positions[0].x_offset, run.positions[0].x_advance), (36, 72));
+    }
+
+    #[test]
+    fn pairs_skip_marks() {
+        let buf = open_font!("data/NotoSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gpos = font.get_table::<Header>().expect("Unable to read GPOS table");
+        let gdef = font.get_table::<gdef::Header>().expect("Unable to read GDEF table");
+
+        let mut run = GlyphBuffer::new();
+        for &glyph_id in &[36, 608, 57] {
+            run.push(glyph_id, 0);
+        }
+        assert!(gpos.apply_lookup(11, &mut run, Some(&gdef)));
+        assert_eq!(run.positions.iter().map(|p| p.x_advance).collect::<Vec<_>>(), vec![-40, 0, 0]);
+
+        // Without GDEF, the mark separates the pair.
+        let mut run = GlyphBuffer::new();
+        for &glyph_id in &[36, 608, 57] {
+            run.push(glyph_id, 0);
+        }
+        assert!(!gpos.apply_lookup(11, &mut run, None));
+    }
+
+    #[test]
+    fn anchors() {
+        assert_eq!(Anchor::parse(&[0x00, 0x01, 0xFF, 0x9C, 0x04, 0xB0]).unwrap(),
+                   Anchor { x: -100, y: 1200, point: None });
+        assert_eq!(Anchor::parse(&[0x00, 0x02, 0x00, 0x0A, 0x00, 0x14, 0x00, 0x05]).unwrap(),
+                   Anchor { x: 10, y: 20, point: Some(5) });
+        assert_eq!(Anchor::parse(&[0x00, 0x03, 0x00, 0x0A, 0x00, 0x14, 0x00, 0x00, 0x00, 0x00]).unwrap(),
+                   Anchor { x: 10, y: 20, point: None });
+        assert!(Anchor::parse(&[0x00, 0x02, 0x00, 0x0A, 0x00, 0x14]).is_err());
+        assert!(Anchor::parse(&[0x00, 0x04, 0x00, 0x0A, 0x00, 0x14]).is_err());
+    }
+
+    #[test]
+    fn marks_notosans() {
+        let buf = open_font!("data/NotoSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gpos = font.get_table::<Header>().expect("Unable to read GPOS table");
+        let gdef = font.get_table::<gdef::Header>().expect("Unable to read GDEF table");
+
+        let mut run = GlyphBuffer::new();
+        for &glyph_id in &[68, 608, 608] {
+            run.push(glyph_id, 0);
+        }
+        run.positions[0].x_advance = 1000;
+
+        // The second mark stacks on the first, and both are moved back over
+        // the advance of the base.
+        assert!(gpos.apply(&mut run, Some(&gdef), Tag(*b"latn"), None, &[Tag(*b"mark"), Tag(*b"mkmk")]));
+        let offsets = run.positions.iter().map(|p| (p.x_offset, p.y_offset)).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![(0, 0), (1189 - 1000, 0), (1189 - 1000, 472)]);
+        assert!(run.positions.iter().all(|p| p.attached_to.is_none()));
+    }
+
+    #[test]
+    fn mark_to_ligature() {
+        let gpos: &[u8] = &[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x0C, 0x00, 0x0E,
+            0x00, 0x00, // no scripts
+            0x00, 0x00, // no features
+            0x00, 0x01, 0x00, 0x04,
+            0x00, 0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08,
+            0x00, 0x01, 0x00, 0x0C, 0x00, 0x12, 0x00, 0x01, 0x00, 0x18, 0x00, 0x24,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x0B, // mark 11
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x1E, // ligature 30
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x06,
+            0x00, 0x01, 0x00, 0x64, 0x00, 0x00,
+            // three components
+            0x00, 0x01, 0x00, 0x04,
+            0x00, 0x03, 0x00, 0x08, 0x00, 0x0E, 0x00, 0x14,
+            0x00, 0x01, 0x00, 0xC8, 0x02, 0xBC,
+            0x00, 0x01, 0x02, 0x58, 0x02, 0xBC,
+            0x00, 0x01, 0x03, 0xE8, 0x02, 0xBC,
+        ];
+        let gdef: &[u8] = &[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // 11 is a mark, 30 is a ligature
+            0x00, 0x02, 0x00, 0x02, 0x00, 0x0B, 0x00, 0x0B, 0x00, 0x03, 0x00, 0x1E, 0x00, 0x1E, 0x00, 0x02,
+        ];
+        let gpos = Header::parse(gpos).expect("Unable to parse GPOS table");
+        let gdef = gdef::Header::parse(gdef).expect("Unable to parse GDEF table");
+
+        let mut run = GlyphBuffer::new();
+        for &glyph_id in &[30, 11, 11] {
+            run.push(glyph_id, 0);
+        }
+        run.positions[0].x_advance = 1200;
+
+        // The first mark was skipped over by the second component, and the
+        // other follows the ligature.
+        let lig_id = run.next_lig_id();
+        run.glyphs[0].lig_id = lig_id;
+        run.glyphs[0].lig_components = 3;
+        run.glyphs[1].lig_id = lig_id;
+        run.glyphs[1].lig_component = 2;
+
+        assert!(gpos.apply_lookup(0, &mut run, Some(&gdef)));
+        assert_eq!(run.positions[1].attached_to, Some(0));
+        assert_eq!(run.positions[2].attached_to, Some(0));
+
+        run.resolve_attachments();
+        let offsets = run.positions.iter().map(|p| (p.x_offset, p.y_offset)).collect::<Vec<_>>();
+        assert_eq!(offsets, vec![(0, 0), (500 - 1200, 700), (900 - 1200, 700)]);
+    }
+}
@@ -0,0 +1,900 @@
+use std::ops::Deref;
+use decode::{Error, Result, Table, ReadPrimitive};
+use decode::primitives::Tag;
+use buffer::{GlyphBuffer, GlyphInfo};
+use table::gdef;
+use table::context::{matches_context, Context, Rule, Sequence};
+use table::layout::{coverage_at, u16_at, Coverage, GlyphFilter, LayoutTable, Lookup};
+
+/// The glyph substitution table.
+pub struct Header<'tbl>(LayoutTable<'tbl>);
+
+impl<'tbl> Table<'tbl> for Header<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<Header<'tbl>> {
+        LayoutTable::parse(buffer).map(Header)
+    }
+}
+
+impl<'tbl> Deref for Header<'tbl> {
+    type Target = LayoutTable<'tbl>;
+
+    fn deref(&self) -> &LayoutTable<'tbl> {
+        &self.0
+    }
+}
+
+impl<'tbl> Header<'tbl> {
+    /// Applies the lookups of some features for a script and language to
+    /// a glyph run, in lookup order.  Alternate substitutions use the first
+    /// alternate.  Returns whether any glyph was substituted.
+    pub fn apply(&self, buffer: &mut GlyphBuffer, gdef: Option<&gdef::Header>,
+                 script: Tag, language: Option<Tag>, features: &[Tag]) -> bool {
+        let mut applied = false;
+        for index in self.lookup_indices(script, language, features) {
+            applied |= self.apply_lookup(index, buffer, gdef);
+        }
+        applied
+    }
+
+    /// Applies a lookup at each glyph of a run in turn, skipping the glyphs
+    /// the lookup flags ignore.  Reverse chaining lookups go from the last
+    /// glyph to the first.
+    pub fn apply_lookup(&self, index: u16, buffer: &mut GlyphBuffer, gdef: Option<&gdef::Header>) -> bool {
+        let lookup = match self.lookups.get(index) {
+            Some(lookup) => lookup,
+            None => return false,
+        };
+        let filter = GlyphFilter::new(&lookup, gdef);
+        let subtables = read_subtables(&lookup);
+        let mut nesting = Nesting::new(buffer.len());
+
+        let mut applied = false;
+        if subtables.iter().any(Subtable::is_reverse) {
+            for index in (0..buffer.len()).rev() {
+                if !filter.skips(buffer.glyphs[index].glyph_id) {
+                    nesting.edits.clear();
+                    applied |= self.apply_subtables(&subtables, buffer, index, &filter, gdef, &mut nesting).is_some();
+                }
+            }
+            return applied
+        }
+
+        let mut index = 0;
+        while index < buffer.len() {
+            let next = if filter.skips(buffer.glyphs[index].glyph_id) {
+                None
+            } else {
+                nesting.edits.clear();
+                self.apply_subtables(&subtables, buffer, index, &filter, gdef, &mut nesting)
+            };
+
+            match next {
+                Some(next) => {
+                    applied = true;
+                    index = next;
+                },
+                None => index += 1,
+            }
+        }
+        applied
+    }
+
+    /// Applies the first subtable that matches at a glyph, returning the
+    /// index of the glyph after those substituted.
+    fn apply_subtables(&self, subtables: &[Subtable], buffer: &mut GlyphBuffer, index: usize,
+                       filter: &GlyphFilter, gdef: Option<&gdef::Header>, nesting: &mut Nesting) -> Option<usize> {
+        subtables.iter()
+            .filter_map(|subtable| subtable.apply(self, buffer, index, filter, gdef, nesting))
+            .next()
+    }
+
+    /// Applies a lookup invoked by a contextual lookup, at one glyph.
+    fn apply_nested(&self, index: u16, buffer: &mut GlyphBuffer, position: usize,
+                    gdef: Option<&gdef::Header>, nesting: &mut Nesting) -> bool {
+        if nesting.depth >= MAX_NESTING || nesting.ops == 0 {
+            return false
+        }
+        nesting.ops -= 1;
+
+        let lookup = match self.lookups.get(index) {
+            Some(lookup) => lookup,
+            None => return false,
+        };
+        let filter = GlyphFilter::new(&lookup, gdef);
+        let subtables = read_subtables(&lookup);
+
+        nesting.depth += 1;
+        let applied = self.apply_subtables(&subtables, buffer, position, &filter, gdef, nesting).is_some();
+        nesting.depth -= 1;
+        applied
+    }
+
+    /// Applies the lookups of a matched rule at its input glyphs, returning
+    /// the index of the glyph after the input.  The positions of the input
+    /// glyphs follow the glyphs lookups insert and remove, and glyphs
+    /// inserted among the input become part of it.
+    fn apply_rule(&self, rule: &Rule, buffer: &mut GlyphBuffer, mut positions: Vec<usize>,
+                  gdef: Option<&gdef::Header>, nesting: &mut Nesting) -> usize {
+        let mut end = positions[positions.len() - 1] + 1;
+
+        for lookup in rule.lookups() {
+            let index = lookup.sequence_index as usize;
+            if index >= positions.len() {
+                continue
+            }
+
+            let edits = nesting.edits.len();
+            if !self.apply_nested(lookup.lookup_index, buffer, positions[index], gdef, nesting) {
+                continue
+            }
+
+            for &edit in &nesting.edits[edits..] {
+                match edit {
+                    Edit::Insert(at) => {
+                        for position in positions.iter_mut().filter(|p| **p >= at) {
+                            *position += 1;
+                        }
+                        if at <= end {
+                            end += 1;
+                            let i = positions.iter().position(|&p| p > at).unwrap_or(positions.len());
+                            positions.insert(i, at);
+                        }
+                    },
+                    Edit::Remove(at) => {
+                        positions.retain(|&p| p != at);
+                        for position in positions.iter_mut().filter(|p| **p > at) {
+                            *position -= 1;
+                        }
+                        if at < end {
+                            end -= 1;
+                        }
+                    },
+                }
+            }
+        }
+
+        end
+    }
+}
+
+/// The deepest nesting of lookups invoked by contextual lookups.
+const MAX_NESTING: u16 = 64;
+
+/// Nested lookups allowed for each glyph of a run, and at least
+/// `MAX_OPS_MIN` in all, so that rules invoking several lookups that
+/// invoke them in turn can't take exponential time.
+const MAX_OPS_FACTOR: usize = 64;
+const MAX_OPS_MIN: usize = 16384;
+
+/// A change in the length of a run, at an index.
+#[derive(Debug, Copy, Clone)]
+enum Edit {
+    Insert(usize),
+    Remove(usize),
+}
+
+/// Tracks the lookups invoked by contextual lookups while one lookup is
+/// applied to a run: how deeply they are nested, how many more may be
+/// applied, and the glyphs inserted and removed at the current glyph.
+struct Nesting {
+    depth: u16,
+    ops: usize,
+    edits: Vec<Edit>,
+}
+
+impl Nesting {
+    fn new(len: usize) -> Nesting {
+        Nesting {
+            depth: 0,
+            ops: max!(len.saturating_mul(MAX_OPS_FACTOR), MAX_OPS_MIN),
+            edits: Vec::new(),
+        }
+    }
+
+    fn insert(&mut self, buffer: &mut GlyphBuffer, index: usize, glyph: GlyphInfo) {
+        buffer.insert(index, glyph);
+        self.edits.push(Edit::Insert(index));
+    }
+
+    fn remove(&mut self, buffer: &mut GlyphBuffer, index: usize) {
+        buffer.remove(index);
+        self.edits.push(Edit::Remove(index));
+    }
+}
+
+fn read_subtables<'tbl>(lookup: &Lookup<'tbl>) -> Vec<Subtable<'tbl>> {
+    lookup.subtables()
+        .filter_map(|data| Subtable::parse(lookup.lookup_type, data).ok())
+        .collect()
+}
+
+/// A subtable of a GSUB lookup.
+pub enum Subtable<'tbl> {
+    Single(SingleSubst<'tbl>),
+    Multiple(MultipleSubst<'tbl>),
+    Alternate(AlternateSubst<'tbl>),
+    Ligature(LigatureSubst<'tbl>),
+    Context(Context<'tbl>),
+    ChainContext(Context<'tbl>),
+    ReverseChainSingle(ReverseChainSingleSubst<'tbl>),
+}
+
+impl<'tbl> Subtable<'tbl> {
+    /// Reads a subtable, following extension subtables to the subtable
+    /// they hold.
+    pub fn parse(lookup_type: u16, buffer: &'tbl [u8]) -> Result<Subtable<'tbl>> {
+        Ok(match lookup_type {
+            1 => Subtable::Single(SingleSubst::parse(buffer)?),
+            2 => Subtable::Multiple(MultipleSubst::parse(buffer)?),
+            3 => Subtable::Alternate(AlternateSubst::parse(buffer)?),
+            4 => Subtable::Ligature(LigatureSubst::parse(buffer)?),
+            5 => Subtable::Context(Context::parse(buffer, false)?),
+            6 => Subtable::ChainContext(Context::parse(buffer, true)?),
+            7 => {
+                let data = buffer;
+                let mut buffer = buffer;
+                required_len!(buffer, 8);
+
+                verify!(buffer.read::<u16>()? == 1);
+                let lookup_type = buffer.read::<u16>()?;
+                let offset = buffer.read::<u32>()? as usize;
+                verify!(lookup_type != 7);
+
+                return Subtable::parse(lookup_type, data.get(offset..).ok_or(Error::UnexpectedEof)?)
+            },
+            8 => Subtable::ReverseChainSingle(ReverseChainSingleSubst::parse(buffer)?),
+            _ => return Err(Error::InvalidData),
+        })
+    }
+
+    /// Whether the subtable is applied from the end of a run.
+    pub fn is_reverse(&self) -> bool {
+        matches!(*self, Subtable::ReverseChainSingle(_))
+    }
+
+    /// Applies the subtable at a glyph, returning the index of the glyph
+    /// after those substituted.
+    fn apply(&self, gsub: &Header, buffer: &mut GlyphBuffer, index: usize, filter: &GlyphFilter,
+             gdef: Option<&gdef::Header>, nesting: &mut Nesting) -> Option<usize> {
+        let glyph = buffer.glyphs[index];
+
+        match *self {
+            Subtable::Single(ref single) => {
+                buffer.glyphs[index].glyph_id = try_opt!(single.substitute(glyph.glyph_id));
+                Some(index + 1)
+            },
+            Subtable::Multiple(ref multiple) => {
+                let sequence = try_opt!(multiple.sequence(glyph.glyph_id));
+                let len = sequence.len();
+                if len == 0 {
+                    nesting.remove(buffer, index);
+                }
+
+                for (i, glyph_id) in sequence.enumerate() {
+                    if i == 0 {
+                        buffer.glyphs[index].glyph_id = glyph_id;
+                    } else {
+                        nesting.insert(buffer, index + i, GlyphInfo { glyph_id, ..glyph });
+                    }
+                }
+                Some(index + len)
+            },
+            Subtable::Alternate(ref alternate) => {
+                let mut alternates = try_opt!(alternate.alternates(glyph.glyph_id));
+                buffer.glyphs[index].glyph_id = try_opt!(alternates.next());
+                Some(index + 1)
+            },
+            Subtable::Ligature(ref ligature) => {
+                for ligature in try_opt!(ligature.ligatures(glyph.glyph_id)) {
+                    let mut positions = vec![index];
+                    for component in ligature.components {
+                        match filter.next(&buffer.glyphs, *positions.last().unwrap()) {
+                            Some(next) if buffer.glyphs[next].glyph_id == component => positions.push(next),
+                            _ => break,
+                        }
+                    }
+
+                    if positions.len() == ligature.components.len() + 1 {
+                        return Some(ligate(buffer, &positions, ligature.glyph_id, filter, nesting))
+                    }
+                }
+                None
+            },
+            Subtable::Context(ref context) | Subtable::ChainContext(ref context) => {
+                for rule in context.rules(glyph.glyph_id) {
+                    if let Some(positions) = rule.match_at(&buffer.glyphs, index, filter) {
+                        return Some(gsub.apply_rule(&rule, buffer, positions, gdef, nesting))
+                    }
+                }
+                None
+            },
+            Subtable::ReverseChainSingle(ref reverse) => {
+                // Reverse chaining lookups cannot be nested.
+                if nesting.depth > 0 {
+                    return None
+                }
+
+                let substitute = try_opt!(reverse.substitute(glyph.glyph_id));
+                if !matches_context(&buffer.glyphs, index, index, &reverse.backtrack, &reverse.lookahead, filter) {
+                    return None
+                }
+                buffer.glyphs[index].glyph_id = substitute;
+                Some(index + 1)
+            },
+        }
+    }
+}
+
+/// Replaces the glyphs at `positions` with a ligature, returning the index
+/// of the glyph after it.  Marks skipped between the components are
+/// assigned to the component before them, so they can later be attached
+/// to the right part of the ligature.
+fn ligate(buffer: &mut GlyphBuffer, positions: &[usize], glyph_id: u16, filter: &GlyphFilter,
+          nesting: &mut Nesting) -> usize {
+    let first = positions[0];
+    let last = positions[positions.len() - 1];
+
+    // Ligatures of marks are themselves marks, and take no part in
+    // mark attachment.
+    let is_mark_ligature = positions.iter().all(|&i| filter.glyph_class(buffer.glyphs[i].glyph_id) == 3);
+    let lig_id = if is_mark_ligature { 0 } else { buffer.next_lig_id() };
+    let components = positions.iter()
+        .fold(0u8, |total, &i| total.saturating_add(buffer.glyphs[i].lig_components));
+
+    buffer.merge_clusters(first, last + 1);
+
+    let mut last_lig_id = buffer.glyphs[first].lig_id;
+    let mut last_components = buffer.glyphs[first].lig_components;
+    let mut components_so_far = last_components;
+
+    {
+        let ligature = &mut buffer.glyphs[first];
+        ligature.glyph_id = glyph_id;
+        if !is_mark_ligature {
+            ligature.lig_id = lig_id;
+            ligature.lig_component = 0;
+            ligature.lig_components = components;
+        }
+    }
+
+    // Marks that belonged to a component that was a ligature keep their
+    // component within it.
+    let mark_component = |mark: &GlyphInfo, components_so_far: u8, last_components: u8| {
+        let component = if mark.lig_component == 0 { last_components } else { mark.lig_component };
+        components_so_far - last_components + min!(component, last_components)
+    };
+
+    for pair in positions.windows(2) {
+        if !is_mark_ligature {
+            for mark in &mut buffer.glyphs[pair[0] + 1..pair[1]] {
+                mark.lig_component = mark_component(mark, components_so_far, last_components);
+                mark.lig_id = lig_id;
+            }
+        }
+
+        let component = buffer.glyphs[pair[1]];
+        last_lig_id = component.lig_id;
+        last_components = component.lig_components;
+        components_so_far = components_so_far.saturating_add(last_components);
+    }
+
+    // Marks following the last component, when it was a ligature.
+    if !is_mark_ligature && last_lig_id != 0 {
+        for mark in &mut buffer.glyphs[last + 1..] {
+            if mark.lig_id != last_lig_id || mark.lig_component == 0 {
+                break
+            }
+            mark.lig_component = mark_component(mark, components_so_far, last_components);
+            mark.lig_id = lig_id;
+        }
+    }
+
+    for &i in positions[1..].iter().rev() {
+        nesting.remove(buffer, i);
+    }
+
+    last + 2 - positions.len()
+}
+
+/// Replaces single glyphs in context, going from the end of a run to its
+/// start so that each substitution can depend on those after it.
+pub struct ReverseChainSingleSubst<'tbl> {
+    pub coverage: Coverage<'tbl>,
+    /// The coverage of the glyphs before, in reverse order.
+    pub backtrack: Sequence<'tbl>,
+    pub lookahead: Sequence<'tbl>,
+    substitutes: GlyphArray<'tbl>,
+}
+
+impl<'tbl> Table<'tbl> for ReverseChainSingleSubst<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<ReverseChainSingleSubst<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 6);
+
+        verify!(buffer.read::<u16>()? == 1);
+        let coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let backtrack = Sequence::read_coverages(data, &mut buffer)?;
+        let lookahead = Sequence::read_coverages(data, &mut buffer)?;
+        let substitutes = GlyphArray::counted(buffer).ok_or(Error::UnexpectedEof)?;
+
+        Ok(ReverseChainSingleSubst { coverage, backtrack, lookahead, substitutes })
+    }
+}
+
+impl<'tbl> ReverseChainSingleSubst<'tbl> {
+    pub fn substitute(&self, glyph_id: u16) -> Option<u16> {
+        self.substitutes.get(try_opt!(self.coverage.coverage_index(glyph_id)) as usize)
+    }
+}
+
+/// An array of glyph ids.
+#[derive(Debug, Copy, Clone)]
+pub struct GlyphArray<'tbl>(&'tbl [u8]);
+
+impl<'tbl> GlyphArray<'tbl> {
+    /// Reads an array preceded by its length.
+    fn counted(buffer: &'tbl [u8]) -> Option<GlyphArray<'tbl>> {
+        let count = try_opt!(u16_at(buffer, 0)) as usize;
+        buffer.get(2..2 + 2 * count).map(GlyphArray)
+    }
+
+    pub fn get(&self, index: usize) -> Option<u16> {
+        u16_at(self.0, 2 * index)
+    }
+}
+
+impl<'tbl> Iterator for GlyphArray<'tbl> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        let glyph_id = try_opt!(u16_at(self.0, 0));
+        self.0 = &self.0[2..];
+        Some(glyph_id)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len() / 2, Some(self.0.len() / 2))
+    }
+}
+
+impl<'tbl> ExactSizeIterator for GlyphArray<'tbl> {}
+
+/// Replaces single glyphs.
+pub enum SingleSubst<'tbl> {
+    /// Adds a delta, modulo 65536, to the glyph id.
+    Format1 {
+        coverage: Coverage<'tbl>,
+        delta: u16,
+    },
+    /// Gives the substitute of each glyph, by coverage index.
+    Format2 {
+        coverage: Coverage<'tbl>,
+        substitutes: GlyphArray<'tbl>,
+    },
+}
+
+impl<'tbl> Table<'tbl> for SingleSubst<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<SingleSubst<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 6);
+
+        let format = buffer.read::<u16>()?;
+        let coverage = coverage_at(data, buffer.read::<u16>()?)?;
+
+        match format {
+            1 => Ok(SingleSubst::Format1 { coverage, delta: buffer.read::<u16>()? }),
+            2 => {
+                let substitutes = GlyphArray::counted(buffer).ok_or(Error::UnexpectedEof)?;
+                Ok(SingleSubst::Format2 { coverage, substitutes })
+            },
+            _ => Err(Error::InvalidData),
+        }
+    }
+}
+
+impl<'tbl> SingleSubst<'tbl> {
+    pub fn substitute(&self, glyph_id: u16) -> Option<u16> {
+        match *self {
+            SingleSubst::Format1 { ref coverage, delta } => {
+                coverage.coverage_index(glyph_id).map(|_| glyph_id.wrapping_add(delta))
+            },
+            SingleSubst::Format2 { ref coverage, ref substitutes } => {
+                substitutes.get(try_opt!(coverage.coverage_index(glyph_id)) as usize)
+            },
+        }
+    }
+}
+
+/// Coverage followed by offsets to a table for each covered glyph, as
+/// used by multiple, alternate and ligature substitution.
+#[derive(Debug, Copy, Clone)]
+struct CoveredTables<'tbl> {
+    data: &'tbl [u8],
+    coverage: Coverage<'tbl>,
+    count: u16,
+}
+
+impl<'tbl> Table<'tbl> for CoveredTables<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<CoveredTables<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 6);
+
+        verify!(buffer.read::<u16>()? == 1);
+        let coverage = coverage_at(data, buffer.read::<u16>()?)?;
+        let count = buffer.read::<u16>()?;
+        required_len!(buffer, 2 * count as usize);
+
+        Ok(CoveredTables { data, coverage, count })
+    }
+}
+
+impl<'tbl> CoveredTables<'tbl> {
+    fn get(&self, glyph_id: u16) -> Option<&'tbl [u8]> {
+        let index = try_opt!(self.coverage.coverage_index(glyph_id));
+        if index >= self.count {
+            return None
+        }
+
+        let offset = try_opt!(u16_at(self.data, 6 + 2 * index as usize)) as usize;
+        self.data.get(offset..)
+    }
+}
+
+/// Replaces single glyphs with sequences of glyphs.
+pub struct MultipleSubst<'tbl>(CoveredTables<'tbl>);
+
+impl<'tbl> Table<'tbl> for MultipleSubst<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<MultipleSubst<'tbl>> {
+        CoveredTables::parse(buffer).map(MultipleSubst)
+    }
+}
+
+impl<'tbl> MultipleSubst<'tbl> {
+    pub fn coverage(&self) -> Coverage<'tbl> {
+        self.0.coverage
+    }
+
+    pub fn sequence(&self, glyph_id: u16) -> Option<GlyphArray<'tbl>> {
+        self.0.get(glyph_id).and_then(GlyphArray::counted)
+    }
+}
+
+/// Offers alternates for single glyphs.
+pub struct AlternateSubst<'tbl>(CoveredTables<'tbl>);
+
+impl<'tbl> Table<'tbl> for AlternateSubst<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<AlternateSubst<'tbl>> {
+        CoveredTables::parse(buffer).map(AlternateSubst)
+    }
+}
+
+impl<'tbl> AlternateSubst<'tbl> {
+    pub fn coverage(&self) -> Coverage<'tbl> {
+        self.0.coverage
+    }
+
+    pub fn alternates(&self, glyph_id: u16) -> Option<GlyphArray<'tbl>> {
+        self.0.get(glyph_id).and_then(GlyphArray::counted)
+    }
+}
+
+/// Replaces sequences of glyphs with ligatures, by their first glyph.
+pub struct LigatureSubst<'tbl>(CoveredTables<'tbl>);
+
+impl<'tbl> Table<'tbl> for LigatureSubst<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<LigatureSubst<'tbl>> {
+        CoveredTables::parse(buffer).map(LigatureSubst)
+    }
+}
+
+impl<'tbl> LigatureSubst<'tbl> {
+    pub fn coverage(&self) -> Coverage<'tbl> {
+        self.0.coverage
+    }
+
+    /// The ligatures starting with a glyph, in order of preference.
+    pub fn ligatures(&self, glyph_id: u16) -> Option<LigatureIter<'tbl>> {
+        let set = try_opt!(self.0.get(glyph_id));
+        let count = try_opt!(u16_at(set, 0));
+        Some(LigatureIter { set, index: 0, count })
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Ligature<'tbl> {
+    pub glyph_id: u16,
+    /// The components after the first.
+    pub components: GlyphArray<'tbl>,
+}
+
+pub struct LigatureIter<'tbl> {
+    set: &'tbl [u8],
+    index: u16,
+    count: u16,
+}
+
+impl<'tbl> Iterator for LigatureIter<'tbl> {
+    type Item = Ligature<'tbl>;
+
+    fn next(&mut self) -> Option<Ligature<'tbl>> {
+        while self.index < self.count {
+            self.index += 1;
+
+            let offset = try_opt!(u16_at(self.set, 2 * self.index as usize)) as usize;
+            let ligature = try_opt!(self.set.get(offset..));
+            let glyph_id = try_opt!(u16_at(ligature, 0));
+            let count = try_opt!(u16_at(ligature, 2)) as usize;
+
+            if count > 0 {
+                if let Some(components) = ligature.get(4..2 + 2 * count) {
+                    return Some(Ligature { glyph_id, components: GlyphArray(components) })
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use decode::Table;
+    use decode::primitives::Tag;
+    use buffer::GlyphBuffer;
+    use table::gdef;
+    use table::gsub::Header;
+
+    fn glyph_ids(buffer: &GlyphBuffer) -> Vec<u16> {
+        buffer.glyphs.iter().map(|g| g.glyph_id).collect()
+    }
+
+    #[test]
+    fn substitute_opensans() {
+        let buf = open_font!("data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        let mut buffer = font.get_glyph_buffer("ffifl").expect("Unable to map text");
+        assert_eq!(glyph_ids(&buffer), vec![73, 73, 76, 73, 79]);
+        assert!(font.apply_substitutions(&mut buffer, Tag(*b"latn"), None, &[Tag(*b"liga")]));
+        assert_eq!(glyph_ids(&buffer), vec![605, 565]);
+        assert_eq!(buffer.glyphs.iter().map(|g| g.cluster).collect::<Vec<_>>(), vec![0, 3]);
+        assert_eq!(buffer.glyphs.iter().map(|g| g.lig_components).collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!(buffer.positions.len(), 2);
+        assert_eq!(buffer.positions[0].x_advance, font.get_glyph_metrics(605).unwrap().advance_width);
+
+        let mut buffer = font.get_glyph_buffer("10").expect("Unable to map text");
+        assert!(font.apply_substitutions(&mut buffer, Tag(*b"latn"), None, &[Tag(*b"onum")]));
+        assert_eq!(glyph_ids(&buffer), vec![900, 899]);
+        assert!(!font.apply_substitutions(&mut buffer, Tag(*b"latn"), None, &[Tag(*b"liga")]));
+    }
+
+    #[test]
+    fn multiple_notosans() {
+        let buf = open_font!("data/NotoSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gsub = font.get_table::<Header>().expect("Unable to read GSUB table");
+
+        let mut buffer = GlyphBuffer::new();
+        buffer.push(2673, 0);
+        buffer.push(36, 1);
+        assert!(gsub.apply_lookup(18, &mut buffer, None));
+        assert_eq!(glyph_ids(&buffer), vec![2603, 2461, 36]);
+        assert_eq!(buffer.glyphs.iter().map(|g| g.cluster).collect::<Vec<_>>(), vec![0, 0, 1]);
+        assert_eq!(buffer.positions.len(), 3);
+    }
+
+    #[test]
+    fn lookup_flags() {
+        let gsub: &[u8] = &[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x0C, 0x00, 0x0E,
+            0x00, 0x00, // no scripts
+            0x00, 0x00, // no features
+            0x00, 0x04, 0x00, 0x0A, 0x00, 0x12, 0x00, 0x1A, 0x00, 0x22,
+            0x00, 0x04, 0x00, 0x08, 0x00, 0x01, 0x00, 0x20, // ignore marks
+            0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x18,
+            0x00, 0x04, 0x01, 0x00, 0x00, 0x01, 0x00, 0x10, // mark attachment type 1
+            0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x00, 0x22,
+            // 20 21 22 -> 30
+            0x00, 0x01, 0x00, 0x08, 0x00, 0x01, 0x00, 0x0E,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x14,
+            0x00, 0x01, 0x00, 0x04, 0x00, 0x1E, 0x00, 0x03, 0x00, 0x15, 0x00, 0x16,
+            // 40 -> 41 or 42
+            0x00, 0x01, 0x00, 0x08, 0x00, 0x01, 0x00, 0x0E,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x28,
+            0x00, 0x02, 0x00, 0x29, 0x00, 0x2A,
+        ];
+        let gdef: &[u8] = &[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x18,
+            // 10 is a base glyph, 11 and 12 are marks
+            0x00, 0x01, 0x00, 0x0A, 0x00, 0x03, 0x00, 0x01, 0x00, 0x03, 0x00, 0x03,
+            0x00, 0x01, 0x00, 0x0B, 0x00, 0x02, 0x00, 0x01, 0x00, 0x02,
+        ];
+        let gsub = Header::parse(gsub).expect("Unable to parse GSUB table");
+        let gdef = gdef::Header::parse(gdef).expect("Unable to parse GDEF table");
+
+        let mut buffer = GlyphBuffer::new();
+        for (cluster, &glyph_id) in [20, 11, 21, 12, 22, 11].iter().enumerate() {
+            buffer.push(glyph_id, cluster as u32);
+        }
+
+        // The marks are only skipped when the lookup ignores them.
+        assert!(!gsub.apply_lookup(1, &mut buffer, Some(&gdef)));
+        assert!(!gsub.apply_lookup(0, &mut buffer, None));
+        assert!(!gsub.apply_lookup(2, &mut buffer, Some(&gdef)));
+        assert_eq!(buffer.len(), 6);
+
+        assert!(gsub.apply_lookup(0, &mut buffer, Some(&gdef)));
+        assert_eq!(glyph_ids(&buffer), vec![30, 11, 12, 11]);
+        assert_eq!(buffer.glyphs.iter().map(|g| g.cluster).collect::<Vec<_>>(), vec![0, 0, 0, 5]);
+
+        // The skipped marks belong to the components before them.
+        let lig_props = buffer.glyphs.iter()
+            .map(|g| (g.lig_id, g.lig_component, g.lig_components))
+            .collect::<Vec<_>>();
+        assert_eq!(lig_props, vec![(1, 0, 3), (1, 1, 1), (1, 2, 1), (0, 0, 1)]);
+
+        let mut buffer = GlyphBuffer::new();
+        buffer.push(40, 0);
+        assert!(gsub.apply_lookup(3, &mut buffer, Some(&gdef)));
+        assert_eq!(glyph_ids(&buffer), vec![41]);
+    }
+
+    #[test]
+    fn context_notosans() {
+        let buf = open_font!("data/NotoSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gsub = font.get_table::<Header>().expect("Unable to read GSUB table");
+
+        // A context applying a ligature lookup at its second glyph.
+        let mut buffer = GlyphBuffer::new();
+        for (cluster, &glyph_id) in [2521, 2488, 2461, 36].iter().enumerate() {
+            buffer.push(glyph_id, cluster as u32);
+        }
+        assert!(gsub.apply_lookup(10, &mut buffer, None));
+        assert_eq!(glyph_ids(&buffer), vec![2521, 2576, 36]);
+        assert_eq!(buffer.glyphs.iter().map(|g| g.cluster).collect::<Vec<_>>(), vec![0, 1, 3]);
+
+        let mut buffer = GlyphBuffer::new();
+        buffer.push(2488, 0);
+        buffer.push(2461, 1);
+        assert!(!gsub.apply_lookup(10, &mut buffer, None));
+    }
+
+    #[test]
+    fn chain_context_roboto() {
+        let buf = open_font!("data/Roboto-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gsub = font.get_table::<Header>().expect("Unable to read GSUB table");
+
+        // i becomes dotless before a mark above.
+        let mut buffer = GlyphBuffer::new();
+        for &glyph_id in &[77, 168, 77, 36] {
+            buffer.push(glyph_id, 0);
+        }
+        assert!(gsub.apply_lookup(2, &mut buffer, None));
+        assert_eq!(glyph_ids(&buffer), vec![609, 168, 77, 36]);
+
+        // Digits after a fraction slash or a denominator become denominators.
+        let mut buffer = GlyphBuffer::new();
+        for &glyph_id in &[20, 404, 21, 22] {
+            buffer.push(glyph_id, 0);
+        }
+        assert!(gsub.apply_lookup(23, &mut buffer, None));
+        assert_eq!(glyph_ids(&buffer), vec![20, 404, 454, 453]);
+    }
+
+    #[test]
+    fn nested_lookups() {
+        let gsub: &[u8] = &[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x0C, 0x00, 0x0E,
+            0x00, 0x00, // no scripts
+            0x00, 0x00, // no features
+            0x00, 0x04, 0x00, 0x0A, 0x00, 0x12, 0x00, 0x1A, 0x00, 0x22,
+            0x00, 0x07, 0x00, 0x00, 0x00, 0x01, 0x00, 0x20,
+            0x00, 0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x2C,
+            0x00, 0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x3E,
+            0x00, 0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x68,
+            // Extension of a single substitution, 10 -> 11
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x08,
+            0x00, 0x01, 0x00, 0x06, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x0A,
+            // Reverse chaining, 20 -> 21 before 21
+            0x00, 0x01, 0x00, 0x0E, 0x00, 0x00, 0x00, 0x01, 0x00, 0x14, 0x00, 0x01, 0x00, 0x15,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x14,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x15,
+            // Classes, applying lookup 0 to 10 after 30
+            0x00, 0x02, 0x00, 0x0E, 0x00, 0x14, 0x00, 0x03, 0x00, 0x00, 0x00, 0x24, 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x1E,
+            0x00, 0x02, 0x00, 0x02, 0x00, 0x0A, 0x00, 0x0A, 0x00, 0x02, 0x00, 0x1E, 0x00, 0x1E, 0x00, 0x01,
+            0x00, 0x01, 0x00, 0x04,
+            0x00, 0x02, 0x00, 0x01, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00,
+            // Coverage, applying itself to 40
+            0x00, 0x03, 0x00, 0x01, 0x00, 0x01, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x03,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x28,
+        ];
+        let gsub = Header::parse(gsub).expect("Unable to parse GSUB table");
+        let run = |lookup: u16, glyphs: &[u16]| {
+            let mut buffer = GlyphBuffer::new();
+            for &glyph_id in glyphs {
+                buffer.push(glyph_id, 0);
+            }
+            gsub.apply_lookup(lookup, &mut buffer, None);
+            glyph_ids(&buffer)
+        };
+
+        assert_eq!(run(0, &[10, 12]), vec![11, 12]);
+        assert_eq!(run(1, &[20, 20, 20, 21, 20]), vec![21, 21, 21, 21, 20]);
+        assert_eq!(run(2, &[30, 10, 10, 30, 12]), vec![30, 11, 10, 30, 12]);
+        assert_eq!(run(3, &[40]), vec![40]);
+    }
+
+    #[test]
+    fn nested_edits() {
+        let gsub: &[u8] = &[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x0C, 0x00, 0x0E,
+            0x00, 0x00, // no scripts
+            0x00, 0x00, // no features
+            0x00, 0x06, 0x00, 0x0E, 0x00, 0x16, 0x00, 0x1E, 0x00, 0x26, 0x00, 0x2E, 0x00, 0x36,
+            0x00, 0x05, 0x00, 0x08, 0x00, 0x01, 0x00, 0x30, // ignore marks
+            0x00, 0x05, 0x00, 0x08, 0x00, 0x01, 0x00, 0x4E, // ignore marks
+            0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x6C,
+            0x00, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x74,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x84,
+            0x00, 0x05, 0x00, 0x00, 0x00, 0x01, 0x00, 0x8A,
+            // 20 21 22, applying lookup 2 then lookup 4 to the first glyph
+            0x00, 0x03, 0x00, 0x03, 0x00, 0x02, 0x00, 0x14, 0x00, 0x1A, 0x00, 0x20,
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x04,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x14,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x15,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x16,
+            // 20 21 22, applying lookup 3 to the first glyph and lookup 4 to the second
+            0x00, 0x03, 0x00, 0x03, 0x00, 0x02, 0x00, 0x14, 0x00, 0x1A, 0x00, 0x20,
+            0x00, 0x00, 0x00, 0x03, 0x00, 0x01, 0x00, 0x04,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x14,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x15,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x16,
+            // 20 -> nothing
+            0x00, 0x01, 0x00, 0x0A, 0x00, 0x01, 0x00, 0x08, 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x14,
+            // 20 11 -> 40
+            0x00, 0x01, 0x00, 0x12, 0x00, 0x01, 0x00, 0x08, 0x00, 0x01, 0x00, 0x04,
+            0x00, 0x28, 0x00, 0x02, 0x00, 0x0B,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x14,
+            // 21 -> 31
+            0x00, 0x02, 0x00, 0x08, 0x00, 0x01, 0x00, 0x1F,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x15,
+            // 50, applying itself twice
+            0x00, 0x03, 0x00, 0x01, 0x00, 0x02, 0x00, 0x10,
+            0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x05,
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x32,
+        ];
+        let gdef: &[u8] = &[
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            // 11 is a mark
+            0x00, 0x01, 0x00, 0x0B, 0x00, 0x01, 0x00, 0x03,
+        ];
+        let gsub = Header::parse(gsub).expect("Unable to parse GSUB table");
+        let gdef = gdef::Header::parse(gdef).expect("Unable to parse GDEF table");
+        let run = |lookup: u16, glyphs: &[u16]| {
+            let mut buffer = GlyphBuffer::new();
+            for &glyph_id in glyphs {
+                buffer.push(glyph_id, 0);
+            }
+            assert!(gsub.apply_lookup(lookup, &mut buffer, Some(&gdef)));
+            glyph_ids(&buffer)
+        };
+
+        // Once the first glyph is removed, the second glyph is first, and
+        // not the mark now at its position.
+        assert_eq!(run(0, &[20, 11, 21, 22]), vec![11, 31, 22]);
+
+        // The ligature takes the mark, which is not part of the input.
+        assert_eq!(run(1, &[20, 11, 21, 22]), vec![40, 31, 22]);
+
+        // Lookups invoking themselves twice stop once out of operations.
+        assert_eq!(run(5, &[50]), vec![50]);
+    }
+}
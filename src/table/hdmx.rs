@@ -0,0 +1,113 @@
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+
+/// Horizontal device metrics.  Holds the hinted advance widths of every
+/// glyph for a number of pixel sizes.
+#[derive(Debug)]
+pub struct Hdmx<'tbl> {
+    records: &'tbl [u8],
+    num_glyphs: u16,
+    pub version: u16,
+    pub num_records: u16,
+    pub size_device_record: u32,
+}
+
+impl<'tbl> Hdmx<'tbl> {
+    /// The number of glyphs are found in the `maxp` table.
+    pub fn parse(mut buffer: &'tbl [u8], num_glyphs: u16) -> Result<Hdmx<'tbl>> {
+        required_len!(buffer, 8);
+
+        let version = buffer.read::<u16>()?;
+        let num_records = buffer.read::<i16>()?;
+        let size_device_record = buffer.read::<i32>()?;
+
+        verify!(num_records >= 0 && size_device_record >= 0);
+        verify!(size_device_record as usize >= num_glyphs as usize + 2);
+
+        let size = num_records as usize * size_device_record as usize;
+        required_len!(buffer, size);
+
+        Ok(Hdmx {
+            records: &buffer[..size],
+            num_glyphs,
+            version,
+            num_records: num_records as u16,
+            size_device_record: size_device_record as u32,
+        })
+    }
+
+    pub fn records(&self) -> DeviceRecordIter<'tbl> {
+        DeviceRecordIter {
+            buffer: self.records,
+            record_size: self.size_device_record as usize,
+            num_glyphs: self.num_glyphs as usize,
+        }
+    }
+
+    pub fn get_record(&self, ppem: u8) -> Option<DeviceRecord<'tbl>> {
+        self.records().find(|rec| rec.pixel_size == ppem)
+    }
+
+    /// The hinted advance width of a glyph in pixels.
+    pub fn get_advance(&self, glyph_id: u16, ppem: u8) -> Option<u8> {
+        self.get_record(ppem).and_then(|rec| rec.get_advance(glyph_id))
+    }
+}
+
+pub struct DeviceRecordIter<'a> {
+    buffer: &'a [u8],
+    record_size: usize,
+    num_glyphs: usize,
+}
+
+impl<'a> Iterator for DeviceRecordIter<'a> {
+    type Item = DeviceRecord<'a>;
+
+    fn next(&mut self) -> Option<DeviceRecord<'a>> {
+        if self.buffer.len() < self.record_size || self.record_size == 0 {
+            return None
+        }
+
+        let (record, rest) = self.buffer.split_at(self.record_size);
+        self.buffer = rest;
+
+        Some(DeviceRecord {
+            pixel_size: record[0],
+            max_width: record[1],
+            widths: &record[2..2 + self.num_glyphs],
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct DeviceRecord<'tbl> {
+    pub pixel_size: u8,
+    pub max_width: u8,
+    widths: &'tbl [u8],
+}
+
+impl<'tbl> DeviceRecord<'tbl> {
+    pub fn get_advance(&self, glyph_id: u16) -> Option<u8> {
+        self.widths.get(glyph_id as usize).cloned()
+    }
+}
+
+#[test]
+fn device_metrics() {
+    use font::Font;
+
+    let buf = open_font!("data/Roboto-Regular.ttf");
+    let font = Font::from_buffer(&buf).expect("Unable to parse font");
+    let hdmx = font.get_table_hdmx().expect("unable to read hdmx table");
+
+    assert_eq!(hdmx.num_records, 1);
+    assert_eq!(hdmx.size_device_record, 1296);
+
+    let rec = hdmx.get_record(9).expect("missing hdmx record for 9 ppem");
+    assert_eq!(rec.max_width, 10);
+
+    assert_eq!(hdmx.get_advance(4, 9), Some(2));  // ' '
+    assert_eq!(hdmx.get_advance(37, 9), Some(6)); // 'A'
+    assert_eq!(hdmx.get_advance(75, 9), Some(5)); // 'g'
+    assert_eq!(hdmx.get_advance(37, 10), None);
+    assert_eq!(hdmx.get_advance(1294, 9), None);
+}
@@ -18,6 +18,42 @@ impl<'tbl> Table<'tbl> for Head<'tbl> {
     }
 }
 
+bitflags! {
+    pub struct HeadFlags: u16 {
+        const BASELINE_AT_ZERO            = 1 << 0;
+        const LSB_AT_ZERO                 = 1 << 1;
+        const INSTRUCTIONS_DEPEND_ON_SIZE = 1 << 2;
+        const FORCE_INTEGER_PPEM          = 1 << 3;
+        const INSTRUCTIONS_ALTER_ADVANCE  = 1 << 4;
+        const LOSSLESS                    = 1 << 11;
+        const CONVERTED                   = 1 << 12;
+        const CLEARTYPE_OPTIMIZED         = 1 << 13;
+        const LAST_RESORT                 = 1 << 14;
+    }
+}
+
+bitflags! {
+    pub struct MacStyle: u16 {
+        const BOLD      = 1 << 0;
+        const ITALIC    = 1 << 1;
+        const UNDERLINE = 1 << 2;
+        const OUTLINE   = 1 << 3;
+        const SHADOW    = 1 << 4;
+        const CONDENSED = 1 << 5;
+        const EXTENDED  = 1 << 6;
+    }
+}
+
+impl<'tbl> Head<'tbl> {
+    pub fn get_flags(&self) -> HeadFlags {
+        HeadFlags::from_bits_truncate(self.flags())
+    }
+
+    pub fn get_mac_style(&self) -> MacStyle {
+        MacStyle::from_bits_truncate(self.mac_style())
+    }
+}
+
 impl_offset_table!(Head,
     major_version: u16,
     minor_version: u16,
@@ -67,4 +103,12 @@ fn head() {
         index_to_loc_format: 0,
         glyph_data_format: 0,
     );
+
+    assert_eq!(tbl.get_flags(), BASELINE_AT_ZERO | FORCE_INTEGER_PPEM);
+    assert!(tbl.get_mac_style().is_empty());
+
+    let buf = open_font!("data/DroidSerif-BoldItalic.ttf");
+    let font = Font::from_buffer(&buf).expect("Unable to parse font");
+    let tbl = font.get_table::<Head>().expect("unable to read Head table");
+    assert_eq!(tbl.get_mac_style(), BOLD | ITALIC);
 }
\ No newline at end of file
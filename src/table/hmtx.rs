@@ -29,7 +29,7 @@ impl<'tbl> Hmtx<'tbl> {
         Ok(Hmtx { h_metrics, left_side_bearing, default_advance })
     }
 
-    fn get_record(&self, glyph_id: u16) -> Option<HorizontalMetricRecord> {
+    pub fn get_record(&self, glyph_id: u16) -> Option<HorizontalMetricRecord> {
         let glyph_id = glyph_id as usize;
 
         if 4 * glyph_id < self.h_metrics.len() {
@@ -50,11 +50,11 @@ impl<'tbl> Hmtx<'tbl> {
         }
     }
 
-    fn get_advance(&self, glyph_id: u16) -> Option<u16> {
+    pub fn get_advance(&self, glyph_id: u16) -> Option<u16> {
         self.get_record(glyph_id).map(|r| r.advance_width)
     }
 
-    fn get_lsb(&self, glyph_id: u16) -> Option<i16> {
+    pub fn get_lsb(&self, glyph_id: u16) -> Option<i16> {
         self.get_record(glyph_id).map(|r| r.lsb)
     }
 }
@@ -0,0 +1,338 @@
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+use buffer::GlyphBuffer;
+
+/// The legacy kerning table, in either the OpenType or Apple flavours.
+#[derive(Debug)]
+pub struct Kern<'tbl> {
+    subtables: &'tbl [u8],
+    pub apple: bool,
+    pub num_tables: u32,
+}
+
+impl<'tbl> Table<'tbl> for Kern<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Kern<'tbl>> {
+        required_len!(buffer, 4);
+
+        let version = buffer.read::<u16>()?;
+        let (apple, num_tables) = match version {
+            0 => (false, buffer.read::<u16>()? as u32),
+            1 => {
+                let _ /* minor */ = buffer.read::<u16>()?;
+                (true, buffer.read::<u32>()?)
+            },
+            _ => return Err(Error::InvalidData),
+        };
+
+        Ok(Kern { subtables: buffer, apple, num_tables })
+    }
+}
+
+impl<'tbl> Kern<'tbl> {
+    pub fn subtables(&self) -> SubtableIter<'tbl> {
+        SubtableIter {
+            buffer: self.subtables,
+            apple: self.apple,
+            remaining: self.num_tables,
+        }
+    }
+
+    /// The horizontal kerning adjustment between two glyphs, accumulated
+    /// over every subtable which applies to horizontal text.
+    pub fn kerning(&self, left: u16, right: u16) -> i16 {
+        let mut value = 0i16;
+
+        for subtable in self.subtables() {
+            if !subtable.is_horizontal_kerning() {
+                continue
+            }
+
+            if let Some(v) = subtable.kerning(left, right) {
+                if subtable.coverage.contains(OVERRIDE) {
+                    value = v;
+                } else {
+                    value = value.saturating_add(v);
+                }
+            }
+        }
+
+        value
+    }
+
+    /// Adjusts the advance of each glyph by its kerning with the next, and
+    /// returns whether any pair was kerned.
+    pub fn apply(&self, buffer: &mut GlyphBuffer) -> bool {
+        let mut applied = false;
+        for i in 1..buffer.len() {
+            let left = buffer.glyphs[i - 1].glyph_id;
+            let right = buffer.glyphs[i].glyph_id;
+            let kerning = self.kerning(left, right);
+            buffer.positions[i - 1].x_advance += kerning as i32;
+            applied |= kerning != 0;
+        }
+        applied
+    }
+}
+
+pub struct SubtableIter<'a> {
+    buffer: &'a [u8],
+    apple: bool,
+    remaining: u32,
+}
+
+impl<'a> Iterator for SubtableIter<'a> {
+    type Item = Subtable<'a>;
+
+    fn next(&mut self) -> Option<Subtable<'a>> {
+        if self.remaining == 0 {
+            return None
+        }
+
+        self.remaining -= 1;
+        let mut buf = self.buffer;
+        let (length, coverage, format, header_size) = if self.apple {
+            let length = try_opt!(buf.read::<u32>().ok()) as usize;
+            let coverage = try_opt!(buf.read::<u16>().ok());
+            let _ /* tuple_index */ = try_opt!(buf.read::<u16>().ok());
+
+            let mut flags = KernCoverage::empty();
+            flags.set(HORIZONTAL, coverage & 0x8000 == 0);
+            flags.set(CROSS_STREAM, coverage & 0x4000 != 0);
+            flags.set(VARIATION, coverage & 0x2000 != 0);
+
+            (length, flags, (coverage & 0xFF) as u8, 8)
+        } else {
+            let _ /* version */ = try_opt!(buf.read::<u16>().ok());
+            let length = try_opt!(buf.read::<u16>().ok()) as usize;
+            let coverage = try_opt!(buf.read::<u16>().ok());
+            let flags = KernCoverage::from_bits_truncate(coverage & 0x0F);
+
+            (length, flags, (coverage >> 8) as u8, 6)
+        };
+
+        // The 16 bit length of large format 0 subtables frequently
+        // overflows, so the final subtable extends to the end of the table.
+        let data = if self.remaining == 0 || length > self.buffer.len() {
+            self.remaining = 0;
+            self.buffer
+        } else {
+            let (data, rest) = self.buffer.split_at(length);
+            self.buffer = rest;
+            data
+        };
+
+        Some(Subtable { data, header_size, coverage, format })
+    }
+}
+
+bitflags! {
+    pub struct KernCoverage: u16 {
+        const HORIZONTAL   = 1 << 0;
+        const MINIMUM      = 1 << 1;
+        const CROSS_STREAM = 1 << 2;
+        const OVERRIDE     = 1 << 3;
+        const VARIATION    = 1 << 4;
+    }
+}
+
+#[derive(Debug)]
+pub struct Subtable<'tbl> {
+    /// The subtable data, including the header.
+    data: &'tbl [u8],
+    header_size: usize,
+    pub coverage: KernCoverage,
+    pub format: u8,
+}
+
+impl<'tbl> Subtable<'tbl> {
+    /// Whether this subtable holds kerning values for horizontal text,
+    /// as opposed to minimum values, cross-stream or variation adjustments.
+    pub fn is_horizontal_kerning(&self) -> bool {
+        self.coverage.contains(HORIZONTAL)
+            && !self.coverage.intersects(MINIMUM | CROSS_STREAM | VARIATION)
+    }
+
+    pub fn kerning(&self, left: u16, right: u16) -> Option<i16> {
+        let body = try_opt!(self.data.get(self.header_size..));
+
+        match self.format {
+            0 => Format0::parse(body).ok().and_then(|f| f.kerning(left, right)),
+            2 => Format2::parse(body).ok().and_then(|f| f.kerning(self.data, left, right)),
+            _ => None,
+        }
+    }
+}
+
+/// Ordered list of kerning pairs.
+struct Format0<'tbl> {
+    pairs: &'tbl [u8],
+}
+
+impl<'tbl> Table<'tbl> for Format0<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Format0<'tbl>> {
+        required_len!(buffer, 8);
+
+        let num_pairs = buffer.read::<u16>()? as usize;
+        let (_, buffer) = buffer.split_at(6); // searchRange, entrySelector, rangeShift
+
+        let size = min!(num_pairs * KerningPair::size(), buffer.len());
+        let (pairs, _) = buffer.split_at(size - size % KerningPair::size());
+
+        Ok(Format0 { pairs })
+    }
+}
+
+impl<'tbl> Format0<'tbl> {
+    fn kerning(&self, left: u16, right: u16) -> Option<i16> {
+        let key = (left as u32) << 16 | right as u32;
+        let (mut lo, mut hi) = (0, self.pairs.len() / KerningPair::size());
+
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let mut buf = &self.pairs[mid * KerningPair::size()..];
+            let pair = try_opt!(buf.read_table::<KerningPair>().ok());
+            let pair_key = (pair.left as u32) << 16 | pair.right as u32;
+
+            if pair_key < key {
+                lo = mid + 1;
+            } else if pair_key > key {
+                hi = mid;
+            } else {
+                return Some(pair.value)
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Table)]
+pub struct KerningPair {
+    pub left: u16,
+    pub right: u16,
+    pub value: i16,
+}
+
+/// Two dimensional array of kerning values indexed by glyph classes.
+struct Format2 {
+    row_width: u16,
+    left_class_offset: usize,
+    right_class_offset: usize,
+    array_offset: usize,
+}
+
+impl<'tbl> Table<'tbl> for Format2 {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Format2> {
+        required_len!(buffer, 8);
+
+        let row_width = buffer.read::<u16>()?;
+        let left_class_offset = buffer.read::<u16>()? as usize;
+        let right_class_offset = buffer.read::<u16>()? as usize;
+        let array_offset = buffer.read::<u16>()? as usize;
+
+        Ok(Format2 { row_width, left_class_offset, right_class_offset, array_offset })
+    }
+}
+
+impl Format2 {
+    /// Offsets are relative to the start of the subtable, including its header.
+    fn kerning(&self, subtable: &[u8], left: u16, right: u16) -> Option<i16> {
+        let left = try_opt!(class_value(subtable, self.left_class_offset, left)) as usize;
+        let right = try_opt!(class_value(subtable, self.right_class_offset, right)) as usize;
+
+        // Left classes are pre-multiplied by the row width and include the
+        // offset to the array, while right classes are pre-multiplied by 2.
+        let offset = left + right;
+        if offset < self.array_offset {
+            return None
+        }
+
+        let mut buf = try_opt!(subtable.get(offset..));
+        buf.read::<i16>().ok()
+    }
+}
+
+fn class_value(subtable: &[u8], offset: usize, glyph_id: u16) -> Option<u16> {
+    let mut buf = try_opt!(subtable.get(offset..));
+    let first_glyph = try_opt!(buf.read::<u16>().ok());
+    let num_glyphs = try_opt!(buf.read::<u16>().ok());
+
+    let index = try_opt!(glyph_id.checked_sub(first_glyph));
+    if index >= num_glyphs {
+        return None
+    }
+
+    let mut buf = try_opt!(buf.get(2 * index as usize..));
+    buf.read::<u16>().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use table::kern::Kern;
+    use decode::Table;
+
+    #[test]
+    fn kerning_opensans() {
+        let buf = open_font!(r"data/OpenSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let kern = font.get_table::<Kern>().expect("Unable to read kern table");
+
+        assert!(!kern.apple);
+        assert_eq!(kern.num_tables, 1);
+        assert_eq!(kern.subtables().next().map(|s| s.format), Some(0));
+
+        assert_eq!(kern.kerning(36, 57), -82);   // 'A' 'V'
+        assert_eq!(kern.kerning(36, 55), -143);  // 'A' 'T'
+        assert_eq!(kern.kerning(55, 92), -41);   // 'T' 'y'
+        assert_eq!(kern.kerning(5, 36), -143);   // first pair
+        assert_eq!(kern.kerning(912, 523), 41);  // last pair
+        assert_eq!(kern.kerning(36, 36), 0);     // 'A' 'A'
+    }
+
+    #[test]
+    fn kerning_format2() {
+        let buf: &[u8] = &[
+            0x00, 0x01, 0x00, 0x00, // version 1.0 (Apple)
+            0x00, 0x00, 0x00, 0x01, // 1 subtable
+
+            0x00, 0x00, 0x00, 0x28, // length
+            0x00, 0x02,             // horizontal, format 2
+            0x00, 0x00,             // tuple index
+            0x00, 0x04,             // row width: 2 classes
+            0x00, 0x10,             // left class table
+            0x00, 0x18,             // right class table
+            0x00, 0x20,             // kerning array
+
+            // left class table: glyphs 10..11
+            0x00, 0x0A, 0x00, 0x02, 0x00, 0x20, 0x00, 0x24,
+
+            // right class table: glyphs 20..21
+            0x00, 0x14, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02,
+
+            // kerning array
+            0x00, 0x00, 0xFF, 0xCE, // row 0: 0, -50
+            0x00, 0x19, 0x00, 0x00, // row 1: 25, 0
+        ];
+
+        let kern = Kern::parse(buf).expect("Unable to parse kern table");
+
+        assert!(kern.apple);
+        assert_eq!(kern.kerning(10, 20), 0);
+        assert_eq!(kern.kerning(10, 21), -50);
+        assert_eq!(kern.kerning(11, 20), 25);
+        assert_eq!(kern.kerning(12, 20), 0);
+    }
+
+    #[test]
+    fn subtable_shorter_than_header() {
+        let buf: &[u8] = &[
+            0x00, 0x00, 0x00, 0x02, // version 0, 2 subtables
+            0x00, 0x00, 0x00, 0x04, 0x00, 0x01, // length 4, horizontal, format 0
+            0x00, 0x00, 0x00, 0x02, 0x00, 0x01,
+        ];
+
+        let kern = Kern::parse(buf).expect("Unable to parse kern table");
+        assert!(kern.subtables().all(|s| s.kerning(10, 20).is_none()));
+        assert_eq!(kern.kerning(10, 20), 0);
+    }
+}
@@ -0,0 +1,1225 @@
+//! Structures shared by the GDEF, GSUB and GPOS tables.
+
+use std::marker::PhantomData;
+use decode::{Error, Result, Table, ReadPrimitive};
+use decode::primitives::Tag;
+use buffer::GlyphInfo;
+use table::gdef;
+
+/// A range of glyphs in format 2 coverage and class definition tables.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct RangeRecord {
+    start: u16,
+    end: u16,
+    /// The coverage index of the first glyph, or the class of the range.
+    value: u16,
+}
+
+fn range_at(ranges: &[u8], index: usize) -> Option<RangeRecord> {
+    let offset = index * 6;
+    Some(RangeRecord {
+        start: try_opt!(u16_at(ranges, offset)),
+        end: try_opt!(u16_at(ranges, offset + 2)),
+        value: try_opt!(u16_at(ranges, offset + 4)),
+    })
+}
+
+/// Finds the range holding a glyph in ranges sorted by start glyph.
+fn find_range(ranges: &[u8], glyph_id: u16) -> Option<RangeRecord> {
+    let (mut lo, mut hi) = (0, ranges.len() / 6);
+
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        let range = try_opt!(range_at(ranges, mid));
+
+        if range.end < glyph_id {
+            lo = mid + 1;
+        } else if range.start > glyph_id {
+            hi = mid;
+        } else {
+            return Some(range)
+        }
+    }
+
+    None
+}
+
+/// The glyphs a subtable applies to.  The coverage index of a glyph
+/// selects its entry in the arrays of the subtable.
+#[derive(Debug, Copy, Clone)]
+pub enum Coverage<'tbl> {
+    /// Sorted glyph ids, in coverage index order.
+    Format1(&'tbl [u8]),
+    /// Sorted ranges of consecutive glyphs.
+    Format2(&'tbl [u8]),
+}
+
+impl<'tbl> Table<'tbl> for Coverage<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Coverage<'tbl>> {
+        required_len!(buffer, 4);
+
+        let format = buffer.read::<u16>()?;
+        let count = buffer.read::<u16>()? as usize;
+
+        match format {
+            1 => {
+                required_len!(buffer, 2 * count);
+                Ok(Coverage::Format1(&buffer[..2 * count]))
+            },
+            2 => {
+                required_len!(buffer, 6 * count);
+                Ok(Coverage::Format2(&buffer[..6 * count]))
+            },
+            _ => Err(Error::InvalidData),
+        }
+    }
+}
+
+impl<'tbl> Coverage<'tbl> {
+    pub fn format(&self) -> u16 {
+        match *self {
+            Coverage::Format1(_) => 1,
+            Coverage::Format2(_) => 2,
+        }
+    }
+
+    pub fn coverage_index(&self, glyph_id: u16) -> Option<u16> {
+        match *self {
+            Coverage::Format1(glyphs) => {
+                let (mut lo, mut hi) = (0, glyphs.len() / 2);
+
+                while lo < hi {
+                    let mid = (lo + hi) / 2;
+                    let glyph = try_opt!(u16_at(glyphs, 2 * mid));
+
+                    if glyph < glyph_id {
+                        lo = mid + 1;
+                    } else if glyph > glyph_id {
+                        hi = mid;
+                    } else {
+                        return Some(mid as u16)
+                    }
+                }
+
+                None
+            },
+            Coverage::Format2(ranges) => {
+                let range = try_opt!(find_range(ranges, glyph_id));
+                range.value.checked_add(glyph_id - range.start)
+            },
+        }
+    }
+
+    pub fn contains(&self, glyph_id: u16) -> bool {
+        self.coverage_index(glyph_id).is_some()
+    }
+
+    /// The number of glyphs covered.
+    pub fn len(&self) -> usize {
+        match *self {
+            Coverage::Format1(glyphs) => glyphs.len() / 2,
+            Coverage::Format2(ranges) => {
+                let count = ranges.len() / 6;
+                match range_at(ranges, count.wrapping_sub(1)) {
+                    Some(last) => last.value as usize + (last.end as usize + 1).saturating_sub(last.start as usize),
+                    None => 0,
+                }
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The covered glyphs, in coverage index order.
+    pub fn iter(&self) -> CoverageIter<'tbl> {
+        CoverageIter { coverage: *self, index: 0, next_glyph: None }
+    }
+}
+
+/// Reads the `u16` at an offset, if the data is long enough.
+pub(crate) fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    let mut buf = try_opt!(data.get(offset..));
+    buf.read::<u16>().ok()
+}
+
+/// Reads the coverage table at an offset from the start of a subtable.
+pub(crate) fn coverage_at(data: &[u8], offset: u16) -> Result<Coverage<'_>> {
+    match data.get(offset as usize..) {
+        Some(coverage) if offset != 0 => Coverage::parse(coverage),
+        _ => Err(Error::InvalidData),
+    }
+}
+
+pub struct CoverageIter<'tbl> {
+    coverage: Coverage<'tbl>,
+    /// The next glyph, or the next range in format 2.
+    index: usize,
+    /// The next glyph of the current range and its end.
+    next_glyph: Option<(u16, u16)>,
+}
+
+impl<'tbl> Iterator for CoverageIter<'tbl> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        match self.coverage {
+            Coverage::Format1(glyphs) => {
+                let glyph = try_opt!(u16_at(glyphs, 2 * self.index));
+                self.index += 1;
+                Some(glyph)
+            },
+            Coverage::Format2(ranges) => {
+                loop {
+                    if let Some((glyph, end)) = self.next_glyph {
+                        self.next_glyph = if glyph < end { Some((glyph + 1, end)) } else { None };
+                        return Some(glyph)
+                    }
+
+                    let range = try_opt!(range_at(ranges, self.index));
+                    self.index += 1;
+                    if range.start <= range.end {
+                        self.next_glyph = Some((range.start, range.end));
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Assigns glyphs to classes.  Glyphs not listed are in class 0.
+#[derive(Debug, Copy, Clone)]
+pub enum ClassDef<'tbl> {
+    /// The classes of consecutive glyphs.
+    Format1 {
+        start_glyph: u16,
+        classes: &'tbl [u8],
+    },
+    /// Sorted ranges of glyphs sharing a class.
+    Format2(&'tbl [u8]),
+}
+
+impl<'tbl> Table<'tbl> for ClassDef<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<ClassDef<'tbl>> {
+        required_len!(buffer, 4);
+
+        match buffer.read::<u16>()? {
+            1 => {
+                required_len!(buffer, 4);
+                let start_glyph = buffer.read::<u16>()?;
+                let count = buffer.read::<u16>()? as usize;
+                required_len!(buffer, 2 * count);
+                Ok(ClassDef::Format1 { start_glyph, classes: &buffer[..2 * count] })
+            },
+            2 => {
+                let count = buffer.read::<u16>()? as usize;
+                required_len!(buffer, 6 * count);
+                Ok(ClassDef::Format2(&buffer[..6 * count]))
+            },
+            _ => Err(Error::InvalidData),
+        }
+    }
+}
+
+impl<'tbl> ClassDef<'tbl> {
+    pub fn format(&self) -> u16 {
+        match *self {
+            ClassDef::Format1 { .. } => 1,
+            ClassDef::Format2(_) => 2,
+        }
+    }
+
+    pub fn class(&self, glyph_id: u16) -> u16 {
+        match *self {
+            ClassDef::Format1 { start_glyph, classes } => {
+                glyph_id.checked_sub(start_glyph)
+                    .and_then(|index| u16_at(classes, 2 * index as usize))
+                    .unwrap_or(0)
+            },
+            ClassDef::Format2(ranges) => find_range(ranges, glyph_id).map_or(0, |range| range.value),
+        }
+    }
+
+    /// The glyphs with a class other than 0, with their class, in glyph
+    /// order.
+    pub fn iter(&self) -> ClassIter<'tbl> {
+        ClassIter { class_def: *self, index: 0, next_glyph: None }
+    }
+}
+
+pub struct ClassIter<'tbl> {
+    class_def: ClassDef<'tbl>,
+    /// The next glyph, or the next range in format 2.
+    index: usize,
+    /// The next glyph of the current range, its end and class.
+    next_glyph: Option<(u16, u16, u16)>,
+}
+
+impl<'tbl> Iterator for ClassIter<'tbl> {
+    type Item = (u16, u16);
+
+    fn next(&mut self) -> Option<(u16, u16)> {
+        loop {
+            match self.class_def {
+                ClassDef::Format1 { start_glyph, classes } => {
+                    let class = try_opt!(u16_at(classes, 2 * self.index));
+                    let glyph = try_opt!(start_glyph.checked_add(self.index as u16));
+                    self.index += 1;
+                    if class != 0 {
+                        return Some((glyph, class))
+                    }
+                },
+                ClassDef::Format2(ranges) => {
+                    if let Some((glyph, end, class)) = self.next_glyph {
+                        self.next_glyph = if glyph < end { Some((glyph + 1, end, class)) } else { None };
+                        return Some((glyph, class))
+                    }
+
+                    let range = try_opt!(range_at(ranges, self.index));
+                    self.index += 1;
+                    if range.value != 0 && range.start <= range.end {
+                        self.next_glyph = Some((range.start, range.end, range.value));
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// The script, feature and lookup lists at the start of the GSUB and
+/// GPOS tables.
+#[derive(Debug, Copy, Clone)]
+pub struct LayoutTable<'tbl> {
+    pub scripts: ScriptList<'tbl>,
+    pub features: FeatureList<'tbl>,
+    pub lookups: LookupList<'tbl>,
+    pub variations: Option<&'tbl [u8]>,
+}
+
+impl<'tbl> Table<'tbl> for LayoutTable<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<LayoutTable<'tbl>> {
+        required_len!(buffer, 10);
+
+        let head = buffer;
+        let major = buffer.read::<u16>()?;
+        let minor = buffer.read::<u16>()?;
+
+        verify!(major == 1);
+
+        // minor >= 0
+        let scripts_offset = buffer.read::<u16>()? as usize;
+        let feature_offset = buffer.read::<u16>()? as usize;
+        let lookup_offset = buffer.read::<u16>()? as usize;
+
+        required_len!(head,
+            max!(scripts_offset, feature_offset, lookup_offset));
+
+        let scripts = ScriptList::parse(&head[scripts_offset..])?;
+        let features = FeatureList::parse(&head[feature_offset..])?;
+        let lookups = LookupList::parse(&head[lookup_offset..])?;
+
+        // minor == 1
+        let variations = match minor {
+            0 => None,
+            1 => {
+                let offset = buffer.read::<u32>()? as usize;
+                required_len!(head, offset);
+                Some(head.split_at(offset).1)
+            }
+            _ => return Err(Error::InvalidData),
+        };
+
+        Ok(LayoutTable { scripts, features, lookups, variations })
+    }
+}
+
+impl<'tbl> LayoutTable<'tbl> {
+    /// Whether the feature list contains a feature with the given tag.
+    pub fn has_feature(&self, tag: Tag) -> bool {
+        self.features.iter().any(|(t, _)| t == tag)
+    }
+
+    /// Whether the language system for a script and language, found as by
+    /// `find_lang_sys`, has a feature with the given tag.
+    pub fn has_lang_sys_feature(&self, script: Tag, language: Option<Tag>, tag: Tag) -> bool {
+        let lang_sys = match self.find_lang_sys(script, language) {
+            Some(lang_sys) => lang_sys,
+            None => return false,
+        };
+
+        lang_sys.required_feature_index.into_iter()
+            .chain(lang_sys.feature_indices())
+            .any(|index| self.features.get(index).map(|(t, _)| t) == Some(tag))
+    }
+
+    /// The script used for text in `script`, falling back to the default
+    /// script and then to Latin as HarfBuzz does.
+    pub fn find_script(&self, script: Tag) -> Option<Script<'tbl>> {
+        let fallbacks = [script, Tag(*b"DFLT"), Tag(*b"dflt"), Tag(*b"latn")];
+        fallbacks.iter().filter_map(|&tag| self.scripts.script(tag)).next()
+    }
+
+    /// The language system for a script and language, using the default
+    /// one of the script when the language has none.
+    pub fn find_lang_sys(&self, script: Tag, language: Option<Tag>) -> Option<LangSys<'tbl>> {
+        let script = try_opt!(self.find_script(script));
+        language.and_then(|language| script.lang_sys(language))
+            .or_else(|| script.default_lang_sys())
+    }
+
+    /// Every script and language with its features, in the order they
+    /// are listed.
+    pub fn language_systems(&self) -> Vec<LanguageSystem> {
+        let mut systems = Vec::new();
+        for (script, table) in self.scripts.iter() {
+            let languages = table.default_lang_sys().map(|lang_sys| (None, lang_sys)).into_iter()
+                .chain(table.lang_sys_iter().map(|(language, lang_sys)| (Some(language), lang_sys)));
+
+            for (language, lang_sys) in languages {
+                let mut features: Vec<Tag> = lang_sys.required_feature_index.into_iter()
+                    .chain(lang_sys.feature_indices())
+                    .filter_map(|index| self.features.get(index))
+                    .map(|(tag, _)| tag)
+                    .collect();
+                features.sort();
+                features.dedup();
+                systems.push(LanguageSystem { script, language, features });
+            }
+        }
+        systems
+    }
+
+    /// The indices of the lookups applied for the features, in the order
+    /// they are applied.  The required feature of the language system is
+    /// always included.
+    pub fn lookup_indices(&self, script: Tag, language: Option<Tag>, features: &[Tag]) -> Vec<u16> {
+        let lang_sys = match self.find_lang_sys(script, language) {
+            Some(lang_sys) => lang_sys,
+            None => return Vec::new(),
+        };
+
+        let mut indices: Vec<u16> = Vec::new();
+        let selected = lang_sys.required_feature_index.into_iter()
+            .chain(lang_sys.feature_indices());
+        for index in selected {
+            if let Some((tag, feature)) = self.features.get(index) {
+                if Some(index) == lang_sys.required_feature_index || features.contains(&tag) {
+                    indices.extend(feature.lookup_indices());
+                }
+            }
+        }
+
+        indices.sort();
+        indices.dedup();
+        indices
+    }
+}
+
+/// The features a table has for a script and language.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageSystem {
+    pub script: Tag,
+    /// None for the default language system of the script.
+    pub language: Option<Tag>,
+    /// Sorted feature tags, each listed once.
+    pub features: Vec<Tag>,
+}
+
+/// Records of a tag and an offset to a table, from the start of the
+/// list holding them.
+#[derive(Debug, Copy, Clone)]
+struct TaggedRecords<'tbl> {
+    /// The list, starting with its header.
+    data: &'tbl [u8],
+    /// The offset of the first record.
+    start: usize,
+    count: u16,
+}
+
+impl<'tbl> TaggedRecords<'tbl> {
+    fn get<T: Table<'tbl>>(&self, index: u16) -> Option<(Tag, T)> {
+        if index >= self.count {
+            return None
+        }
+
+        let mut buffer = try_opt!(self.data.get(self.start + 6 * index as usize..));
+        let tag = try_opt!(buffer.read::<Tag>().ok());
+        let offset = try_opt!(buffer.read::<u16>().ok()) as usize;
+        let table = try_opt!(T::parse(try_opt!(self.data.get(offset..))).ok());
+        Some((tag, table))
+    }
+
+    /// Finds the first record with a tag.
+    fn find<T: Table<'tbl>>(&self, tag: Tag) -> Option<T> {
+        (0..self.count)
+            .find(|&index| self.data.get(self.start + 6 * index as usize..)
+                .and_then(|mut buffer| buffer.read::<Tag>().ok()) == Some(tag))
+            .and_then(|index| self.get(index))
+            .map(|(_, table)| table)
+    }
+
+    fn iter<T: Table<'tbl>>(&self) -> RecordIter<'tbl, T> {
+        RecordIter { records: *self, index: 0, kind: PhantomData }
+    }
+}
+
+/// Iterates over the tags and tables of a list.
+pub struct RecordIter<'tbl, T> {
+    records: TaggedRecords<'tbl>,
+    index: u16,
+    kind: PhantomData<T>,
+}
+
+impl<'tbl, T: Table<'tbl>> Iterator for RecordIter<'tbl, T> {
+    type Item = (Tag, T);
+
+    fn next(&mut self) -> Option<(Tag, T)> {
+        while self.index < self.records.count {
+            self.index += 1;
+            if let Some(record) = self.records.get(self.index - 1) {
+                return Some(record)
+            }
+        }
+
+        None
+    }
+}
+
+/// Iterates over an array of 16 bit indices.
+#[derive(Debug, Clone)]
+pub struct IndexIter<'tbl> {
+    buffer: &'tbl [u8],
+}
+
+impl<'tbl> Iterator for IndexIter<'tbl> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        self.buffer.read::<u16>().ok()
+    }
+}
+
+fn read_records<'tbl>(data: &'tbl [u8], start: usize) -> Result<TaggedRecords<'tbl>> {
+    let mut buffer = &data[start..];
+    let count = buffer.read::<u16>()?;
+    required_len!(buffer, 6 * count as usize);
+    Ok(TaggedRecords { data, start: start + 2, count })
+}
+
+/// The scripts supported by a table.
+#[derive(Debug, Copy, Clone)]
+pub struct ScriptList<'tbl> {
+    records: TaggedRecords<'tbl>,
+}
+
+impl<'tbl> Table<'tbl> for ScriptList<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<ScriptList<'tbl>> {
+        required_len!(buffer, 2);
+        Ok(ScriptList { records: read_records(buffer, 0)? })
+    }
+}
+
+impl<'tbl> ScriptList<'tbl> {
+    pub fn len(&self) -> usize {
+        self.records.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.count == 0
+    }
+
+    pub fn script(&self, tag: Tag) -> Option<Script<'tbl>> {
+        self.records.find(tag)
+    }
+
+    pub fn iter(&self) -> RecordIter<'tbl, Script<'tbl>> {
+        self.records.iter()
+    }
+}
+
+/// The language systems of a script.
+#[derive(Debug, Copy, Clone)]
+pub struct Script<'tbl> {
+    default_lang_sys: Option<LangSys<'tbl>>,
+    records: TaggedRecords<'tbl>,
+}
+
+impl<'tbl> Table<'tbl> for Script<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Script<'tbl>> {
+        required_len!(buffer, 4);
+
+        let head = buffer;
+        let default_lang_sys = match offset_maybe_null!(head, buffer) {
+            Some(data) => Some(LangSys::parse(data)?),
+            None => None,
+        };
+
+        Ok(Script { default_lang_sys, records: read_records(head, 2)? })
+    }
+}
+
+impl<'tbl> Script<'tbl> {
+    pub fn default_lang_sys(&self) -> Option<LangSys<'tbl>> {
+        self.default_lang_sys
+    }
+
+    pub fn lang_sys(&self, tag: Tag) -> Option<LangSys<'tbl>> {
+        self.records.find(tag)
+    }
+
+    /// The language systems other than the default one.
+    pub fn lang_sys_iter(&self) -> RecordIter<'tbl, LangSys<'tbl>> {
+        self.records.iter()
+    }
+}
+
+/// The features used for a language.
+#[derive(Debug, Copy, Clone)]
+pub struct LangSys<'tbl> {
+    pub required_feature_index: Option<u16>,
+    feature_indices: &'tbl [u8],
+}
+
+impl<'tbl> Table<'tbl> for LangSys<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<LangSys<'tbl>> {
+        required_len!(buffer, 6);
+
+        let _ /* lookup_order */ = buffer.read::<u16>()?;
+        let required_feature_index = match buffer.read::<u16>()? {
+            0xFFFF => None,
+            index => Some(index),
+        };
+        let count = buffer.read::<u16>()? as usize;
+        required_len!(buffer, 2 * count);
+
+        Ok(LangSys { required_feature_index, feature_indices: &buffer[..2 * count] })
+    }
+}
+
+impl<'tbl> LangSys<'tbl> {
+    /// Indices into the feature list, not including the required feature.
+    pub fn feature_indices(&self) -> IndexIter<'tbl> {
+        IndexIter { buffer: self.feature_indices }
+    }
+}
+
+/// The features of a table.  The same tag appears once for each set of
+/// language systems using different lookups for it.
+#[derive(Debug, Copy, Clone)]
+pub struct FeatureList<'tbl> {
+    records: TaggedRecords<'tbl>,
+}
+
+impl<'tbl> Table<'tbl> for FeatureList<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<FeatureList<'tbl>> {
+        required_len!(buffer, 2);
+        Ok(FeatureList { records: read_records(buffer, 0)? })
+    }
+}
+
+impl<'tbl> FeatureList<'tbl> {
+    pub fn len(&self) -> usize {
+        self.records.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.count == 0
+    }
+
+    pub fn get(&self, index: u16) -> Option<(Tag, Feature<'tbl>)> {
+        self.records.get(index)
+    }
+
+    pub fn iter(&self) -> RecordIter<'tbl, Feature<'tbl>> {
+        self.records.iter()
+    }
+
+    /// The parameters of a feature, read according to its tag.
+    pub fn params(&self, index: u16) -> Option<FeatureParams> {
+        let (tag, feature) = try_opt!(self.get(index));
+        let offset = feature.params_offset() as usize;
+        if offset == 0 {
+            return None
+        }
+
+        match &tag.0 {
+            b"size" => {
+                // Early fonts measured the offset from the feature list
+                // rather than from the feature, as HarfBuzz notes.
+                feature.data.get(offset..).and_then(SizeParams::parse)
+                    .or_else(|| self.records.data.get(offset..).and_then(SizeParams::parse))
+                    .map(FeatureParams::Size)
+            },
+            &[b's', b's', a, b] if a.is_ascii_digit() && b.is_ascii_digit() => {
+                let ui_name_id = try_opt!(u16_at(feature.data, offset + 2));
+                Some(FeatureParams::StylisticSet { ui_name_id })
+            },
+            &[b'c', b'v', a, b] if a.is_ascii_digit() && b.is_ascii_digit() => {
+                CharacterVariantParams::parse(try_opt!(feature.data.get(offset..)))
+                    .map(FeatureParams::CharacterVariant)
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Feature parameters, for the few features that have them.  Name ids
+/// refer to the `name` table, with 0 for missing names.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureParams {
+    Size(SizeParams),
+    /// `ss01` to `ss20`.
+    StylisticSet {
+        ui_name_id: u16,
+    },
+    /// `cv01` to `cv99`.
+    CharacterVariant(CharacterVariantParams),
+}
+
+/// The range of sizes a face is designed for, given by the `size`
+/// feature.  Sizes are in decipoints.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SizeParams {
+    pub design_size: u16,
+    /// Identifies the faces of a family designed for the same range.
+    pub subfamily_id: u16,
+    pub subfamily_name_id: u16,
+    pub range_start: u16,
+    pub range_end: u16,
+}
+
+impl SizeParams {
+    /// Reads the parameters, returning None when they are not consistent.
+    fn parse(buffer: &[u8]) -> Option<SizeParams> {
+        let params = SizeParams {
+            design_size: try_opt!(u16_at(buffer, 0)),
+            subfamily_id: try_opt!(u16_at(buffer, 2)),
+            subfamily_name_id: try_opt!(u16_at(buffer, 4)),
+            range_start: try_opt!(u16_at(buffer, 6)),
+            range_end: try_opt!(u16_at(buffer, 8)),
+        };
+
+        let no_range = params.subfamily_id == 0 && params.subfamily_name_id == 0
+            && params.range_start == 0 && params.range_end == 0;
+        let valid_range = params.range_start <= params.design_size
+            && params.design_size <= params.range_end
+            && params.subfamily_name_id >= 256 && params.subfamily_name_id <= 32767;
+
+        if params.design_size != 0 && (no_range || valid_range) {
+            Some(params)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CharacterVariantParams {
+    pub label_name_id: u16,
+    pub tooltip_name_id: u16,
+    pub sample_text_name_id: u16,
+    /// The number of parameters with labels, whose name ids follow on
+    /// from `first_param_label_name_id`.
+    pub num_named_parameters: u16,
+    pub first_param_label_name_id: u16,
+    /// The characters the feature applies to.
+    pub characters: Vec<u32>,
+}
+
+impl CharacterVariantParams {
+    fn parse(buffer: &[u8]) -> Option<CharacterVariantParams> {
+        let count = try_opt!(u16_at(buffer, 12)) as usize;
+        let characters = try_opt!(buffer.get(14..14 + 3 * count));
+
+        Some(CharacterVariantParams {
+            label_name_id: try_opt!(u16_at(buffer, 2)),
+            tooltip_name_id: try_opt!(u16_at(buffer, 4)),
+            sample_text_name_id: try_opt!(u16_at(buffer, 6)),
+            num_named_parameters: try_opt!(u16_at(buffer, 8)),
+            first_param_label_name_id: try_opt!(u16_at(buffer, 10)),
+            characters: characters.chunks(3)
+                .map(|c| (c[0] as u32) << 16 | (c[1] as u32) << 8 | c[2] as u32)
+                .collect(),
+        })
+    }
+}
+
+/// A feature of a font, with the names its parameters refer to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureInfo {
+    pub tag: Tag,
+    pub params: Option<FeatureParams>,
+    /// The name shown for the feature, or the subfamily name for `size`.
+    pub name: Option<String>,
+    pub tooltip: Option<String>,
+    pub sample_text: Option<String>,
+    /// The names of the alternates of a character variant.
+    pub param_labels: Vec<String>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Feature<'tbl> {
+    /// The feature table, starting with the offset to its parameters.
+    data: &'tbl [u8],
+    lookup_indices: &'tbl [u8],
+}
+
+impl<'tbl> Table<'tbl> for Feature<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<Feature<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 4);
+
+        let _ /* feature_params */ = buffer.read::<u16>()?;
+        let count = buffer.read::<u16>()? as usize;
+        required_len!(buffer, 2 * count);
+
+        Ok(Feature { data, lookup_indices: &buffer[..2 * count] })
+    }
+}
+
+impl<'tbl> Feature<'tbl> {
+    /// The offset to the feature parameters from the start of the
+    /// feature table, or 0 if there are none.
+    pub fn params_offset(&self) -> u16 {
+        (&self.data[..]).read::<u16>().unwrap_or(0)
+    }
+
+    /// The feature parameters, whose layout depends on the feature.
+    pub fn params(&self) -> Option<&'tbl [u8]> {
+        match self.params_offset() {
+            0 => None,
+            offset => self.data.get(offset as usize..),
+        }
+    }
+
+    /// Indices into the lookup list.
+    pub fn lookup_indices(&self) -> IndexIter<'tbl> {
+        IndexIter { buffer: self.lookup_indices }
+    }
+}
+
+/// The lookups of a table, in the order they are applied.
+#[derive(Debug, Copy, Clone)]
+pub struct LookupList<'tbl> {
+    data: &'tbl [u8],
+    count: u16,
+}
+
+impl<'tbl> Table<'tbl> for LookupList<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<LookupList<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 2);
+
+        let count = buffer.read::<u16>()?;
+        required_len!(buffer, 2 * count as usize);
+
+        Ok(LookupList { data, count })
+    }
+}
+
+impl<'tbl> LookupList<'tbl> {
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn get(&self, index: u16) -> Option<Lookup<'tbl>> {
+        if index >= self.count {
+            return None
+        }
+
+        let offset = try_opt!(u16_at(self.data, 2 + 2 * index as usize)) as usize;
+        Lookup::parse(try_opt!(self.data.get(offset..))).ok()
+    }
+
+    pub fn iter(&self) -> LookupIter<'tbl> {
+        LookupIter { list: *self, index: 0 }
+    }
+}
+
+pub struct LookupIter<'tbl> {
+    list: LookupList<'tbl>,
+    index: u16,
+}
+
+impl<'tbl> Iterator for LookupIter<'tbl> {
+    type Item = Lookup<'tbl>;
+
+    fn next(&mut self) -> Option<Lookup<'tbl>> {
+        while self.index < self.list.count {
+            self.index += 1;
+            if let Some(lookup) = self.list.get(self.index - 1) {
+                return Some(lookup)
+            }
+        }
+
+        None
+    }
+}
+
+bitflags! {
+    pub struct LookupFlag: u16 {
+        const RIGHT_TO_LEFT          = 1 << 0;
+        const IGNORE_BASE_GLYPHS     = 1 << 1;
+        const IGNORE_LIGATURES       = 1 << 2;
+        const IGNORE_MARKS           = 1 << 3;
+        const USE_MARK_FILTERING_SET = 1 << 4;
+        const MARK_ATTACHMENT_TYPE   = 0xFF00;
+    }
+}
+
+impl LookupFlag {
+    /// When not 0, marks of other attachment classes are skipped.
+    pub fn mark_attachment_type(&self) -> u16 {
+        (*self & MARK_ATTACHMENT_TYPE).bits() >> 8
+    }
+}
+
+/// A set of subtables of the same type, applied together.
+#[derive(Debug, Copy, Clone)]
+pub struct Lookup<'tbl> {
+    data: &'tbl [u8],
+    pub lookup_type: u16,
+    pub lookup_flag: LookupFlag,
+    count: u16,
+    /// The set in GDEF of the marks not skipped.
+    pub mark_filtering_set: Option<u16>,
+}
+
+impl<'tbl> Table<'tbl> for Lookup<'tbl> {
+    fn parse(buffer: &'tbl [u8]) -> Result<Lookup<'tbl>> {
+        let data = buffer;
+        let mut buffer = buffer;
+        required_len!(buffer, 6);
+
+        let lookup_type = buffer.read::<u16>()?;
+        let lookup_flag = LookupFlag::from_bits_truncate(buffer.read::<u16>()?);
+        let count = buffer.read::<u16>()?;
+        required_len!(buffer, 2 * count as usize);
+
+        let mark_filtering_set = if lookup_flag.contains(USE_MARK_FILTERING_SET) {
+            let (_, mut rest) = buffer.split_at(2 * count as usize);
+            Some(rest.read::<u16>()?)
+        } else {
+            None
+        };
+
+        Ok(Lookup { data, lookup_type, lookup_flag, count, mark_filtering_set })
+    }
+}
+
+impl<'tbl> Lookup<'tbl> {
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The data of a subtable, to be read according to the lookup type.
+    pub fn subtable(&self, index: u16) -> Option<&'tbl [u8]> {
+        if index >= self.count {
+            return None
+        }
+
+        let offset = try_opt!(u16_at(self.data, 6 + 2 * index as usize)) as usize;
+        self.data.get(offset..)
+    }
+
+    pub fn subtables(&self) -> SubtableIter<'tbl> {
+        SubtableIter { lookup: *self, index: 0 }
+    }
+}
+
+pub struct SubtableIter<'tbl> {
+    lookup: Lookup<'tbl>,
+    index: u16,
+}
+
+impl<'tbl> Iterator for SubtableIter<'tbl> {
+    type Item = &'tbl [u8];
+
+    fn next(&mut self) -> Option<&'tbl [u8]> {
+        while self.index < self.lookup.count {
+            self.index += 1;
+            if let Some(subtable) = self.lookup.subtable(self.index - 1) {
+                return Some(subtable)
+            }
+        }
+
+        None
+    }
+}
+
+/// Decides which glyphs a lookup skips over, from their classes in GDEF.
+/// Without GDEF, no glyph is skipped.
+#[derive(Copy, Clone)]
+pub struct GlyphFilter<'a, 'tbl: 'a> {
+    gdef: Option<&'a gdef::Header<'tbl>>,
+    flag: LookupFlag,
+    mark_filtering_set: Option<u16>,
+}
+
+impl<'a, 'tbl> GlyphFilter<'a, 'tbl> {
+    pub fn new(lookup: &Lookup, gdef: Option<&'a gdef::Header<'tbl>>) -> GlyphFilter<'a, 'tbl> {
+        GlyphFilter {
+            gdef,
+            flag: lookup.lookup_flag,
+            mark_filtering_set: lookup.mark_filtering_set,
+        }
+    }
+
+    /// The same filter with its lookup flags replaced by `flag`, and its
+    /// mark filtering set dropped.  Mark-to-base and mark-to-ligature
+    /// attachment use this to find the previous non-mark glyph, whatever
+    /// flags their own lookup has.
+    pub fn with_flags_replaced(&self, flag: LookupFlag) -> GlyphFilter<'a, 'tbl> {
+        GlyphFilter { gdef: self.gdef, flag, mark_filtering_set: None }
+    }
+
+    pub fn glyph_class(&self, glyph_id: u16) -> u16 {
+        self.gdef.map_or(0, |gdef| gdef.glyph_class(glyph_id))
+    }
+
+    pub fn skips(&self, glyph_id: u16) -> bool {
+        let gdef = match self.gdef {
+            Some(gdef) => gdef,
+            None => return false,
+        };
+
+        match gdef.glyph_class(glyph_id) {
+            1 => self.flag.contains(IGNORE_BASE_GLYPHS),
+            2 => self.flag.contains(IGNORE_LIGATURES),
+            3 => {
+                if self.flag.contains(IGNORE_MARKS) {
+                    true
+                } else if let Some(set) = self.mark_filtering_set {
+                    !gdef.is_in_mark_set(set, glyph_id)
+                } else {
+                    let attachment_type = self.flag.mark_attachment_type();
+                    attachment_type != 0 && gdef.mark_attach_class(glyph_id) != attachment_type
+                }
+            },
+            _ => false,
+        }
+    }
+
+    /// The index of the first glyph after `index` that is not skipped.
+    pub fn next(&self, glyphs: &[GlyphInfo], index: usize) -> Option<usize> {
+        (index + 1..glyphs.len()).find(|&i| !self.skips(glyphs[i].glyph_id))
+    }
+
+    /// The index of the last glyph before `index` that is not skipped.
+    pub fn prev(&self, glyphs: &[GlyphInfo], index: usize) -> Option<usize> {
+        (0..index).rev().find(|&i| !self.skips(glyphs[i].glyph_id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use font::Font;
+    use decode::Table;
+    use decode::primitives::Tag;
+    use table::{gpos, gsub};
+    use table::layout::{CharacterVariantParams, ClassDef, Coverage, FeatureList, FeatureParams,
+                        SizeParams, IGNORE_MARKS};
+
+    #[test]
+    fn coverage() {
+        let buf: &[u8] = &[
+            0x00, 0x01, 0x00, 0x03, // format 1, 3 glyphs
+            0x00, 0x05, 0x00, 0x09, 0x00, 0x0A,
+        ];
+        let coverage = Coverage::parse(buf).expect("Unable to parse coverage");
+        assert_eq!(coverage.len(), 3);
+        assert_eq!(coverage.coverage_index(5), Some(0));
+        assert_eq!(coverage.coverage_index(10), Some(2));
+        assert_eq!(coverage.coverage_index(6), None);
+        assert_eq!(coverage.iter().collect::<Vec<_>>(), vec![5, 9, 10]);
+
+        let buf: &[u8] = &[
+            0x00, 0x02, 0x00, 0x02, // format 2, 2 ranges
+            0x00, 0x04, 0x00, 0x06, 0x00, 0x00,
+            0x00, 0x0A, 0x00, 0x0A, 0x00, 0x03,
+        ];
+        let coverage = Coverage::parse(buf).expect("Unable to parse coverage");
+        assert_eq!(coverage.len(), 4);
+        assert_eq!(coverage.coverage_index(6), Some(2));
+        assert_eq!(coverage.coverage_index(10), Some(3));
+        assert_eq!(coverage.coverage_index(7), None);
+        assert_eq!(coverage.iter().collect::<Vec<_>>(), vec![4, 5, 6, 10]);
+
+        assert!(Coverage::parse(&[0x00, 0x01, 0x00, 0x02, 0x00, 0x05]).is_err());
+        assert!(Coverage::parse(&[0x00, 0x03, 0x00, 0x00]).is_err());
+    }
+
+    #[test]
+    fn class_def() {
+        let buf: &[u8] = &[
+            0x00, 0x01, 0x00, 0x08, 0x00, 0x03, // format 1, from glyph 8
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02,
+        ];
+        let class_def = ClassDef::parse(buf).expect("Unable to parse class definition");
+        assert_eq!(class_def.class(8), 1);
+        assert_eq!(class_def.class(9), 0);
+        assert_eq!(class_def.class(10), 2);
+        assert_eq!(class_def.class(7), 0);
+        assert_eq!(class_def.class(11), 0);
+        assert_eq!(class_def.iter().collect::<Vec<_>>(), vec![(8, 1), (10, 2)]);
+
+        let buf: &[u8] = &[
+            0x00, 0x02, 0x00, 0x02, // format 2, 2 ranges
+            0x00, 0x02, 0x00, 0x03, 0x00, 0x04,
+            0x00, 0x07, 0x00, 0x07, 0x00, 0x01,
+        ];
+        let class_def = ClassDef::parse(buf).expect("Unable to parse class definition");
+        assert_eq!(class_def.class(3), 4);
+        assert_eq!(class_def.class(7), 1);
+        assert_eq!(class_def.class(5), 0);
+        assert_eq!(class_def.iter().collect::<Vec<_>>(), vec![(2, 4), (3, 4), (7, 1)]);
+    }
+
+    #[test]
+    fn feature_params() {
+        let buf: &[u8] = &[
+            0x00, 0x04, // 4 features
+            b's', b's', b'0', b'1', 0x00, 0x1A,
+            b'c', b'v', b'0', b'1', 0x00, 0x22,
+            b's', b'i', b'z', b'e', 0x00, 0x3A,
+            b'l', b'i', b'g', b'a', 0x00, 0x3E,
+            // ss01
+            0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+            // cv01
+            0x00, 0x04, 0x00, 0x00,
+            0x00, 0x00, 0x01, 0x01, 0x01, 0x02, 0x00, 0x00, 0x00, 0x02, 0x01, 0x03,
+            0x00, 0x02, 0x00, 0x00, 0x41, 0x01, 0xF6, 0x00,
+            // size, with its parameters measured from the feature list
+            0x00, 0x42, 0x00, 0x00,
+            // liga
+            0x00, 0x00, 0x00, 0x00,
+            // size parameters
+            0x00, 0x64, 0x00, 0x01, 0x01, 0x04, 0x00, 0x50, 0x00, 0x78,
+        ];
+        let features = FeatureList::parse(buf).expect("Unable to parse feature list");
+
+        assert_eq!(features.params(0), Some(FeatureParams::StylisticSet { ui_name_id: 256 }));
+        assert_eq!(features.params(1), Some(FeatureParams::CharacterVariant(CharacterVariantParams {
+            label_name_id: 257,
+            tooltip_name_id: 258,
+            sample_text_name_id: 0,
+            num_named_parameters: 2,
+            first_param_label_name_id: 259,
+            characters: vec![0x41, 0x1F600],
+        })));
+        assert_eq!(features.params(2), Some(FeatureParams::Size(SizeParams {
+            design_size: 100,
+            subfamily_id: 1,
+            subfamily_name_id: 260,
+            range_start: 80,
+            range_end: 120,
+        })));
+        assert_eq!(features.params(3), None);
+        assert_eq!(features.params(4), None);
+    }
+
+    #[test]
+    fn language_systems() {
+        let buf = open_font!("data/Roboto-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+
+        let systems = font.get_language_systems();
+        let find = |script: &[u8; 4], language: Option<&[u8; 4]>| systems.iter()
+            .find(|s| s.script == Tag(*script) && s.language == language.map(|l| Tag(*l)))
+            .expect("Missing language system");
+
+        // Features from both tables are merged.
+        let default = find(b"DFLT", None);
+        assert!(default.features.contains(&Tag(*b"ccmp")));
+        assert!(default.features.contains(&Tag(*b"kern")));
+        assert!(!default.features.contains(&Tag(*b"liga")));
+        assert!(find(b"latn", None).features.contains(&Tag(*b"liga")));
+        assert!(find(b"latn", Some(b"FRA ")).features.contains(&Tag(*b"locl")));
+        assert!(!find(b"latn", Some(b"TRK ")).features.contains(&Tag(*b"locl")));
+
+        let features = font.get_features();
+        let tags: Vec<_> = features.iter().map(|f| f.tag).collect();
+        let mut sorted = tags.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(tags, sorted);
+        assert!(tags.contains(&Tag(*b"ss01")));
+        assert!(tags.contains(&Tag(*b"kern")));
+        assert!(features.iter().all(|f| f.params.is_none() && f.name.is_none()));
+    }
+
+    #[test]
+    fn lookups_roboto() {
+        let buf = open_font!("data/Roboto-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gsub = font.get_table::<gsub::Header>().expect("Unable to read GSUB table");
+
+        let tags: Vec<Tag> = gsub.scripts.iter().map(|(tag, _)| tag).collect();
+        assert_eq!(tags, vec![Tag(*b"DFLT"), Tag(*b"cyrl"), Tag(*b"grek"), Tag(*b"latn")]);
+
+        let latn = gsub.scripts.script(Tag(*b"latn")).expect("Missing script");
+        assert_eq!(latn.lang_sys_iter().count(), 7);
+        let default = latn.default_lang_sys().expect("Missing default language system");
+        assert_eq!(default.required_feature_index, None);
+        assert_eq!(default.feature_indices().take(6).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 7]);
+
+        let (tag, liga) = gsub.features.get(6).expect("Missing feature");
+        assert_eq!(tag, Tag(*b"liga"));
+        assert_eq!(liga.lookup_indices().collect::<Vec<_>>(), vec![8, 9, 8, 9]);
+        assert!(liga.params().is_none());
+        assert_eq!(gsub.features.len(), 24);
+
+        let latn = Tag(*b"latn");
+        let (ccmp, liga, locl) = (Tag(*b"ccmp"), Tag(*b"liga"), Tag(*b"locl"));
+        assert_eq!(gsub.lookup_indices(latn, None, &[liga, ccmp]), vec![2, 4, 8, 9]);
+        assert_eq!(gsub.lookup_indices(latn, Some(Tag(*b"TRK ")), &[liga]), vec![9]);
+        assert_eq!(gsub.lookup_indices(latn, Some(Tag(*b"FRA ")), &[liga, locl]), vec![7, 8, 9]);
+        assert_eq!(gsub.lookup_indices(latn, Some(Tag(*b"XXX ")), &[locl]), vec![]);
+
+        // Unknown scripts use the default script.
+        assert_eq!(gsub.lookup_indices(Tag(*b"arab"), None, &[ccmp]), vec![2, 4]);
+
+        let lookup = gsub.lookups.get(2).expect("Missing lookup");
+        assert_eq!((lookup.lookup_type, lookup.len()), (6, 6));
+        assert_eq!(lookup.subtables().count(), 6);
+        assert!(gsub.lookups.get(26).is_none());
+    }
+
+    #[test]
+    fn lookups_notosans() {
+        let buf = open_font!("data/NotoSans-Regular.ttf");
+        let font = Font::from_buffer(&buf).expect("Unable to parse font");
+        let gpos = font.get_table::<gpos::Header>().expect("Unable to read GPOS table");
+
+        let features = [Tag(*b"kern"), Tag(*b"mark"), Tag(*b"mkmk")];
+        assert_eq!(gpos.lookup_indices(Tag(*b"latn"), None, &features), (0..12).collect::<Vec<_>>());
+        assert_eq!(gpos.lookup_indices(Tag(*b"cyrl"), None, &features[1..]), vec![1, 9, 10]);
+        assert_eq!(gpos.lookup_indices(Tag(*b"deva"), Some(Tag(*b"MAR ")), &[Tag(*b"dist")]),
+                   vec![14, 15, 17]);
+
+        // No default script, so Latin is used.
+        assert_eq!(gpos.lookup_indices(Tag(*b"arab"), None, &[Tag(*b"kern")]), vec![11]);
+
+        let lookups: Vec<_> = gpos.lookups.iter().collect();
+        assert_eq!(lookups.len(), 20);
+        assert_eq!(lookups[5].lookup_flag.mark_attachment_type(), 1);
+        assert_eq!(lookups[10].lookup_flag.mark_attachment_type(), 3);
+        assert_eq!(lookups[11].lookup_flag, IGNORE_MARKS);
+        assert_eq!((lookups[11].lookup_type, lookups[11].len()), (2, 2));
+        assert_eq!(lookups[11].mark_filtering_set, None);
+
+        assert!(gpos.has_feature(Tag(*b"kern")));
+        assert!(!gpos.has_feature(Tag(*b"liga")));
+    }
+}
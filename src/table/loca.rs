@@ -0,0 +1,87 @@
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+use std::ops::Range;
+
+/// Index to location table.  Maps glyph ids to the byte range of their
+/// description in the `glyf` table.
+#[derive(Debug)]
+pub enum Loca<'tbl> {
+    /// Offsets stored as `u16`, divided by two.
+    Short(&'tbl [u8]),
+    Long(&'tbl [u8]),
+}
+
+impl<'tbl> Loca<'tbl> {
+    /// The number of glyphs are found in the `maxp` table,
+    /// and the format is `index_to_loc_format` from the `head` table.
+    pub fn parse(buffer: &'tbl [u8], num_glyphs: u16, format: i16) -> Result<Loca<'tbl>> {
+        let count = num_glyphs as usize + 1;
+
+        match format {
+            0 => {
+                required_len!(buffer, 2 * count);
+                Ok(Loca::Short(&buffer[..2 * count]))
+            },
+
+            1 => {
+                required_len!(buffer, 4 * count);
+                Ok(Loca::Long(&buffer[..4 * count]))
+            },
+
+            _ => Err(Error::InvalidData),
+        }
+    }
+
+    pub fn num_glyphs(&self) -> u16 {
+        match *self {
+            Loca::Short(buf) => (buf.len() / 2 - 1) as u16,
+            Loca::Long(buf) => (buf.len() / 4 - 1) as u16,
+        }
+    }
+
+    fn get_offset(&self, idx: usize) -> Option<usize> {
+        match *self {
+            Loca::Short(buf) => {
+                let mut buf = try_opt!(buf.get(2 * idx..));
+                buf.read::<u16>().ok().map(|off| 2 * off as usize)
+            },
+
+            Loca::Long(buf) => {
+                let mut buf = try_opt!(buf.get(4 * idx..));
+                buf.read::<u32>().ok().map(|off| off as usize)
+            },
+        }
+    }
+
+    /// Returns the range of bytes in the `glyf` table describing the glyph.
+    /// Glyphs without an outline, such as the space, have an empty range.
+    pub fn get_glyph_range(&self, glyph_id: u16) -> Option<Range<usize>> {
+        let start = try_opt!(self.get_offset(glyph_id as usize));
+        let end = try_opt!(self.get_offset(glyph_id as usize + 1));
+
+        if start > end {
+            return None
+        }
+
+        Some(start..end)
+    }
+}
+
+#[test]
+fn glyph_ranges() {
+    use font::Font;
+
+    let buf = open_font!("data/OpenSans-Regular.ttf");
+    let font = Font::from_buffer(&buf).expect("Unable to parse font");
+    let loca = font.get_table_loca().expect("unable to read loca table");
+
+    assert_eq!(loca.num_glyphs(), 938);
+
+    // ' ' has no outline, while 'A' does.
+    let space = loca.get_glyph_range(3).expect("missing range for ' '");
+    assert!(space.start == space.end);
+
+    let a = loca.get_glyph_range(36).expect("missing range for 'A'");
+    assert!(a.start < a.end);
+
+    assert!(loca.get_glyph_range(938).is_none());
+}
@@ -0,0 +1,59 @@
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+
+/// Linear threshold table.  Gives the ppem at which each glyph's
+/// advance starts to scale linearly, despite hinting.
+#[derive(Debug)]
+pub struct Ltsh<'tbl> {
+    y_pels: &'tbl [u8],
+    pub version: u16,
+    pub num_glyphs: u16,
+}
+
+impl<'tbl> Table<'tbl> for Ltsh<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Ltsh<'tbl>> {
+        required_len!(buffer, 4);
+
+        let version = buffer.read::<u16>()?;
+        let num_glyphs = buffer.read::<u16>()?;
+
+        required_len!(buffer, num_glyphs as usize);
+
+        Ok(Ltsh {
+            y_pels: &buffer[..num_glyphs as usize],
+            version,
+            num_glyphs,
+        })
+    }
+}
+
+impl<'tbl> Ltsh<'tbl> {
+    /// The ppem at which the glyph becomes linear.
+    pub fn get_threshold(&self, glyph_id: u16) -> Option<u8> {
+        self.y_pels.get(glyph_id as usize).cloned()
+    }
+
+    /// Whether the hinted advance of a glyph at the given ppem is
+    /// the same as the linearly scaled advance.
+    pub fn is_linear(&self, glyph_id: u16, ppem: u16) -> bool {
+        match self.get_threshold(glyph_id) {
+            Some(threshold) => ppem >= threshold as u16,
+            None => false,
+        }
+    }
+}
+
+#[test]
+fn linear_thresholds() {
+    let buf: &[u8] = &[
+        0x00, 0x00, 0x00, 0x03, // version 0, 3 glyphs
+        0x01, 0x0C, 0xFF,
+    ];
+
+    let ltsh = Ltsh::parse(buf).expect("unable to parse LTSH table");
+
+    assert!(ltsh.is_linear(0, 8));
+    assert!(!ltsh.is_linear(1, 11));
+    assert!(ltsh.is_linear(1, 12));
+    assert!(!ltsh.is_linear(2, 254));
+    assert!(!ltsh.is_linear(3, 100));
+}
@@ -5,9 +5,24 @@ pub mod os2;
 pub mod name;
 pub mod cmap;
 pub mod glyf;
+pub mod loca;
+pub mod eblc;
+pub mod layout;
 pub mod gdef;
 pub mod gpos;
+pub mod gsub;
+pub mod context;
 pub mod hmtx;
+pub mod kern;
+pub mod hdmx;
+pub mod ltsh;
+pub mod gasp;
+pub mod vdmx;
+pub mod vhea;
+pub mod vmtx;
+pub mod vorg;
+pub mod cff;
+pub mod variations;
 
 use decode::primitives::Tag;
 use decode::Table;
@@ -32,8 +47,19 @@ macro_rules! impl_tagged_table {
 impl_tagged_table!(
     name::Name<'tbl> => *b"name",
     hhea::Hhea => *b"hhea",
+    vhea::Vhea => *b"vhea",
+    vorg::Vorg<'tbl> => *b"VORG",
     maxp::Maxp => *b"maxp",
     os2::Os2<'tbl> => *b"OS/2",
     head::Head<'tbl> => *b"head",
     cmap::CmapHeader<'tbl> => *b"cmap",
+    kern::Kern<'tbl> => *b"kern",
+    ltsh::Ltsh<'tbl> => *b"LTSH",
+    gasp::Gasp<'tbl> => *b"gasp",
+    vdmx::Vdmx<'tbl> => *b"VDMX",
+    gdef::Header<'tbl> => *b"GDEF",
+    gpos::Header<'tbl> => *b"GPOS",
+    gsub::Header<'tbl> => *b"GSUB",
+    cff::Cff<'tbl> => *b"CFF ",
+    cff::cff2::Cff2<'tbl> => *b"CFF2",
 );
\ No newline at end of file
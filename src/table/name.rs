@@ -97,6 +97,46 @@ impl<'tbl> Name<'tbl> {
            })
     }
 
+    /// Finds a name by id in any language, preferring Windows English
+    /// names, then other Unicode names, then Mac Roman ones.
+    pub fn get_name(&self, name_id: u16) -> Option<String> {
+        let mut records = try_opt!(self.buffer.get(Self::size()..));
+        let mut best: Option<(u8, NameRecord)> = None;
+
+        for _ in 0..self.count {
+            let rec = try_opt!(records.read_table::<NameRecord>().ok());
+            if rec.name_id != name_id {
+                continue
+            }
+
+            let rank = match (rec.platform_id, rec.encoding_id, rec.language_id) {
+                (3, 1, 0x409) => 0,
+                (3, 1, _) | (3, 10, _) => 1,
+                (0, _, _) => 2,
+                (1, 0, 0) => 3,
+                _ => continue,
+            };
+
+            if best.as_ref().is_none_or(|&(best_rank, _)| rank < best_rank) {
+                best = Some((rank, rec));
+            }
+        }
+
+        let (_, rec) = try_opt!(best);
+        let start = self.offset as usize + rec.offset as usize;
+        let s = try_opt!(self.buffer.get(start..start + rec.length as usize));
+
+        if rec.platform_id == 1 {
+            Some(decode_mac_roman(s))
+        } else {
+            let units: Vec<u16> = s.chunks(2)
+                .filter(|c| c.len() == 2)
+                .map(|c| (c[0] as u16) << 8 | c[1] as u16)
+                .collect();
+            Some(String::from_utf16_lossy(&units))
+        }
+    }
+
     get_name!(get_copyright = 0,
               get_family = 1,
               get_subfamily = 2,
@@ -269,6 +309,9 @@ mod test {
             get_license = "Licensed under the Apache License, Version 2.0",
             get_license_url = "http://www.apache.org/licenses/LICENSE-2.0"
         );
+
+        assert_eq!(tbl.get_name(1).as_ref().map(|s| &s[..]), Some("Open Sans"));
+        assert_eq!(tbl.get_name(256), None);
     }
 
     #[test]
@@ -32,6 +32,27 @@ pub struct Panose {
     pub x_height: u8,
 }
 
+bitflags! {
+    pub struct FsSelection: u16 {
+        const ITALIC           = 1 << 0;
+        const UNDERSCORE       = 1 << 1;
+        const NEGATIVE         = 1 << 2;
+        const OUTLINED         = 1 << 3;
+        const STRIKEOUT        = 1 << 4;
+        const BOLD             = 1 << 5;
+        const REGULAR          = 1 << 6;
+        const USE_TYPO_METRICS = 1 << 7;
+        const WWS              = 1 << 8;
+        const OBLIQUE          = 1 << 9;
+    }
+}
+
+impl<'tbl> Os2<'tbl> {
+    pub fn get_fs_selection(&self) -> FsSelection {
+        FsSelection::from_bits_truncate(self.fs_selection())
+    }
+}
+
 impl_offset_table!(Os2,
     version: u16,
     x_avg_char_width: i16,
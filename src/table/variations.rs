@@ -0,0 +1,221 @@
+use decode::{Error, Result, Primitive, ReadPrimitive};
+use decode::primitives::F2Dot14;
+
+/// Converts a 2.14 fixed point value, as used by normalized coordinates.
+pub fn f2dot14_to_f32(value: F2Dot14) -> f32 {
+    value.0 as f32 / 16384.0
+}
+
+/// Deltas for varying values, indexed by an outer and an inner index.
+/// Used by `CFF2` and the metrics variation tables.
+#[derive(Debug, Copy, Clone)]
+pub struct ItemVariationStore<'tbl> {
+    buffer: &'tbl [u8],
+    regions: &'tbl [u8],
+    axis_count: u16,
+    region_count: u16,
+    data_offsets: &'tbl [u8],
+}
+
+impl<'tbl> ItemVariationStore<'tbl> {
+    pub fn parse(buffer: &'tbl [u8]) -> Result<ItemVariationStore<'tbl>> {
+        let mut header = buffer;
+        let format = header.read::<u16>()?;
+        let region_list_offset = header.read::<u32>()? as usize;
+        let data_count = header.read::<u16>()? as usize;
+
+        verify!(format == 1);
+        required_len!(header, 4 * data_count);
+        let data_offsets = &header[..4 * data_count];
+
+        let mut region_list = match buffer.get(region_list_offset..) {
+            Some(region_list) => region_list,
+            None => return Err(Error::UnexpectedEof),
+        };
+
+        let axis_count = region_list.read::<u16>()?;
+        let region_count = region_list.read::<u16>()?;
+        let regions_size = 6 * axis_count as usize * region_count as usize;
+        required_len!(region_list, regions_size);
+
+        Ok(ItemVariationStore {
+            buffer,
+            regions: &region_list[..regions_size],
+            axis_count,
+            region_count,
+            data_offsets,
+        })
+    }
+
+    pub fn axis_count(&self) -> u16 {
+        self.axis_count
+    }
+
+    pub fn data_count(&self) -> u16 {
+        (self.data_offsets.len() / 4) as u16
+    }
+
+    fn data(&self, outer: u16) -> Result<ItemVariationData<'tbl>> {
+        let start = 4 * outer as usize;
+        let mut offset = match self.data_offsets.get(start..start + 4) {
+            Some(offset) => offset,
+            None => return Err(Error::InvalidData),
+        };
+
+        match self.buffer.get(offset.read::<u32>()? as usize..) {
+            Some(data) => ItemVariationData::parse(data),
+            None => Err(Error::UnexpectedEof),
+        }
+    }
+
+    /// How much a region applies at the given normalized coordinates.
+    /// Axes without a coordinate are at their default.
+    pub fn region_scalar(&self, region: u16, coords: &[f32]) -> f32 {
+        if region >= self.region_count {
+            return 0.0
+        }
+
+        let size = 6 * self.axis_count as usize;
+        let mut axes = &self.regions[region as usize * size..][..size];
+        let mut scalar = 1.0;
+
+        for axis in 0..self.axis_count as usize {
+            let start = f2dot14_to_f32(axes.read::<F2Dot14>().unwrap());
+            let peak = f2dot14_to_f32(axes.read::<F2Dot14>().unwrap());
+            let end = f2dot14_to_f32(axes.read::<F2Dot14>().unwrap());
+            let coord = coords.get(axis).cloned().unwrap_or(0.0);
+
+            // Invalid regions, and those crossing zero, ignore the axis.
+            if peak == 0.0 || coord == peak || start > peak || peak > end ||
+               (start < 0.0 && end > 0.0) {
+                continue
+            }
+
+            if coord <= start || coord >= end {
+                return 0.0
+            } else if coord < peak {
+                scalar *= (coord - start) / (peak - start);
+            } else {
+                scalar *= (end - coord) / (end - peak);
+            }
+        }
+
+        scalar
+    }
+
+    /// The scalars of each region referenced by an item variation data,
+    /// in the order its deltas are stored.
+    pub fn region_scalars(&self, outer: u16, coords: &[f32]) -> Result<Vec<f32>> {
+        let data = self.data(outer)?;
+        Ok(data.region_indices().map(|region| self.region_scalar(region, coords)).collect())
+    }
+
+    /// The interpolated delta for an item at the given coordinates.
+    pub fn get_delta(&self, outer: u16, inner: u16, coords: &[f32]) -> Result<f32> {
+        let data = self.data(outer)?;
+        let deltas = data.deltas(inner)?;
+
+        Ok(data.region_indices()
+            .zip(deltas)
+            .map(|(region, delta)| delta as f32 * self.region_scalar(region, coords))
+            .sum())
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ItemVariationData<'tbl> {
+    item_count: u16,
+    word_delta_count: u16,
+    region_indices: &'tbl [u8],
+    delta_sets: &'tbl [u8],
+}
+
+impl<'tbl> ItemVariationData<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<ItemVariationData<'tbl>> {
+        let item_count = buffer.read::<u16>()?;
+        let word_delta_count = buffer.read::<u16>()?;
+        let region_index_count = buffer.read::<u16>()? as usize;
+
+        required_len!(buffer, 2 * region_index_count);
+        let (region_indices, delta_sets) = buffer.split_at(2 * region_index_count);
+
+        let data = ItemVariationData { item_count, word_delta_count, region_indices, delta_sets };
+        required_len!(delta_sets, data.row_size() * item_count as usize);
+        Ok(data)
+    }
+
+    fn region_indices(&self) -> RegionIndices<'tbl> {
+        RegionIndices(self.region_indices)
+    }
+
+    fn row_size(&self) -> usize {
+        // The high bit of the word count selects 32 and 16 bit words.
+        let words = (self.word_delta_count & 0x7FFF) as usize;
+        let regions = self.region_indices.len() / 2;
+        if self.word_delta_count & 0x8000 != 0 {
+            4 * words + 2 * regions.saturating_sub(words)
+        } else {
+            2 * words + regions.saturating_sub(words)
+        }
+    }
+
+    fn deltas(&self, inner: u16) -> Result<Vec<i32>> {
+        verify!(inner < self.item_count);
+
+        let long_words = self.word_delta_count & 0x8000 != 0;
+        let words = (self.word_delta_count & 0x7FFF) as usize;
+        let mut row = &self.delta_sets[inner as usize * self.row_size()..];
+
+        (0..self.region_indices.len() / 2)
+            .map(|i| match (i < words, long_words) {
+                (true, true) => row.read::<i32>(),
+                (true, false) | (false, true) => row.read::<i16>().map(|d| d as i32),
+                (false, false) => row.read::<i8>().map(|d| d as i32),
+            })
+            .collect()
+    }
+}
+
+struct RegionIndices<'tbl>(&'tbl [u8]);
+
+impl<'tbl> Iterator for RegionIndices<'tbl> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        self.0.read::<u16>().ok()
+    }
+}
+
+#[test]
+fn item_variation_store() {
+    let buf: &[u8] = &[
+        0x00, 0x01,                 // format
+        0x00, 0x00, 0x00, 0x0C,     // region list offset
+        0x00, 0x01,                 // data count
+        0x00, 0x00, 0x00, 0x1E,     // data offset
+        // region list: 1 axis, 2 regions
+        0x00, 0x01, 0x00, 0x02,
+        0x00, 0x00, 0x40, 0x00, 0x40, 0x00, // 0 → 1 → 1
+        0xC0, 0x00, 0xC0, 0x00, 0x00, 0x00, // -1 → -1 → 0
+        0x00, 0x00,
+        // item variation data: 2 items, 1 word delta, 2 regions
+        0x00, 0x02, 0x00, 0x01, 0x00, 0x02,
+        0x00, 0x00, 0x00, 0x01,
+        0x00, 0x64, 0xF6,           // 100, -10
+        0xFF, 0x38, 0x14,           // -200, 20
+    ];
+
+    let store = ItemVariationStore::parse(buf).expect("unable to parse item variation store");
+    assert_eq!(store.axis_count(), 1);
+    assert_eq!(store.data_count(), 1);
+
+    assert_eq!(store.region_scalar(0, &[0.5]), 0.5);
+    assert_eq!(store.region_scalar(0, &[-0.5]), 0.0);
+    assert_eq!(store.region_scalar(1, &[-0.25]), 0.25);
+    assert_eq!(store.region_scalars(0, &[1.0]).ok(), Some(vec![1.0, 0.0]));
+
+    assert_eq!(store.get_delta(0, 0, &[]).ok(), Some(0.0));
+    assert_eq!(store.get_delta(0, 0, &[0.5]).ok(), Some(50.0));
+    assert_eq!(store.get_delta(0, 1, &[-1.0]).ok(), Some(20.0));
+    assert!(store.get_delta(0, 2, &[0.0]).is_err());
+}
@@ -0,0 +1,181 @@
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+
+/// Vertical device metrics.  Holds the maximum and minimum hinted
+/// y values of all glyphs, for ranges of pixel aspect ratios.
+#[derive(Debug)]
+pub struct Vdmx<'tbl> {
+    buffer: &'tbl [u8],
+    pub version: u16,
+    pub num_recs: u16,
+    pub num_ratios: u16,
+}
+
+impl<'tbl> Table<'tbl> for Vdmx<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<Vdmx<'tbl>> {
+        required_len!(buffer, 6);
+
+        let head = buffer;
+        let version = buffer.read::<u16>()?;
+        let num_recs = buffer.read::<u16>()?;
+        let num_ratios = buffer.read::<u16>()?;
+
+        verify!(version <= 1);
+        required_len!(buffer, num_ratios as usize * (RatioRange::size() + 2));
+
+        Ok(Vdmx { buffer: head, version, num_recs, num_ratios })
+    }
+}
+
+impl<'tbl> Vdmx<'tbl> {
+    pub fn ratios(&self) -> RatioIter<'tbl> {
+        RatioIter {
+            buffer: &self.buffer[6..],
+            current: 0,
+            num_ratios: self.num_ratios,
+        }
+    }
+
+    /// Finds the group of records for the first ratio range containing
+    /// the aspect ratio `x_ratio:y_ratio`.
+    pub fn get_group(&self, x_ratio: u16, y_ratio: u16) -> Option<VdmxGroup<'tbl>> {
+        let index = try_opt!(self.ratios().position(|r| r.contains(x_ratio, y_ratio)));
+
+        let offsets = 6 + self.num_ratios as usize * RatioRange::size();
+        let mut buf = &self.buffer[offsets + 2 * index..];
+        let offset = try_opt!(buf.read::<u16>().ok()) as usize;
+
+        VdmxGroup::parse(try_opt!(self.buffer.get(offset..))).ok()
+    }
+
+    /// The hinted extents at the given ppem for square pixels.
+    pub fn get_extents(&self, ppem: u16) -> Option<VdmxRecord> {
+        self.get_group(1, 1).and_then(|group| group.get_record(ppem))
+    }
+}
+
+pub struct RatioIter<'a> {
+    buffer: &'a [u8],
+    current: u16,
+    num_ratios: u16,
+}
+
+impl<'a> Iterator for RatioIter<'a> {
+    type Item = RatioRange;
+
+    fn next(&mut self) -> Option<RatioRange> {
+        if self.current >= self.num_ratios {
+            return None
+        }
+
+        // The length of the ratios is checked when the table is parsed.
+        self.current += 1;
+        self.buffer.read_table::<RatioRange>().ok()
+    }
+}
+
+#[derive(Debug, Table, PartialEq)]
+pub struct RatioRange {
+    pub char_set: u8,
+    pub x_ratio: u8,
+    pub y_start_ratio: u8,
+    pub y_end_ratio: u8,
+}
+
+impl RatioRange {
+    /// A ratio of `0:0-0` matches every aspect ratio.
+    pub fn contains(&self, x_ratio: u16, y_ratio: u16) -> bool {
+        if self.x_ratio == 0 && self.y_start_ratio == 0 && self.y_end_ratio == 0 {
+            return true
+        }
+
+        // Compare y_ratio / x_ratio against the range of y / x in the record.
+        let x = self.x_ratio as u32;
+        let lhs = y_ratio as u32 * x;
+        self.y_start_ratio as u32 * x_ratio as u32 <= lhs
+            && lhs <= self.y_end_ratio as u32 * x_ratio as u32
+    }
+}
+
+#[derive(Debug)]
+pub struct VdmxGroup<'tbl> {
+    records: &'tbl [u8],
+    pub recs: u16,
+    pub start_size: u8,
+    pub end_size: u8,
+}
+
+impl<'tbl> Table<'tbl> for VdmxGroup<'tbl> {
+    fn parse(mut buffer: &'tbl [u8]) -> Result<VdmxGroup<'tbl>> {
+        required_len!(buffer, 4);
+
+        let recs = buffer.read::<u16>()?;
+        let start_size = buffer.read::<u8>()?;
+        let end_size = buffer.read::<u8>()?;
+
+        let size = recs as usize * VdmxRecord::size();
+        required_len!(buffer, size);
+
+        Ok(VdmxGroup { records: &buffer[..size], recs, start_size, end_size })
+    }
+}
+
+impl<'tbl> VdmxGroup<'tbl> {
+    pub fn get_record(&self, ppem: u16) -> Option<VdmxRecord> {
+        if ppem < self.start_size as u16 || ppem > self.end_size as u16 {
+            return None
+        }
+
+        // Records are sorted by y_pel_height.
+        let size = VdmxRecord::size();
+        let (mut lo, mut hi) = (0, self.recs as usize);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let mut buf = &self.records[mid * size..];
+            let rec = try_opt!(buf.read_table::<VdmxRecord>().ok());
+
+            if rec.y_pel_height < ppem {
+                lo = mid + 1;
+            } else if rec.y_pel_height > ppem {
+                hi = mid;
+            } else {
+                return Some(rec)
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Table, PartialEq)]
+pub struct VdmxRecord {
+    pub y_pel_height: u16,
+    pub y_max: i16,
+    pub y_min: i16,
+}
+
+#[test]
+fn vertical_device_metrics() {
+    let buf: &[u8] = &[
+        0x00, 0x01, 0x00, 0x01, // version 1, 1 group
+        0x00, 0x02,             // 2 ratios
+        0x01, 0x02, 0x01, 0x01, // 2:1 only
+        0x01, 0x00, 0x00, 0x00, // any ratio
+        0x00, 0x12, 0x00, 0x12, // both ratios use the group at 18
+
+        // group
+        0x00, 0x02, 0x0A, 0x0C, // 2 records, 10 to 12 ppem
+        0x00, 0x0A, 0x00, 0x09, 0xFF, 0xFE, // 10: 9, -2
+        0x00, 0x0C, 0x00, 0x0B, 0xFF, 0xFD, // 12: 11, -3
+    ];
+
+    let vdmx = Vdmx::parse(buf).expect("unable to parse VDMX table");
+
+    assert_eq!(vdmx.ratios().count(), 2);
+    assert!(!vdmx.ratios().next().unwrap().contains(1, 1));
+    assert!(vdmx.ratios().next().unwrap().contains(2, 1));
+
+    assert_eq!(vdmx.get_extents(10), Some(VdmxRecord { y_pel_height: 10, y_max: 9, y_min: -2 }));
+    assert_eq!(vdmx.get_extents(12), Some(VdmxRecord { y_pel_height: 12, y_max: 11, y_min: -3 }));
+    assert_eq!(vdmx.get_extents(11), None);
+    assert_eq!(vdmx.get_extents(13), None);
+}
@@ -0,0 +1,24 @@
+use decode::primitives::{Tag, Fixed, FWord, UFWord, Ignored};
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+
+/// vertical fonts header table
+#[derive(Debug, Table)]
+pub struct Vhea {
+    pub version: Fixed,
+    pub ascent: FWord,
+    pub descent: FWord,
+    pub line_gap: FWord,
+    pub advance_height_max: UFWord,
+    pub min_top_side_bearing: FWord,
+    pub min_bottom_side_bearing: FWord,
+    pub y_max_extent: FWord,
+    pub caret_slope_rise: i16,
+    pub caret_slope_run: i16,
+    pub caret_offset: i16,
+    _reserved1: Ignored<u16>,
+    _reserved2: Ignored<u16>,
+    _reserved3: Ignored<u16>,
+    _reserved4: Ignored<u16>,
+    pub metric_data_format: i16,
+    pub number_of_v_metrics: u16,
+}
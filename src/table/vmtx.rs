@@ -0,0 +1,111 @@
+use decode::{Error, Result, SizedTable, Table, TableInherited, Primitive, ReadPrimitive, ReadTable};
+use decode::primitives::Ignored;
+
+#[derive(Debug)]
+pub struct Vmtx<'tbl> {
+    v_metrics: &'tbl [u8],
+    top_side_bearing: &'tbl [u8],
+    default_advance: u16,
+}
+
+impl<'tbl> Vmtx<'tbl> {
+    /// The number of glyphs are found in the `maxp` table,
+    /// and the number of v_glyphs are found in the `vhea` table.
+    pub fn parse(buffer: &'tbl [u8], num_glyphs: u16, num_v_glyphs: u16) -> Result<Vmtx<'tbl>> {
+        verify!(num_v_glyphs >= 1 && num_v_glyphs <= num_glyphs);
+
+        let vm_size = VerticalMetricRecord::size() * num_v_glyphs as usize;
+        let tsb_size = 2 * (num_glyphs - num_v_glyphs) as usize;
+
+        required_len!(buffer, vm_size + tsb_size);
+
+        let (v_metrics, buffer) = buffer.split_at(vm_size);
+        let (top_side_bearing, _) = buffer.split_at(tsb_size);
+
+        let (_, mut last_record) = v_metrics
+            .split_at(vm_size - VerticalMetricRecord::size());
+        let default_advance = last_record.read_table::<VerticalMetricRecord>()?
+            .advance_height;
+
+        Ok(Vmtx { v_metrics, top_side_bearing, default_advance })
+    }
+
+    pub fn get_record(&self, glyph_id: u16) -> Option<VerticalMetricRecord> {
+        let glyph_id = glyph_id as usize;
+
+        if 4 * glyph_id < self.v_metrics.len() {
+            let (_, mut record) = self.v_metrics.split_at(4 * glyph_id);
+            return record.read_table::<VerticalMetricRecord>().ok();
+        }
+
+        let diff = glyph_id - self.v_metrics.len() / 4;
+        if 2 * diff < self.top_side_bearing.len() {
+            let (_, mut record) = self.top_side_bearing.split_at(2 * diff);
+            record.read::<i16>().ok().map(|tsb|
+                VerticalMetricRecord {
+                    advance_height: self.default_advance,
+                    tsb,
+                })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_advance(&self, glyph_id: u16) -> Option<u16> {
+        self.get_record(glyph_id).map(|r| r.advance_height)
+    }
+
+    pub fn get_tsb(&self, glyph_id: u16) -> Option<i16> {
+        self.get_record(glyph_id).map(|r| r.tsb)
+    }
+}
+
+#[derive(Debug, Table)]
+pub struct VerticalMetricRecord {
+    pub advance_height: u16,
+    pub tsb: i16,
+}
+
+/// Vertical metrics for fonts lacking `vhea` and `vmtx`.  Every glyph
+/// is given the same advance, spanning from the ascender to the descender,
+/// with the vertical origin placed on the ascender.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SyntheticVmtx {
+    pub ascender: i16,
+    pub descender: i16,
+}
+
+impl SyntheticVmtx {
+    pub fn get_advance(&self) -> u16 {
+        (self.ascender as i32 - self.descender as i32) as u16
+    }
+
+    pub fn get_vert_origin_y(&self) -> i16 {
+        self.ascender
+    }
+}
+
+#[test]
+fn vertical_metrics() {
+    let buf: &[u8] = &[
+        0x03, 0xE8, 0x00, 0x32, // gid 0: advance 1000, tsb 50
+        0x04, 0x00, 0xFF, 0xEC, // gid 1: advance 1024, tsb -20
+        0x00, 0x64,             // gid 2: tsb 100
+        0x00, 0x0A,             // gid 3: tsb 10
+    ];
+
+    let vmtx = Vmtx::parse(buf, 4, 2).expect("unable to parse vmtx table");
+
+    assert_eq!(vmtx.get_advance(0), Some(1000));
+    assert_eq!(vmtx.get_tsb(0), Some(50));
+    assert_eq!(vmtx.get_advance(1), Some(1024));
+    assert_eq!(vmtx.get_tsb(1), Some(-20));
+    assert_eq!(vmtx.get_advance(2), Some(1024));
+    assert_eq!(vmtx.get_tsb(2), Some(100));
+    assert_eq!(vmtx.get_advance(3), Some(1024));
+    assert_eq!(vmtx.get_tsb(3), Some(10));
+    assert_eq!(vmtx.get_record(4).map(|r| r.tsb), None);
+
+    assert!(Vmtx::parse(buf, 4, 0).is_err());
+    assert!(Vmtx::parse(buf, 5, 2).is_err());
+}
@@ -0,0 +1,71 @@
+use decode::{Error, Result, SizedTable, Table, Primitive, ReadPrimitive, ReadTable};
+use decode::primitives::Ignored;
+
+/// Vertical origin table, found in CFF flavoured OpenType fonts.
+#[derive(Debug, Table)]
+pub struct Vorg<'tbl> {
+    buffer: &'tbl [u8],
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub default_vert_origin_y: i16,
+    pub num_vert_origin_y_metrics: u16,
+}
+
+impl<'tbl> Vorg<'tbl> {
+    /// Returns the y coordinate of the vertical origin for the given glyph.
+    /// Glyphs without an explicit record use `default_vert_origin_y`.
+    pub fn get_vert_origin_y(&self, glyph_id: u16) -> i16 {
+        let size = VertOriginYMetrics::size();
+        let records = &self.buffer[Self::size()..];
+        let count = min!(self.num_vert_origin_y_metrics as usize,
+                         records.len() / size);
+
+        // Records are sorted by glyph id.
+        let (mut lo, mut hi) = (0, count);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let mut buf = &records[mid * size..];
+            let rec = match buf.read_table::<VertOriginYMetrics>() {
+                Ok(rec) => rec,
+                Err(_) => break,
+            };
+
+            if rec.glyph_index < glyph_id {
+                lo = mid + 1;
+            } else if rec.glyph_index > glyph_id {
+                hi = mid;
+            } else {
+                return rec.vert_origin_y;
+            }
+        }
+
+        self.default_vert_origin_y
+    }
+}
+
+#[derive(Debug, Table)]
+pub struct VertOriginYMetrics {
+    pub glyph_index: u16,
+    pub vert_origin_y: i16,
+}
+
+#[test]
+fn vert_origin_y() {
+    let buf: &[u8] = &[
+        0x00, 0x01, 0x00, 0x00, // version 1.0
+        0x03, 0x70,             // default: 880
+        0x00, 0x03,             // 3 records
+        0x00, 0x05, 0x03, 0x84, // gid 5: 900
+        0x00, 0x10, 0x02, 0xEE, // gid 16: 750
+        0x01, 0x00, 0xFF, 0xF6, // gid 256: -10
+    ];
+
+    let vorg = Vorg::parse(buf).expect("unable to parse VORG table");
+
+    assert_eq!(vorg.num_vert_origin_y_metrics, 3);
+    assert_eq!(vorg.get_vert_origin_y(0), 880);
+    assert_eq!(vorg.get_vert_origin_y(5), 900);
+    assert_eq!(vorg.get_vert_origin_y(16), 750);
+    assert_eq!(vorg.get_vert_origin_y(17), 880);
+    assert_eq!(vorg.get_vert_origin_y(256), -10);
+}